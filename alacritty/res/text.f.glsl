@@ -10,6 +10,7 @@ layout(location = 0, index = 1) out vec4 alphaMask;
 uniform sampler2D mask;
 
 #define COLORED 2
+#define GRAYSCALE 4
 
 void main() {
     if (backgroundPass != 0) {
@@ -33,7 +34,13 @@ void main() {
     } else {
         // Regular text glyphs.
         vec3 textColor = texture(mask, TexCoords).rgb;
-        alphaMask = vec4(textColor, textColor.r);
+        if ((int(fg.a) & GRAYSCALE) != 0) {
+            // Collapse subpixel coverage into a single alpha value.
+            float alpha = (textColor.r + textColor.g + textColor.b) / 3.0;
+            alphaMask = vec4(alpha);
+        } else {
+            alphaMask = vec4(textColor, textColor.r);
+        }
         color = vec4(fg.rgb, 1.0);
     }
 }
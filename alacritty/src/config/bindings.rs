@@ -13,6 +13,8 @@ use alacritty_terminal::config::Program;
 use alacritty_terminal::term::TermMode;
 use alacritty_terminal::vi_mode::ViMotion;
 
+use crate::keyboard;
+
 /// Describes a state and action to take in that state.
 ///
 /// This is the shared component of `MouseBinding` and `KeyBinding`.
@@ -142,9 +144,30 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the closest shell prompt above the top of the screen.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the closest shell prompt below the top of the screen.
+    ScrollToNextPrompt,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
+    /// Write the scrollback history and screen content to a file.
+    DumpScrollback,
+
+    /// Write the scrollback history to a file and open it with the given program.
+    OpenScrollbackIn(Program),
+
+    /// Copy the output of the last command to the clipboard.
+    CopyLastCommandOutput,
+
+    /// Write the output of the last command to the standard input of the given program.
+    PipeLastCommandOutput(Program),
+
+    /// Show the contents and attributes of the cell under the vi mode cursor or the mouse.
+    InspectCell,
+
     /// Hide the Alacritty window.
     Hide,
 
@@ -477,6 +500,24 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         }
     }
 
+    // Escapes are replaced by the input handler's encoding once win32-input-mode, the kitty
+    // keyboard protocol or modifyOtherKeys covers their key.
+    for binding in &mut bindings {
+        if let (Action::Esc(_), Key::Keycode(key)) = (&binding.action, binding.trigger) {
+            binding.notmode.insert(TermMode::WIN32_INPUT);
+
+            if keyboard::is_functional_key(key) {
+                binding.notmode.insert(TermMode::KITTY_KEYBOARD_PROTOCOL);
+            }
+
+            for &mode in &[TermMode::MODIFY_OTHER_KEYS, TermMode::MODIFY_ALL_KEYS] {
+                if keyboard::modify_other_keys_sequence(key, binding.mods, mode).is_some() {
+                    binding.notmode.insert(mode);
+                }
+            }
+        }
+    }
+
     bindings.extend(platform_key_bindings());
 
     bindings
@@ -12,7 +12,9 @@ use alacritty_terminal::config::{Config as TermConfig, LOG_TARGET_CONFIG};
 pub mod debug;
 pub mod font;
 pub mod monitor;
+pub mod scrollback_dump;
 pub mod serde_utils;
+pub mod terminal;
 pub mod ui_config;
 pub mod window;
 
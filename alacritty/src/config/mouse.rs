@@ -19,6 +19,8 @@ pub struct Mouse {
     pub hide_when_typing: bool,
     #[serde(deserialize_with = "failure_default")]
     pub url: Url,
+    #[serde(deserialize_with = "failure_default")]
+    pub paste_at_click: bool,
 }
 
 #[serde(default)]
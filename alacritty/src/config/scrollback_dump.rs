@@ -0,0 +1,72 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use alacritty_terminal::config::failure_default;
+
+/// Placeholder for the scrollback file in the arguments of `OpenScrollbackIn`.
+const FILE_PLACEHOLDER: &str = "{file}";
+
+/// Options for the `DumpScrollback` and `OpenScrollbackIn` actions.
+#[serde(default)]
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScrollbackDump {
+    /// Directory the dumps are written to.
+    #[serde(deserialize_with = "failure_default")]
+    directory: Option<PathBuf>,
+
+    /// Preserve colors and text attributes using SGR escapes.
+    #[serde(deserialize_with = "failure_default")]
+    pub escapes: bool,
+}
+
+impl ScrollbackDump {
+    /// Directory the dumps are written to, defaulting to the temporary directory.
+    pub fn directory(&self) -> PathBuf {
+        match &self.directory {
+            Some(directory) => match (directory.strip_prefix("~/"), dirs::home_dir()) {
+                (Ok(stripped), Some(home_dir)) => home_dir.join(stripped),
+                _ => directory.clone(),
+            },
+            None => env::temp_dir(),
+        }
+    }
+}
+
+/// Arguments for opening the scrollback file at `path`.
+///
+/// Every [`FILE_PLACEHOLDER`] is replaced by the path. Without any placeholder, the path is
+/// appended as the last argument instead.
+pub fn program_args(args: &[String], path: &Path) -> Vec<String> {
+    let path = path.to_string_lossy();
+
+    let mut program_args: Vec<String> =
+        args.iter().map(|arg| arg.replace(FILE_PLACEHOLDER, &path)).collect();
+    if !args.iter().any(|arg| arg.contains(FILE_PLACEHOLDER)) {
+        program_args.push(path.into_owned());
+    }
+
+    program_args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_args_replace_placeholder() {
+        let args = vec![String::from("+{file}"), String::from("-R"), String::from("{file}")];
+        let program_args = program_args(&args, Path::new("/tmp/dump.txt"));
+        assert_eq!(program_args, vec!["+/tmp/dump.txt", "-R", "/tmp/dump.txt"]);
+    }
+
+    #[test]
+    fn program_args_append_path() {
+        let program_args = program_args(&[String::from("-R")], Path::new("/tmp/dump.txt"));
+        assert_eq!(program_args, vec!["-R", "/tmp/dump.txt"]);
+
+        let program_args = program_args(&[], Path::new("/tmp/dump.txt"));
+        assert_eq!(program_args, vec!["/tmp/dump.txt"]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::cmp::max;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use alacritty_terminal::config::{failure_default, option_explicit_none, Program};
+
+/// Minimum blink interval of blinking text in milliseconds.
+const MIN_TEXT_BLINK_INTERVAL: u64 = 20;
+
+/// Terminal behavior options.
+#[serde(default)]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct TerminalConfig {
+    /// Handling of the `^S`/`^Q` software flow control characters.
+    #[serde(deserialize_with = "failure_default")]
+    pub flow_control: FlowControl,
+
+    /// Display bidirectional text in visual order.
+    #[serde(deserialize_with = "failure_default")]
+    pub bidi: bool,
+
+    /// Blink interval of slowly blinking text, `None` disables text blinking.
+    #[serde(deserialize_with = "option_explicit_none")]
+    blink_interval: Option<TextBlinkInterval>,
+
+    /// Quoting of file paths dropped onto the window.
+    #[serde(deserialize_with = "failure_default")]
+    pub drop_quoting: DropQuoting,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            flow_control: Default::default(),
+            bidi: Default::default(),
+            blink_interval: Some(Default::default()),
+            drop_quoting: Default::default(),
+        }
+    }
+}
+
+impl TerminalConfig {
+    /// Blink interval of slowly blinking text.
+    ///
+    /// Rapidly blinking text toggles its visibility twice as often.
+    #[inline]
+    pub fn blink_interval(&self) -> Option<Duration> {
+        self.blink_interval
+            .map(|interval| Duration::from_millis(max(interval.0, MIN_TEXT_BLINK_INTERVAL)))
+    }
+}
+
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum FlowControl {
+    /// Send `^S` and `^Q` to the child untouched.
+    #[serde(rename = "passthrough")]
+    Passthrough,
+    /// Stop reading child output on `^S` and resume it on `^Q`.
+    #[serde(rename = "legacy")]
+    Legacy,
+}
+
+impl Default for FlowControl {
+    fn default() -> FlowControl {
+        FlowControl::Passthrough
+    }
+}
+
+/// Quoting of file paths dropped onto the window.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+pub enum DropQuoting {
+    /// Pick the quoting based on the shell.
+    #[serde(rename = "auto")]
+    Auto,
+    /// Single quotes, escaping single quotes as `'\''`.
+    #[serde(rename = "posix")]
+    Posix,
+    /// Single quotes, escaping single quotes by doubling them.
+    #[serde(rename = "powershell")]
+    PowerShell,
+    /// Double quotes, which can't be part of Windows file names.
+    #[serde(rename = "cmd")]
+    Cmd,
+}
+
+impl Default for DropQuoting {
+    fn default() -> DropQuoting {
+        DropQuoting::Auto
+    }
+}
+
+impl DropQuoting {
+    /// Quote a path for the shell, resolving automatic quoting based on the shell program.
+    pub fn quote(self, path: &str, shell: Option<&Program>) -> String {
+        match self.resolve(shell) {
+            DropQuoting::PowerShell => {
+                // PowerShell also treats typographic single quotes as quotes.
+                let mut quoted = String::from("'");
+                for c in path.chars() {
+                    if let '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' = c {
+                        quoted.push(c);
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('\'');
+                quoted
+            },
+            DropQuoting::Cmd => format!("\"{}\"", path),
+            _ => format!("'{}'", path.replace('\'', "'\\''")),
+        }
+    }
+
+    /// Resolve automatic quoting.
+    ///
+    /// PowerShell quoting is used for `powershell` and `pwsh`, which is also the default on Windows.
+    /// Cmd quoting is used for `cmd`, all other shells use POSIX quoting.
+    fn resolve(self, shell: Option<&Program>) -> DropQuoting {
+        if self != DropQuoting::Auto {
+            return self;
+        }
+
+        let program = match shell {
+            Some(shell) => shell.program(),
+            None if cfg!(windows) => return DropQuoting::PowerShell,
+            None => return DropQuoting::Posix,
+        };
+
+        // Windows paths are split manually, since they are not recognized on other platforms.
+        let name = program.rsplit(&['/', '\\'][..]).next().unwrap_or(program);
+        match name.to_lowercase().trim_end_matches(".exe") {
+            "powershell" | "pwsh" => DropQuoting::PowerShell,
+            "cmd" => DropQuoting::Cmd,
+            _ => DropQuoting::Posix,
+        }
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+struct TextBlinkInterval(u64);
+
+impl Default for TextBlinkInterval {
+    fn default() -> Self {
+        TextBlinkInterval(500)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_quoting() {
+        let quote = |path| DropQuoting::Posix.quote(path, None);
+
+        assert_eq!(quote("/tmp/file name"), "'/tmp/file name'");
+        assert_eq!(quote("/tmp/it's"), "'/tmp/it'\\''s'");
+        assert_eq!(quote("/tmp/a\nb \"c\" $HOME"), "'/tmp/a\nb \"c\" $HOME'");
+    }
+
+    #[test]
+    fn powershell_quoting() {
+        let quote = |path| DropQuoting::PowerShell.quote(path, None);
+
+        assert_eq!(quote("C:\\Program Files\\it's"), "'C:\\Program Files\\it''s'");
+        assert_eq!(quote("C:\\a\u{2019}b\nc"), "'C:\\a\u{2019}\u{2019}b\nc'");
+    }
+
+    #[test]
+    fn cmd_quoting() {
+        let quote = |path| DropQuoting::Cmd.quote(path, None);
+
+        assert_eq!(quote("C:\\Program Files\\it's"), "\"C:\\Program Files\\it's\"");
+        assert_eq!(quote("C:\\a&b"), "\"C:\\a&b\"");
+    }
+
+    #[test]
+    fn automatic_quoting() {
+        let shell = |program: &str| Program::Just(program.into());
+
+        let pwsh = shell("C:\\Program Files\\PowerShell\\7\\pwsh.exe");
+        assert_eq!(DropQuoting::Auto.resolve(Some(&pwsh)), DropQuoting::PowerShell);
+        assert_eq!(DropQuoting::Auto.resolve(Some(&shell("cmd.exe"))), DropQuoting::Cmd);
+        assert_eq!(DropQuoting::Auto.resolve(Some(&shell("/bin/zsh"))), DropQuoting::Posix);
+        assert_eq!(DropQuoting::Posix.resolve(Some(&pwsh)), DropQuoting::Posix);
+    }
+}
@@ -9,6 +9,8 @@ use crate::config::bindings::{self, Binding, KeyBinding, MouseBinding};
 use crate::config::debug::Debug;
 use crate::config::font::Font;
 use crate::config::mouse::Mouse;
+use crate::config::scrollback_dump::ScrollbackDump;
+use crate::config::terminal::TerminalConfig;
 use crate::config::window::WindowConfig;
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -24,6 +26,14 @@ pub struct UIConfig {
     #[serde(default, deserialize_with = "failure_default")]
     pub mouse: Mouse,
 
+    /// Terminal behavior.
+    #[serde(default, deserialize_with = "failure_default")]
+    pub terminal: TerminalConfig,
+
+    /// Scrollback dump options.
+    #[serde(default, deserialize_with = "failure_default")]
+    pub scrollback_dump: ScrollbackDump,
+
     /// Keybindings.
     #[serde(default = "default_key_bindings", deserialize_with = "deserialize_key_bindings")]
     pub key_bindings: Vec<KeyBinding>,
@@ -44,6 +54,10 @@ pub struct UIConfig {
     #[serde(default, deserialize_with = "failure_default")]
     live_config_reload: DefaultTrueBool,
 
+    /// Listen for IPC messages on a Unix socket.
+    #[serde(default, deserialize_with = "failure_default")]
+    ipc_socket: bool,
+
     /// Background opacity from 0.0 to 1.0.
     #[serde(default, deserialize_with = "failure_default")]
     background_opacity: Percentage,
@@ -63,12 +77,15 @@ impl Default for UIConfig {
             font: Default::default(),
             window: Default::default(),
             mouse: Default::default(),
+            terminal: Default::default(),
+            scrollback_dump: Default::default(),
             key_bindings: default_key_bindings(),
             mouse_bindings: default_mouse_bindings(),
             debug: Default::default(),
             alt_send_esc: Default::default(),
             background_opacity: Default::default(),
             live_config_reload: Default::default(),
+            ipc_socket: Default::default(),
             dynamic_title: Default::default(),
             config_paths: Default::default(),
         }
@@ -81,6 +98,11 @@ impl UIConfig {
         self.background_opacity.as_f32()
     }
 
+    #[inline]
+    pub fn set_background_opacity(&mut self, opacity: f32) {
+        self.background_opacity = Percentage::new(opacity);
+    }
+
     #[inline]
     pub fn dynamic_title(&self) -> bool {
         self.dynamic_title.unwrap_or_else(|| self.window.dynamic_title())
@@ -97,6 +119,12 @@ impl UIConfig {
         self.live_config_reload.0
     }
 
+    /// Listen for IPC messages on a Unix socket.
+    #[inline]
+    pub fn ipc_socket(&self) -> bool {
+        self.ipc_socket
+    }
+
     /// Send escape sequences using the alt key.
     #[inline]
     pub fn alt_send_esc(&self) -> bool {
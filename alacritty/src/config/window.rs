@@ -1,3 +1,4 @@
+use std::cmp::max;
 use std::os::raw::c_ulong;
 
 use glutin::window::Fullscreen;
@@ -7,6 +8,7 @@ use serde_yaml::Value;
 
 use alacritty_terminal::config::{failure_default, option_explicit_none, LOG_TARGET_CONFIG};
 use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::{MIN_COLS, MIN_SCREEN_LINES};
 
 use crate::config::ui_config::{DefaultTrueBool, Delta};
 
@@ -44,10 +46,26 @@ pub struct WindowConfig {
     #[serde(deserialize_with = "option_explicit_none")]
     pub gtk_theme_variant: Option<String>,
 
+    /// Show the shell's working directory as the title bar icon on macOS.
+    #[serde(deserialize_with = "failure_default")]
+    pub macos_represented_dir: bool,
+
     /// Spread out additional padding evenly.
     #[serde(deserialize_with = "failure_default")]
     pub dynamic_padding: bool,
 
+    /// Resize the window in cell increments.
+    #[serde(deserialize_with = "failure_default")]
+    pub resize_increments: bool,
+
+    /// Minimum number of columns.
+    #[serde(deserialize_with = "failure_default")]
+    minimum_columns: usize,
+
+    /// Minimum number of lines.
+    #[serde(deserialize_with = "failure_default")]
+    minimum_lines: usize,
+
     /// Pixel padding.
     #[serde(deserialize_with = "failure_default")]
     padding: Delta<u8>,
@@ -88,6 +106,18 @@ impl WindowConfig {
         }
     }
 
+    /// Minimum number of columns in the grid.
+    #[inline]
+    pub fn minimum_columns(&self) -> Column {
+        Column(max(self.minimum_columns, MIN_COLS))
+    }
+
+    /// Minimum number of lines in the grid.
+    #[inline]
+    pub fn minimum_lines(&self) -> Line {
+        Line(max(self.minimum_lines, MIN_SCREEN_LINES))
+    }
+
     #[inline]
     pub fn padding(&self, dpr: f64) -> (f32, f32) {
         let padding_x = (f32::from(self.padding.x) * dpr as f32).floor();
@@ -118,10 +148,14 @@ impl Default for WindowConfig {
             padding: Default::default(),
             decorations: Default::default(),
             dynamic_padding: Default::default(),
+            resize_increments: Default::default(),
+            minimum_columns: Default::default(),
+            minimum_lines: Default::default(),
             startup_mode: Default::default(),
             class: Default::default(),
             embed: Default::default(),
             gtk_theme_variant: Default::default(),
+            macos_represented_dir: Default::default(),
             title: default_title(),
             dynamic_title: Default::default(),
         }
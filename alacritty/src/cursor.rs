@@ -26,11 +26,11 @@ pub fn get_cursor_glyph(
     }
 
     match cursor {
-        CursorShape::HollowBlock => get_box_cursor_glyph(height, width, line_width),
         CursorShape::Underline => get_underline_cursor_glyph(width, line_width),
         CursorShape::Beam => get_beam_cursor_glyph(height, line_width),
         CursorShape::Block => get_block_cursor_glyph(height, width),
-        CursorShape::Hidden => RasterizedGlyph::default(),
+        // The hollow cursor is drawn using rects instead of a glyph.
+        CursorShape::HollowBlock | CursorShape::Hidden => RasterizedGlyph::default(),
     }
 }
 
@@ -66,35 +66,6 @@ pub fn get_beam_cursor_glyph(height: usize, line_width: usize) -> RasterizedGlyp
     }
 }
 
-/// Returns a custom box cursor character.
-pub fn get_box_cursor_glyph(height: usize, width: usize, line_width: usize) -> RasterizedGlyph {
-    // Create a new box outline rectangle.
-    let mut buf = Vec::with_capacity(width * height * 3);
-    for y in 0..height {
-        for x in 0..width {
-            if y < line_width
-                || y >= height - line_width
-                || x < line_width
-                || x >= width - line_width
-            {
-                buf.append(&mut vec![255u8; 3]);
-            } else {
-                buf.append(&mut vec![0u8; 3]);
-            }
-        }
-    }
-
-    // Create a custom glyph with the rectangle data attached to it.
-    RasterizedGlyph {
-        c: ' ',
-        top: height as i32,
-        left: 0,
-        height: height as i32,
-        width: width as i32,
-        buf: BitmapBuffer::RGB(buf),
-    }
-}
-
 /// Return a custom block cursor character.
 pub fn get_block_cursor_glyph(height: usize, width: usize) -> RasterizedGlyph {
     // Create a completely filled glyph.
@@ -1,76 +1,453 @@
-use std::ffi::OsStr;
-use std::fmt::Debug;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug, Display, Formatter};
+#[cfg(not(windows))]
+use std::fs::File;
 use std::io;
+use std::io::{Cursor, Read, Write};
+#[cfg(not(windows))]
+use std::mem;
 #[cfg(not(windows))]
-use std::os::unix::process::CommandExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+#[cfg(not(windows))]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
-use std::process::{Command, Stdio};
+use std::path::Path;
+#[cfg(not(windows))]
+use std::process::ExitStatus;
+use std::process::{self, Command, Output, Stdio};
 
 use log::{debug, warn};
 
+use alacritty_terminal::thread;
+
 #[cfg(windows)]
 use winapi::um::winbase::{CREATE_NEW_PROCESS_GROUP, CREATE_NO_WINDOW};
 
-/// Start the daemon and log error on failure.
-pub fn start_daemon<I, S>(program: &str, args: I)
+/// Errors occurring while launching a daemon.
+#[derive(Debug)]
+pub enum DaemonError {
+    /// The process could not be spawned.
+    Spawn(io::Error),
+
+    /// The input of the process could not be written.
+    ///
+    /// Input is currently written in the background, so failures are only logged.
+    #[allow(dead_code)]
+    Write(io::Error),
+}
+
+impl Error for DaemonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DaemonError::Spawn(err) | DaemonError::Write(err) => Some(err),
+        }
+    }
+}
+
+impl Display for DaemonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DaemonError::Spawn(err) => write!(f, "Unable to spawn process: {}", err),
+            DaemonError::Write(err) => write!(f, "Unable to write process input: {}", err),
+        }
+    }
+}
+
+/// Environment identifying the running terminal session to launched programs.
+///
+/// The IPC socket is not included, since `ALACRITTY_SOCKET` is already exported to every child.
+pub fn session_env() -> Vec<(OsString, OsString)> {
+    vec![
+        ("ALACRITTY_PID".into(), process::id().to_string().into()),
+        ("TERM_PROGRAM".into(), "alacritty".into()),
+    ]
+}
+
+/// Start the daemon and log error on failure, returning the PID of the daemon.
+///
+/// The variables in `envs` are added to the environment inherited by the daemon. If `input` is
+/// present, it is written to the standard input of the daemon, which is closed afterwards. The
+/// daemon is started in `working_directory`, or in Alacritty's working directory if it is `None`.
+///
+/// Use [`spawn_daemon`] directly to stream the input from a reader instead.
+pub fn start_daemon<I, S>(
+    program: &str,
+    args: I,
+    envs: &[(OsString, OsString)],
+    input: Option<Vec<u8>>,
+    working_directory: Option<&Path>,
+) -> Result<u32, DaemonError>
 where
     I: IntoIterator<Item = S> + Debug + Copy,
     S: AsRef<OsStr>,
 {
-    match spawn_daemon(program, args) {
-        Ok(_) => debug!("Launched {} with args {:?}", program, args),
-        Err(_) => warn!("Unable to launch {} with args {:?}", program, args),
+    let input = input.map(|input| Box::new(Cursor::new(input)) as Box<dyn Read + Send>);
+    match spawn_daemon(program, args, envs, input, working_directory, false) {
+        Ok(pid) => {
+            debug!("Launched {} with args {:?} as PID {}", program, args, pid);
+            Ok(pid)
+        },
+        Err(err) => {
+            warn!("Unable to launch {}: {}", program, err);
+            Err(DaemonError::Spawn(err))
+        },
     }
 }
 
+/// Start the daemon, returning its PID or an error if it could not be launched.
+///
+/// The `input` is copied to the standard input of the daemon while it is running, so it does not
+/// need to be read into memory first.
+///
+/// Console applications run without a console window, unless `show_window` is `true`.
 #[cfg(windows)]
-fn spawn_daemon<I, S>(program: &str, args: I) -> io::Result<()>
+pub fn spawn_daemon<I, S>(
+    program: &str,
+    args: I,
+    envs: &[(OsString, OsString)],
+    input: Option<Box<dyn Read + Send>>,
+    working_directory: Option<&Path>,
+    show_window: bool,
+) -> io::Result<u32>
 where
     I: IntoIterator<Item = S> + Copy,
     S: AsRef<OsStr>,
 {
+    let stdin = if input.is_some() { Stdio::piped() } else { Stdio::null() };
+
+    let mut command = Command::new(program);
+    if let Some(working_directory) = working_directory {
+        command.current_dir(check_directory(working_directory)?);
+    }
+
     // Setting all the I/O handles to null and setting the
     // CREATE_NEW_PROCESS_GROUP and CREATE_NO_WINDOW has the effect
     // that console applications will run without opening a new
     // console window.
-    Command::new(program)
+    let mut creation_flags = CREATE_NEW_PROCESS_GROUP;
+    if !show_window {
+        creation_flags |= CREATE_NO_WINDOW;
+    }
+
+    let mut child = command
         .args(args)
-        .stdin(Stdio::null())
+        .envs(envs.iter().map(|(key, value)| (key, value)))
+        .stdin(stdin)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW)
-        .spawn()
-        .map(|_| ())
+        .creation_flags(creation_flags)
+        .spawn()?;
+
+    if let (Some(stdin), Some(input)) = (child.stdin.take(), input) {
+        write_input(stdin, input);
+    }
+
+    Ok(child.id())
 }
 
+/// Start the daemon, returning its PID or an error if it could not be launched.
+///
+/// The `input` is copied to the standard input of the daemon while it is running, so it does not
+/// need to be read into memory first.
+///
+/// The daemon is double-forked, so it is not a child of Alacritty. The intermediate process
+/// reports the PID of the daemon through a pipe before exiting.
+///
+/// Console windows only exist on Windows, so `_show_window` is ignored.
 #[cfg(not(windows))]
-fn spawn_daemon<I, S>(program: &str, args: I) -> io::Result<()>
+pub fn spawn_daemon<I, S>(
+    program: &str,
+    args: I,
+    envs: &[(OsString, OsString)],
+    input: Option<Box<dyn Read + Send>>,
+    working_directory: Option<&Path>,
+    _show_window: bool,
+) -> io::Result<u32>
 where
     I: IntoIterator<Item = S> + Copy,
     S: AsRef<OsStr>,
 {
+    let (mut reader, writer) = pipe()?;
+    let writer_fd = writer.as_raw_fd();
+
+    let (stdin, input) = match input {
+        Some(input) => {
+            let (stdin, input_writer) = pipe()?;
+            (Stdio::from(stdin), Some((input_writer, input)))
+        },
+        None => (Stdio::null(), None),
+    };
+
+    let mut command = Command::new(program);
+    command.args(args).envs(envs.iter().map(|(key, value)| (key, value)));
+    command.stdin(stdin).stdout(Stdio::null()).stderr(Stdio::null());
+    if let Some(working_directory) = working_directory {
+        command.current_dir(check_directory(working_directory)?);
+    }
+
     unsafe {
-        Command::new(program)
-            .args(args)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .pre_exec(|| {
-                match libc::fork() {
-                    -1 => return Err(io::Error::last_os_error()),
-                    0 => (),
-                    _ => libc::_exit(0),
-                }
-
-                if libc::setsid() == -1 {
-                    return Err(io::Error::last_os_error());
-                }
-
-                Ok(())
-            })
-            .spawn()?
-            .wait()
-            .map(|_| ())
+        command.pre_exec(move || {
+            match libc::fork() {
+                -1 => return Err(io::Error::last_os_error()),
+                0 => (),
+                pid => {
+                    let pid = pid.to_ne_bytes();
+                    libc::write(writer_fd, pid.as_ptr() as *const libc::c_void, pid.len());
+                    libc::_exit(0);
+                },
+            }
+
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        });
+    }
+
+    // Close our end of the pipe, so reading fails if the PID was never written.
+    let child = command.spawn();
+    drop(command);
+    drop(writer);
+    let status = child?.wait()?;
+    if status.success() {
+        debug!("Intermediate process of {} exited successfully", program);
+    } else {
+        warn!("Intermediate process of {} exited with {}", program, describe_exit(status));
+    }
+
+    let mut pid = [0; mem::size_of::<libc::pid_t>()];
+    reader.read_exact(&mut pid)?;
+
+    if let Some((input_writer, input)) = input {
+        write_input(input_writer, input);
+    }
+
+    Ok(libc::pid_t::from_ne_bytes(pid) as u32)
+}
+
+/// Describe why a process exited, using its exit code or the signal which terminated it.
+#[cfg(not(windows))]
+fn describe_exit(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("code {}", code),
+        (None, Some(signal)) => format!("signal {}", signal),
+        (None, None) => status.to_string(),
+    }
+}
+
+/// Run a program to completion, capturing its standard output and error.
+///
+/// Unlike [`start_daemon`], this blocks until the program has exited, so it should only be used
+/// for short-lived helpers. The environment, input and working directory are handled like for
+/// [`spawn_daemon`].
+#[allow(dead_code)]
+pub fn spawn_daemon_capture<I, S>(
+    program: &str,
+    args: I,
+    envs: &[(OsString, OsString)],
+    input: Option<Box<dyn Read + Send>>,
+    working_directory: Option<&Path>,
+) -> io::Result<Output>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let stdin = if input.is_some() { Stdio::piped() } else { Stdio::null() };
+
+    let mut command = Command::new(program);
+    command.args(args).envs(envs.iter().map(|(key, value)| (key, value)));
+    command.stdin(stdin).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if let Some(working_directory) = working_directory {
+        command.current_dir(check_directory(working_directory)?);
+    }
+
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = command.spawn()?;
+
+    // The input is written concurrently, since the output pipes might fill up before it is read.
+    if let (Some(stdin), Some(input)) = (child.stdin.take(), input) {
+        write_input(stdin, input);
+    }
+
+    child.wait_with_output()
+}
+
+/// Ensure the working directory of a daemon exists.
+///
+/// Otherwise spawning fails with an error which does not mention the directory, making it
+/// indistinguishable from a missing program.
+fn check_directory(directory: &Path) -> io::Result<&Path> {
+    if directory.is_dir() {
+        Ok(directory)
+    } else {
+        let message = format!("working directory {:?} does not exist", directory);
+        Err(io::Error::new(io::ErrorKind::NotFound, message))
+    }
+}
+
+/// Write the input of a daemon in a separate thread, since it might not be read immediately.
+fn write_input<W: Write + Send + 'static>(mut writer: W, mut input: Box<dyn Read + Send>) {
+    thread::spawn_named("daemon input", move || match io::copy(&mut input, &mut writer) {
+        Ok(_) => (),
+        // Programs are free to exit without reading all of their input.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+            debug!("Daemon exited before reading all input");
+        },
+        Err(err) => warn!("Unable to write daemon input: {}", err),
+    });
+}
+
+/// Create a pipe which is not inherited by executed programs.
+#[cfg(not(windows))]
+fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    unsafe {
+        if libc::pipe(fds.as_mut_ptr()) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let pipe = (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1]));
+        for fd in &fds {
+            if libc::fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(pipe)
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use std::{env, fs, thread};
+
+    fn output_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("alacritty-daemon-{}-{}", name, process::id()))
+    }
+
+    /// Wait for the daemon to write its output to `path` and remove the file.
+    fn read_output(path: &Path) -> String {
+        for _ in 0..250 {
+            if let Ok(output) = fs::read_to_string(path) {
+                fs::remove_file(path).unwrap();
+                return output;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        panic!("daemon did not write its output");
+    }
+
+    #[test]
+    fn forward_environment() {
+        let path = output_path("env");
+        let script = r#"printf '%s|%s' "$A" "${B-unset}" > "$OUT.tmp" && mv "$OUT.tmp" "$OUT""#;
+        let envs = [
+            ("A".into(), "x=y=".into()),
+            ("B".into(), OsString::new()),
+            ("OUT".into(), path.clone().into_os_string()),
+        ];
+
+        spawn_daemon("sh", &["-c", script], &envs, None, None, false).unwrap();
+
+        assert_eq!(read_output(&path), "x=y=|");
+    }
+
+    #[test]
+    fn pipe_input() {
+        let path = output_path("input");
+        let script = r#"cat > "$OUT.tmp" && mv "$OUT.tmp" "$OUT""#;
+        let envs = [("OUT".into(), path.clone().into_os_string())];
+
+        // Larger than the pipe buffer, so writing has to wait for the daemon.
+        let input = "line\n".repeat(100_000);
+        let reader: Box<dyn Read + Send> = Box::new(Cursor::new(input.clone()));
+        spawn_daemon("sh", &["-c", script], &envs, Some(reader), None, false).unwrap();
+
+        assert_eq!(read_output(&path), input);
+    }
+
+    #[test]
+    fn stream_input() {
+        let path = output_path("stream");
+        let script = r#"wc -c > "$OUT.tmp" && mv "$OUT.tmp" "$OUT""#;
+        let envs = [("OUT".into(), path.clone().into_os_string())];
+
+        let reader: Box<dyn Read + Send> = Box::new(io::repeat(b'a').take(1 << 22));
+        spawn_daemon("sh", &["-c", script], &envs, Some(reader), None, false).unwrap();
+        assert_eq!(read_output(&path).trim(), (1 << 22).to_string());
+
+        // Programs exiting without reading their input do not cause any errors.
+        let reader: Box<dyn Read + Send> = Box::new(io::repeat(b'a').take(1 << 22));
+        let output = spawn_daemon_capture("true", &[] as &[&str], &[], Some(reader), None).unwrap();
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn working_directory() {
+        let path = output_path("cwd");
+        let script = r#"pwd > "$OUT.tmp" && mv "$OUT.tmp" "$OUT""#;
+        let envs = [("OUT".into(), path.clone().into_os_string())];
+
+        let directory = env::temp_dir().canonicalize().unwrap();
+        spawn_daemon("sh", &["-c", script], &envs, None, Some(&directory), false).unwrap();
+        assert_eq!(read_output(&path).trim_end(), directory.to_str().unwrap());
+
+        let missing = directory.join("alacritty-missing-directory");
+        let err =
+            spawn_daemon("sh", &["-c", script], &envs, None, Some(&missing), false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("alacritty-missing-directory"));
+    }
+
+    #[test]
+    fn capture_output() {
+        let script = "printf out; printf err >&2; cat";
+        let input = "line\n".repeat(100_000);
+        let reader: Box<dyn Read + Send> = Box::new(Cursor::new(input.clone()));
+        let output = spawn_daemon_capture("sh", &["-c", script], &[], Some(reader), None).unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("out{}", input));
+        assert_eq!(output.stderr, b"err");
+
+        let output = spawn_daemon_capture("sh", &["-c", "exit 3"], &[], None, None).unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert!(output.stdout.is_empty());
+    }
+
+    #[test]
+    fn exit_description() {
+        assert_eq!(describe_exit(ExitStatus::from_raw(3 << 8)), "code 3");
+        assert_eq!(describe_exit(ExitStatus::from_raw(libc::SIGKILL)), "signal 9");
+    }
+
+    #[test]
+    fn session_environment() {
+        let envs = session_env();
+        let value = |key: &str| envs.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+
+        assert_eq!(value("ALACRITTY_PID"), Some(process::id().to_string().into()));
+        assert_eq!(value("TERM_PROGRAM"), Some("alacritty".into()));
+    }
+}
+
+#[cfg(all(test, windows))]
+mod windows_tests {
+    use super::*;
+
+    #[test]
+    fn spawn_with_console_window() {
+        spawn_daemon("cmd", &["/C", "exit"], &[], None, None, true).unwrap();
+        spawn_daemon("cmd", &["/C", "exit"], &[], None, None, false).unwrap();
     }
 }
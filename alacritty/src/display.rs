@@ -24,11 +24,14 @@ use wayland_client::{Display as WaylandDisplay, EventQueue};
 use crossfont::set_font_smoothing;
 use crossfont::{self, Rasterize, Rasterizer};
 
+use alacritty_terminal::ansi::CursorShape;
 use alacritty_terminal::event::{EventListener, OnResize};
 use alacritty_terminal::index::{Column, Direction, Point};
 use alacritty_terminal::selection::Selection;
-use alacritty_terminal::term::{SizeInfo, Term, TermMode};
-use alacritty_terminal::term::{MIN_COLS, MIN_SCREEN_LINES};
+#[cfg(feature = "bidi")]
+use alacritty_terminal::term::bidi::{self, BidiMap};
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::{RenderableCellContent, SizeInfo, Term, TermMode};
 
 use crate::config::font::Font;
 use crate::config::window::Dimensions;
@@ -38,14 +41,17 @@ use crate::config::Config;
 use crate::event::{Mouse, SearchState};
 use crate::message_bar::{MessageBuffer, MessageType};
 use crate::meter::Meter;
-use crate::renderer::rects::{RenderLines, RenderRect};
+use crate::renderer::rects::{HollowCursor, RenderLines, RenderRect};
 use crate::renderer::{self, GlyphCache, QuadRenderer};
 use crate::url::{Url, Urls};
-use crate::window::{self, Window};
+use crate::window::{self, ResizeIncrements, Window};
 
 const FORWARD_SEARCH_LABEL: &str = "Search: ";
 const BACKWARD_SEARCH_LABEL: &str = "Backward Search: ";
 
+/// Opacity of the tint shown while a file is dragged over the window.
+const HOVERED_FILE_ALPHA: f32 = 0.1;
+
 #[derive(Debug)]
 pub enum Error {
     /// Error with window management.
@@ -154,14 +160,21 @@ pub struct Display {
     /// Currently highlighted URL.
     pub highlighted_url: Option<Url>,
 
+    /// Visual order of the rows in the viewport, `None` for rows in logical order.
+    #[cfg(feature = "bidi")]
+    pub bidi_maps: Vec<Option<BidiMap>>,
+
     #[cfg(all(feature = "wayland", not(any(target_os = "macos", windows))))]
     pub wayland_event_queue: Option<EventQueue>,
 
     #[cfg(not(any(target_os = "macos", windows)))]
     pub is_x11: bool,
 
-    /// UI cursor visibility for blinking.
-    pub cursor_hidden: bool,
+    /// Phase of the text blinking animation, advanced every half blink interval.
+    pub blink_phase: u8,
+
+    /// Blinking text was visible during the last draw.
+    pub has_blinking_text: bool,
 
     renderer: QuadRenderer,
     glyph_cache: GlyphCache,
@@ -228,8 +241,8 @@ impl Display {
         let padding = config.ui_config.window.padding(window.dpr);
         let viewport_size = window.inner_size();
 
-        // Create new size with at least one column and row.
-        let size_info = SizeInfo::new(
+        // Create new size with at least the minimum number of columns and lines.
+        let mut size_info = SizeInfo::new(
             viewport_size.width as f32,
             viewport_size.height as f32,
             cell_width,
@@ -238,6 +251,9 @@ impl Display {
             padding.1,
             config.ui_config.window.dynamic_padding && dimensions.is_none(),
         );
+        let window_config = &config.ui_config.window;
+        size_info
+            .set_minimum_dimensions(window_config.minimum_columns(), window_config.minimum_lines());
 
         info!("Cell size: {} x {}", cell_width, cell_height);
         info!("Padding: {} x {}", size_info.padding_x(), size_info.padding_y());
@@ -291,7 +307,7 @@ impl Display {
             _ => (),
         }
 
-        Ok(Self {
+        let mut display = Self {
             window,
             renderer,
             glyph_cache,
@@ -299,12 +315,19 @@ impl Display {
             size_info,
             urls: Urls::new(),
             highlighted_url: None,
+            #[cfg(feature = "bidi")]
+            bidi_maps: Vec::new(),
             #[cfg(not(any(target_os = "macos", windows)))]
             is_x11,
             #[cfg(all(feature = "wayland", not(any(target_os = "macos", windows))))]
             wayland_event_queue,
-            cursor_hidden: false,
-        })
+            blink_phase: 0,
+            has_blinking_text: false,
+        };
+
+        display.update_size_hints(config, cell_width, cell_height);
+
+        Ok(display)
     }
 
     fn new_glyph_cache(
@@ -387,8 +410,23 @@ impl Display {
             self.clear_glyph_cache();
         }
 
+        self.update_size_hints(config, cell_width, cell_height);
+
         let (mut width, mut height) = (self.size_info.width(), self.size_info.height());
         if let Some(dimensions) = update_pending.dimensions() {
+            // Emulate resize increments on Wayland, since they're not supported by the protocol.
+            #[cfg(not(any(target_os = "macos", windows)))]
+            let dimensions = match self.window.resize_increments() {
+                Some(increments) if !self.is_x11 => {
+                    let snapped = increments.snap(dimensions);
+                    if snapped != dimensions {
+                        self.window.set_inner_size(snapped);
+                    }
+                    snapped
+                },
+                _ => dimensions,
+            };
+
             width = dimensions.width as f32;
             height = dimensions.height as f32;
         }
@@ -405,6 +443,11 @@ impl Display {
             config.ui_config.window.dynamic_padding,
         );
 
+        // Keep the grid at its minimum size, even if the window is smaller.
+        let window_config = &config.ui_config.window;
+        self.size_info
+            .set_minimum_dimensions(window_config.minimum_columns(), window_config.minimum_lines());
+
         // Update number of column/lines in the viewport.
         let message_bar_lines =
             message_buffer.message().map(|m| m.text(&self.size_info).len()).unwrap_or(0);
@@ -427,6 +470,33 @@ impl Display {
         info!("Width: {}, Height: {}", self.size_info.width(), self.size_info.height());
     }
 
+    /// Update the window's size constraints for the current cell dimensions.
+    ///
+    /// This prevents the window from being resized below the minimum grid size and snaps resizes
+    /// to the cell grid when `window.resize_increments` is enabled.
+    fn update_size_hints(&mut self, config: &Config, cell_width: f32, cell_height: f32) {
+        let window_config = &config.ui_config.window;
+        let padding = window_config.padding(self.window.dpr);
+        let base = PhysicalSize::new((2. * padding.0) as u32, (2. * padding.1) as u32);
+
+        let min_width = cell_width * window_config.minimum_columns().0 as f32;
+        let min_height = cell_height * window_config.minimum_lines().0 as f32;
+        let min_size =
+            PhysicalSize::new(base.width + min_width as u32, base.height + min_height as u32);
+        self.window.set_min_inner_size(Some(min_size));
+
+        let increments = if window_config.resize_increments {
+            Some(ResizeIncrements {
+                step: PhysicalSize::new(cell_width as u32, cell_height as u32),
+                base,
+            })
+        } else {
+            None
+        };
+
+        self.window.set_resize_increments(increments);
+    }
+
     /// Draw the screen.
     ///
     /// A reference to Term whose state is being drawn must be provided.
@@ -434,7 +504,7 @@ impl Display {
     /// This call may block if vsync is enabled.
     pub fn draw<T>(
         &mut self,
-        terminal: MutexGuard<'_, Term<T>>,
+        mut terminal: MutexGuard<'_, Term<T>>,
         message_buffer: &MessageBuffer,
         config: &Config,
         mouse: &Mouse,
@@ -446,15 +516,32 @@ impl Display {
         let viewport_match = search_state
             .focused_match()
             .and_then(|focused_match| terminal.grid().clamp_buffer_range_to_visible(focused_match));
-        let cursor_hidden = self.cursor_hidden || search_state.regex().is_some();
+        let cursor_hidden = !terminal.cursor_blink() || search_state.regex().is_some();
+        // Rapid blinking toggles on every phase, slow blinking on every second one.
+        let blink_animated =
+            terminal.is_focused && config.ui_config.terminal.blink_interval().is_some();
+        let mut hidden_blinking = Flags::empty();
+        if blink_animated && self.blink_phase & 1 != 0 {
+            hidden_blinking.insert(Flags::RAPID_BLINKING);
+        }
+        if blink_animated && self.blink_phase & 2 != 0 {
+            hidden_blinking.insert(Flags::BLINKING);
+        }
 
         let grid_cells = terminal.renderable_cells(config, !cursor_hidden).collect::<Vec<_>>();
         let visual_bell_intensity = terminal.visual_bell.intensity();
         let background_color = terminal.background_color();
         let cursor_point = terminal.grid().cursor.point;
         let metrics = self.glyph_cache.font_metrics();
+
+        // Release the atlas space of image tiles once too many of them have been displayed.
+        if self.glyph_cache.graphics_full() {
+            self.clear_glyph_cache();
+        }
+
         let glyph_cache = &mut self.glyph_cache;
         let size_info = self.size_info;
+        let scale_factor = self.window.dpr;
 
         let selection = !terminal.selection.as_ref().map(Selection::is_empty).unwrap_or(true);
         let mouse_mode = terminal.mode().intersects(TermMode::MOUSE_MODE)
@@ -466,6 +553,18 @@ impl Display {
             None
         };
 
+        #[cfg(feature = "bidi")]
+        {
+            self.bidi_maps = if config.ui_config.terminal.bidi {
+                bidi::visible_bidi_maps(terminal.grid())
+            } else {
+                Vec::new()
+            };
+        }
+
+        // Cells written after this frame are one generation younger than the ones it displays.
+        terminal.next_generation();
+
         // Drop terminal as early as possible to free lock.
         drop(terminal);
 
@@ -475,11 +574,16 @@ impl Display {
 
         let mut lines = RenderLines::new();
         let mut urls = Urls::new();
+        let mut hollow_cursor = None;
+        let mut has_blinking_text = false;
 
         // Draw grid.
         {
             let _sampler = self.meter.sampler();
 
+            #[cfg(feature = "bidi")]
+            let bidi_maps = &self.bidi_maps;
+
             self.renderer.with_api(&config.ui_config, config.cursor, &size_info, |mut api| {
                 // Iterate over all non-empty cells in the grid.
                 for mut cell in grid_cells {
@@ -500,17 +604,51 @@ impl Display {
                     // Update URL underlines.
                     urls.update(size_info.cols(), &cell);
 
+                    // Move cells to their visual position, the grid itself stays in logical order.
+                    #[cfg(feature = "bidi")]
+                    if let Some(Some(bidi_map)) = bidi_maps.get(cell.line.0) {
+                        cell.column = bidi_map.to_visual(cell.column);
+                    }
+
+                    // Hide blinking text and its decorations during the blink's off phase.
+                    if cell.flags.intersects(Flags::ALL_BLINKING) {
+                        has_blinking_text = true;
+
+                        if cell.flags.intersects(hidden_blinking) {
+                            if let RenderableCellContent::Chars(_) = cell.inner {
+                                cell.inner = RenderableCellContent::Chars((' ', None));
+                                api.render_cell(cell, glyph_cache);
+                                continue;
+                            }
+                        }
+                    }
+
                     // Update underline/strikeout.
                     lines.update(&cell);
 
+                    // The hollow cursor is drawn as rects on top of the text.
+                    if let RenderableCellContent::Cursor(key) = cell.inner {
+                        if key.shape == CursorShape::HollowBlock {
+                            hollow_cursor = Some(HollowCursor::new(&cell, key.is_wide));
+                            continue;
+                        }
+                    }
+
                     // Draw the cell.
                     api.render_cell(cell, glyph_cache);
                 }
             });
         }
 
+        self.has_blinking_text = has_blinking_text;
+
         let mut rects = lines.rects(&metrics, &size_info);
 
+        if let Some(hollow_cursor) = hollow_cursor {
+            let thickness = config.cursor.unfocused_thickness();
+            rects.extend_from_slice(&hollow_cursor.rects(thickness, scale_factor, &size_info));
+        }
+
         // Update visible URLs.
         self.urls = urls;
         if let Some(url) = self.urls.highlighted(config, mouse, mods, mouse_mode, selection) {
@@ -549,6 +687,19 @@ impl Display {
             rects.push(visual_bell_rect);
         }
 
+        // Tint the window while a file is dragged over it.
+        if mouse.hovered_file {
+            let color = config.colors.primary.foreground;
+            rects.push(RenderRect::new(
+                0.,
+                0.,
+                size_info.width(),
+                size_info.height(),
+                color,
+                HOVERED_FILE_ALPHA,
+            ));
+        }
+
         if let Some(message) = message_buffer.message() {
             let search_offset = if search_active { 1 } else { 0 };
             let text = message.text(&size_info);
@@ -731,8 +882,9 @@ fn window_size(
 ) -> PhysicalSize<u32> {
     let padding = config.ui_config.window.padding(dpr);
 
-    let grid_width = cell_width * dimensions.columns.0.max(MIN_COLS) as f32;
-    let grid_height = cell_height * dimensions.lines.0.max(MIN_SCREEN_LINES) as f32;
+    let window_config = &config.ui_config.window;
+    let grid_width = cell_width * dimensions.columns.max(window_config.minimum_columns()).0 as f32;
+    let grid_height = cell_height * dimensions.lines.max(window_config.minimum_lines()).0 as f32;
 
     let width = (padding.0).mul_add(2., grid_width).floor();
     let height = (padding.1).mul_add(2., grid_height).floor();
@@ -2,63 +2,94 @@
 
 use std::borrow::Cow;
 use std::cmp::{max, min};
+use std::collections::HashSet;
 use std::env;
 use std::fmt::Debug;
-#[cfg(not(any(target_os = "macos", windows)))]
-use std::fs;
-use std::fs::File;
-use std::io::Write;
+use std::fs::{self, File};
+use std::io::{self, Write};
 use std::mem;
 use std::ops::RangeInclusive;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process;
 #[cfg(not(any(target_os = "macos", windows)))]
 use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
 use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
 use glutin::dpi::PhysicalSize;
-use glutin::event::{ElementState, Event as GlutinEvent, ModifiersState, MouseButton, WindowEvent};
+use glutin::event::{
+    ElementState, Event as GlutinEvent, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent,
+};
 use glutin::event_loop::{ControlFlow, EventLoop, EventLoopProxy, EventLoopWindowTarget};
 use glutin::platform::desktop::EventLoopExtDesktop;
 #[cfg(all(feature = "wayland", not(any(target_os = "macos", windows))))]
 use glutin::platform::unix::EventLoopWindowTargetExtUnix;
-use log::info;
+use log::{error, info};
 use serde_json as json;
 
 #[cfg(target_os = "macos")]
 use crossfont::set_font_smoothing;
 use crossfont::{self, Size};
 
-use alacritty_terminal::config::LOG_TARGET_CONFIG;
-use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify, OnResize};
+use alacritty_terminal::config::{Program, LOG_TARGET_CONFIG};
+use alacritty_terminal::event::{Event as TerminalEvent, EventListener, Notify, OnPause, OnResize};
 use alacritty_terminal::grid::{Dimensions, Scroll};
 use alacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use alacritty_terminal::selection::{Selection, SelectionType};
 use alacritty_terminal::sync::FairMutex;
-use alacritty_terminal::term::{ClipboardType, SizeInfo, Term, TermMode};
+#[cfg(feature = "bidi")]
+use alacritty_terminal::term::bidi::BidiMap;
+use alacritty_terminal::term::{persist, ClipboardType, SizeInfo, Term, TermMode};
+use alacritty_terminal::thread;
 #[cfg(not(windows))]
 use alacritty_terminal::tty;
 
 use crate::cli::Options as CLIOptions;
 use crate::clipboard::Clipboard;
 use crate::config;
+use crate::config::scrollback_dump;
+use crate::config::terminal::FlowControl;
 use crate::config::Config;
-use crate::daemon::start_daemon;
+use crate::daemon::{session_env, spawn_daemon, start_daemon};
 use crate::display::{Display, DisplayUpdate};
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
+#[cfg(unix)]
+use crate::ipc::IpcAction;
+use crate::keyboard::Win32KeyEvent;
 #[cfg(target_os = "macos")]
 use crate::macos;
-use crate::message_bar::{Message, MessageBuffer};
+use crate::message_bar::{Message, MessageBuffer, MessageType};
 use crate::scheduler::{Scheduler, TimerId};
 use crate::url::{Url, Urls};
-use crate::window::Window;
+use crate::window::{cascade_position, Window};
 
 /// Duration after the last user input until an unlimited search is performed.
 pub const TYPING_SEARCH_DELAY: Duration = Duration::from_millis(500);
 
+/// Minimum time between updates of the window's represented directory.
+#[cfg(target_os = "macos")]
+const REPRESENTED_DIRECTORY_DELAY: Duration = Duration::from_millis(250);
+
+/// Interval between writes of the scrollback history to its persistence file.
+const SCROLLBACK_PERSISTENCE_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Maximum number of lines for the blocking search while still typing the search regex.
 const MAX_SEARCH_WHILE_TYPING: Option<usize> = Some(1000);
 
+/// Message bar target of the paused output indicator.
+const FLOW_CONTROL_TARGET: &str = "alacritty_flow_control";
+
+/// Message bar target for the result of the last scrollback dump.
+const SCROLLBACK_DUMP_TARGET: &str = "alacritty_scrollback_dump";
+
+/// Message bar target of the notice shown when no command output is marked.
+const COMMAND_OUTPUT_TARGET: &str = "alacritty_command_output";
+
+/// Message bar target of the cell description shown by `InspectCell`.
+const INSPECT_CELL_TARGET: &str = "alacritty_inspect_cell";
+
 /// Events dispatched through the UI event loop.
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -68,7 +99,12 @@ pub enum Event {
     ConfigReload(PathBuf),
     Message(Message),
     BlinkCursor,
+    BlinkText,
     SearchNext,
+    RepresentedDirectory,
+    PersistScrollback,
+    #[cfg(unix)]
+    Ipc(IpcAction),
 }
 
 impl From<Event> for GlutinEvent<'_, Event> {
@@ -134,6 +170,44 @@ impl Default for SearchState {
     }
 }
 
+/// Background thread writing scrollback snapshots to the persistence file.
+struct ScrollbackWriter {
+    sender: SyncSender<persist::Snapshot>,
+    thread: JoinHandle<()>,
+}
+
+impl ScrollbackWriter {
+    fn new(path: PathBuf) -> Self {
+        // Snapshots are only accepted while no other write is in progress.
+        let (sender, receiver) = mpsc::sync_channel::<persist::Snapshot>(0);
+
+        let thread = thread::spawn_named("scrollback writer", move || {
+            for snapshot in receiver {
+                if let Err(err) = persist::save(&path, &snapshot) {
+                    error!("Unable to persist scrollback to {:?}: {}", path, err);
+                    break;
+                }
+            }
+        });
+
+        Self { sender, thread }
+    }
+
+    /// Queue a snapshot for writing.
+    ///
+    /// Snapshots taken while the previous one is still being written are dropped. Returns `false`
+    /// if the writer has stopped after a failed write.
+    fn write(&self, snapshot: persist::Snapshot) -> bool {
+        !matches!(self.sender.try_send(snapshot), Err(TrySendError::Disconnected(_)))
+    }
+
+    /// Wait for the current write to complete.
+    fn finish(self) {
+        drop(self.sender);
+        let _ = self.thread.join();
+    }
+}
+
 pub struct ActionContext<'a, N, T> {
     pub notifier: &'a mut N,
     pub terminal: &'a mut Term<T>,
@@ -142,6 +216,8 @@ pub struct ActionContext<'a, N, T> {
     pub mouse: &'a mut Mouse,
     pub received_count: &'a mut usize,
     pub suppress_chars: &'a mut bool,
+    pub reported_keys: &'a mut HashSet<VirtualKeyCode>,
+    pub pending_win32_key: &'a mut Option<Win32KeyEvent>,
     pub modifiers: &'a mut ModifiersState,
     pub window: &'a mut Window,
     pub message_buffer: &'a mut MessageBuffer,
@@ -149,14 +225,22 @@ pub struct ActionContext<'a, N, T> {
     pub config: &'a mut Config,
     pub event_loop: &'a EventLoopWindowTarget<Event>,
     pub urls: &'a Urls,
+    #[cfg(feature = "bidi")]
+    pub bidi_maps: &'a [Option<BidiMap>],
     pub scheduler: &'a mut Scheduler,
     pub search_state: &'a mut SearchState,
-    cursor_hidden: &'a mut bool,
+    pub event_proxy: &'a EventProxy,
+    blink_phase: &'a mut u8,
+    scrollback_writer: &'a mut Option<ScrollbackWriter>,
+    output_paused: &'a mut bool,
+    cascade_step: &'a mut usize,
     cli_options: &'a CLIOptions,
     font_size: &'a mut Size,
 }
 
-impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionContext<'a, N, T> {
+impl<'a, N: Notify + OnPause + 'a, T: EventListener> input::ActionContext<T>
+    for ActionContext<'a, N, T>
+{
     fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, val: B) {
         self.notifier.notify(val);
     }
@@ -185,6 +269,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             || self.mouse().right_button_state == ElementState::Pressed
         {
             let point = self.size_info().pixels_to_coords(self.mouse().x, self.mouse().y);
+            let point = self.logical_point(point);
             let cell_side = self.mouse().cell_side;
             self.update_selection(Point { line: point.line, col: point.col }, cell_side);
         }
@@ -254,12 +339,22 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         let y = self.mouse.y as usize;
 
         if self.size_info.contains_point(x, y) {
-            Some(self.size_info.pixels_to_coords(x, y))
+            Some(self.logical_point(self.size_info.pixels_to_coords(x, y)))
         } else {
             None
         }
     }
 
+    /// Convert a point in the viewport from its visual to its logical column.
+    fn logical_point(&self, point: Point) -> Point {
+        #[cfg(feature = "bidi")]
+        if let Some(Some(bidi_map)) = self.bidi_maps.get(point.line.0) {
+            return Point::new(point.line, bidi_map.to_logical(point.col));
+        }
+
+        point
+    }
+
     #[inline]
     fn mouse_mode(&self) -> bool {
         self.terminal.mode().intersects(TermMode::MOUSE_MODE)
@@ -286,6 +381,14 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         &mut self.suppress_chars
     }
 
+    fn reported_keys(&mut self) -> &mut HashSet<VirtualKeyCode> {
+        &mut self.reported_keys
+    }
+
+    fn pending_win32_key(&mut self) -> &mut Option<Win32KeyEvent> {
+        &mut self.pending_win32_key
+    }
+
     #[inline]
     fn modifiers(&mut self) -> &mut ModifiersState {
         &mut self.modifiers
@@ -341,6 +444,27 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
         let working_directory_set = !args.is_empty();
 
+        // Cascade the new window on the monitor of the current one.
+        //
+        // The position is passed before the reused arguments, since the command must be last.
+        if let (Some(origin), Some(work_area)) =
+            (self.window.outer_position(), self.window.monitor_area())
+        {
+            *self.cascade_step += 1;
+
+            let cell = PhysicalSize::new(
+                self.size_info.cell_width().round() as u32,
+                self.size_info.cell_height().round() as u32,
+            );
+            let size = self.window.outer_size();
+            let position = cascade_position(origin, size, cell, *self.cascade_step, work_area);
+
+            args.push("--option".into());
+            args.push(format!("window.position.x={}", position.x).into());
+            args.push("--option".into());
+            args.push(format!("window.position.y={}", position.y).into());
+        }
+
         // Reuse the arguments passed to Alacritty for the new instance.
         while let Some(arg) = env_args.next() {
             // Drop working directory from existing parameters.
@@ -352,7 +476,41 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             args.push(arg.into());
         }
 
-        start_daemon(&alacritty, &args);
+        let _ = start_daemon(&alacritty, &args, &[], None, None);
+    }
+
+    fn dump_scrollback(&mut self) {
+        self.write_scrollback(None);
+    }
+
+    fn open_scrollback_in(&mut self, program: &Program) {
+        self.write_scrollback(Some(program.clone()));
+    }
+
+    fn copy_last_command_output(&mut self) {
+        if let Some(text) = self.last_command_output() {
+            self.clipboard.store(ClipboardType::Clipboard, text);
+        }
+    }
+
+    fn pipe_last_command_output(&mut self, program: &Program) {
+        if let Some(text) = self.last_command_output() {
+            let input = Some(text.into_bytes());
+            let _ = start_daemon(program.program(), program.args(), &session_env(), input, None);
+        }
+    }
+
+    fn inspect_cell(&mut self, point: Point) {
+        self.message_buffer.remove_target(INSPECT_CELL_TARGET);
+
+        let point = self.terminal.visible_to_buffer(point);
+        let text = self.terminal.describe_cell(point).to_string();
+        let mut message = Message::new(text, MessageType::Warning);
+        message.set_target(INSPECT_CELL_TARGET.into());
+        self.message_buffer.push(message);
+
+        self.display_update_pending.dirty = true;
+        self.terminal.dirty = true;
     }
 
     /// Spawn URL launcher when clicking on URLs.
@@ -363,11 +521,18 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
         if let Some(ref launcher) = self.config.ui_config.mouse.url.launcher {
             let mut args = launcher.args().to_vec();
-            let start = self.terminal.visible_to_buffer(url.start());
-            let end = self.terminal.visible_to_buffer(url.end());
-            args.push(self.terminal.bounds_to_string(start, end));
 
-            start_daemon(launcher.program(), &args);
+            // Launch hyperlinks with their URI instead of the text they're displayed as.
+            match url.hyperlink() {
+                Some(hyperlink) => args.push(hyperlink.uri.clone()),
+                None => {
+                    let start = self.terminal.visible_to_buffer(url.start());
+                    let end = self.terminal.visible_to_buffer(url.end());
+                    args.push(self.terminal.bounds_to_string(start, end));
+                },
+            }
+
+            let _ = start_daemon(launcher.program(), &args, &[], None, None);
         }
     }
 
@@ -508,8 +673,9 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         // Disable cursor blinking.
         let blink_interval = self.config.cursor.blink_interval();
         if let Some(timer) = self.scheduler.get_mut(TimerId::BlinkCursor) {
-            timer.deadline = Instant::now() + Duration::from_millis(blink_interval);
-            *self.cursor_hidden = false;
+            let now = Instant::now();
+            self.terminal.reset_cursor_blink(now);
+            timer.deadline = now + Duration::from_millis(blink_interval);
             self.terminal.dirty = true;
         }
 
@@ -519,6 +685,28 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         }
     }
 
+    /// Stop or resume reading output from the PTY.
+    fn set_output_paused(&mut self, paused: bool) {
+        if *self.output_paused == paused {
+            return;
+        }
+
+        *self.output_paused = paused;
+        self.notifier.on_pause(paused);
+
+        if paused {
+            let text = String::from("Output paused, press Ctrl+Q to resume");
+            let mut message = Message::new(text, MessageType::Warning);
+            message.set_target(FLOW_CONTROL_TARGET.into());
+            self.message_buffer.push(message);
+        } else {
+            self.message_buffer.remove_target(FLOW_CONTROL_TARGET);
+        }
+
+        self.display_update_pending.dirty = true;
+        self.terminal.dirty = true;
+    }
+
     #[inline]
     fn search_direction(&self) -> Direction {
         self.search_state.direction
@@ -554,7 +742,71 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     }
 }
 
-impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+impl<'a, N: Notify + OnPause + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Write the entire scrollback history to a file and optionally open it in `program`.
+    ///
+    /// The text is extracted while the terminal is locked, but writing it is done in a separate
+    /// thread, which reports the result to the message bar once it is done.
+    fn write_scrollback(&mut self, program: Option<Program>) {
+        let dump_config = &self.config.ui_config.scrollback_dump;
+        let total_lines = self.terminal.grid().total_lines();
+        let text = if dump_config.escapes {
+            self.terminal.dump_grid(Line(0)..=Line(total_lines - 1))
+        } else {
+            let start = Point::new(total_lines - 1, Column(0));
+            let end = Point::new(0, self.terminal.cols() - 1);
+            self.terminal.bounds_to_string(start, end)
+        };
+
+        let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now()).unwrap();
+        let file_name = format!("alacritty-scrollback-{}-{}.txt", timestamp, process::id());
+        let path = dump_config.directory().join(file_name);
+
+        self.message_buffer.remove_target(SCROLLBACK_DUMP_TARGET);
+
+        let event_proxy = self.event_proxy.clone();
+        thread::spawn_named("scrollback dump", move || {
+            let mut message = match (fs::write(&path, text), program) {
+                (Err(err), _) => {
+                    let text = format!("Unable to write scrollback to {}: {}", path.display(), err);
+                    Message::new(text, MessageType::Error)
+                },
+                (Ok(()), Some(program)) => match open_scrollback_file(&program, &path) {
+                    Ok(()) => return,
+                    Err(err) => {
+                        let text = format!("Unable to launch {}: {}", program.program(), err);
+                        Message::new(text, MessageType::Error)
+                    },
+                },
+                (Ok(()), None) => {
+                    let text = format!("Scrollback written to {}", path.display());
+                    Message::new(text, MessageType::Warning)
+                },
+            };
+
+            message.set_target(SCROLLBACK_DUMP_TARGET.into());
+            event_proxy.send_event(Event::Message(message));
+        });
+    }
+
+    /// Extract the output of the last command, showing a notice if the shell did not mark it.
+    fn last_command_output(&mut self) -> Option<String> {
+        self.message_buffer.remove_target(COMMAND_OUTPUT_TARGET);
+
+        let text = self.terminal.last_command_output();
+        if text.is_none() {
+            let text = String::from("No command output marked by the shell using OSC 133");
+            let mut message = Message::new(text, MessageType::Warning);
+            message.set_target(COMMAND_OUTPUT_TARGET.into());
+            self.message_buffer.push(message);
+
+            self.display_update_pending.dirty = true;
+            self.terminal.dirty = true;
+        }
+
+        text
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex.as_mut() {
             Some(regex) => regex,
@@ -704,20 +956,53 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         let terminal_blinking = self.terminal.cursor_style().blinking;
         let blinking = cursor_style.blinking_override().unwrap_or(terminal_blinking);
 
-        // Update cursor blinking state.
+        // Update cursor blinking state, hidden cursors do not need to blink.
         self.scheduler.unschedule(TimerId::BlinkCursor);
-        if blinking && self.terminal.is_focused {
+        self.terminal.reset_cursor_blink(Instant::now());
+        self.terminal.dirty = true;
+        if blinking && self.terminal.is_focused && self.terminal.cursor_visible() {
             self.scheduler.schedule(
                 GlutinEvent::UserEvent(Event::BlinkCursor),
                 Duration::from_millis(self.config.cursor.blink_interval()),
                 true,
                 TimerId::BlinkCursor,
             )
-        } else {
-            *self.cursor_hidden = false;
-            self.terminal.dirty = true;
         }
     }
+
+    /// Schedule an update of the window's represented directory.
+    ///
+    /// Shells usually report their directory with every prompt, so updates are throttled.
+    #[cfg(target_os = "macos")]
+    fn schedule_represented_directory_update(&mut self) {
+        if self.config.ui_config.window.macos_represented_dir
+            && !self.scheduler.scheduled(TimerId::RepresentedDirectory)
+        {
+            self.scheduler.schedule(
+                GlutinEvent::UserEvent(Event::RepresentedDirectory),
+                REPRESENTED_DIRECTORY_DELAY,
+                false,
+                TimerId::RepresentedDirectory,
+            );
+        }
+    }
+
+    /// Show the shell's working directory as the window's represented directory.
+    #[cfg(target_os = "macos")]
+    fn update_represented_directory(&mut self) {
+        let directory = if self.config.ui_config.window.macos_represented_dir {
+            self.terminal.working_directory()
+        } else {
+            None
+        };
+        self.window.set_represented_directory(directory);
+    }
+}
+
+/// Open a file containing the scrollback history in an external program.
+fn open_scrollback_file(program: &Program, path: &Path) -> io::Result<()> {
+    let args = scrollback_dump::program_args(program.args(), path);
+    spawn_daemon(program.program(), &args, &[], None, None, false).map(|_| ())
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -746,6 +1031,7 @@ pub struct Mouse {
     pub lines_scrolled: f32,
     pub block_url_launcher: bool,
     pub inside_text_area: bool,
+    pub hovered_file: bool,
 }
 
 impl Default for Mouse {
@@ -766,6 +1052,7 @@ impl Default for Mouse {
             lines_scrolled: 0.,
             block_url_launcher: false,
             inside_text_area: false,
+            hovered_file: false,
         }
     }
 }
@@ -779,6 +1066,8 @@ pub struct Processor<N> {
     mouse: Mouse,
     received_count: usize,
     suppress_chars: bool,
+    reported_keys: HashSet<VirtualKeyCode>,
+    pending_win32_key: Option<Win32KeyEvent>,
     clipboard: Clipboard,
     modifiers: ModifiersState,
     config: Config,
@@ -787,10 +1076,14 @@ pub struct Processor<N> {
     font_size: Size,
     event_queue: Vec<GlutinEvent<'static, Event>>,
     search_state: SearchState,
+    event_proxy: EventProxy,
+    scrollback_writer: Option<ScrollbackWriter>,
+    output_paused: bool,
+    cascade_step: usize,
     cli_options: CLIOptions,
 }
 
-impl<N: Notify + OnResize> Processor<N> {
+impl<N: Notify + OnResize + OnPause> Processor<N> {
     /// Create a new event processor.
     ///
     /// Takes a writer which is expected to be hooked up to the write end of a PTY.
@@ -799,6 +1092,7 @@ impl<N: Notify + OnResize> Processor<N> {
         message_buffer: MessageBuffer,
         config: Config,
         display: Display,
+        event_proxy: EventProxy,
         cli_options: CLIOptions,
     ) -> Processor<N> {
         #[cfg(not(any(target_os = "macos", windows)))]
@@ -811,6 +1105,8 @@ impl<N: Notify + OnResize> Processor<N> {
             mouse: Default::default(),
             received_count: 0,
             suppress_chars: false,
+            reported_keys: HashSet::new(),
+            pending_win32_key: None,
             modifiers: Default::default(),
             font_size: config.ui_config.font.size,
             config,
@@ -819,6 +1115,10 @@ impl<N: Notify + OnResize> Processor<N> {
             event_queue: Vec::new(),
             clipboard,
             search_state: SearchState::new(),
+            event_proxy,
+            scrollback_writer: None,
+            output_paused: false,
+            cascade_step: 0,
             cli_options,
         }
     }
@@ -861,6 +1161,13 @@ impl<N: Notify + OnResize> Processor<N> {
             self.event_queue.push(event.into());
         }
 
+        // Start periodically persisting the scrollback history.
+        Self::update_scrollback_persistence(
+            &mut scheduler,
+            &mut self.scrollback_writer,
+            &self.config,
+        );
+
         event_loop.run_return(|event, event_loop, control_flow| {
             if self.config.ui_config.debug.print_events {
                 info!("glutin event: {:?}", event);
@@ -920,6 +1227,8 @@ impl<N: Notify + OnResize> Processor<N> {
                 size_info: &mut self.display.size_info,
                 received_count: &mut self.received_count,
                 suppress_chars: &mut self.suppress_chars,
+                reported_keys: &mut self.reported_keys,
+                pending_win32_key: &mut self.pending_win32_key,
                 modifiers: &mut self.modifiers,
                 message_buffer: &mut self.message_buffer,
                 display_update_pending: &mut display_update_pending,
@@ -927,10 +1236,16 @@ impl<N: Notify + OnResize> Processor<N> {
                 font_size: &mut self.font_size,
                 config: &mut self.config,
                 urls: &self.display.urls,
+                #[cfg(feature = "bidi")]
+                bidi_maps: &self.display.bidi_maps,
                 scheduler: &mut scheduler,
                 search_state: &mut self.search_state,
+                event_proxy: &self.event_proxy,
                 cli_options: &self.cli_options,
-                cursor_hidden: &mut self.display.cursor_hidden,
+                blink_phase: &mut self.display.blink_phase,
+                scrollback_writer: &mut self.scrollback_writer,
+                output_paused: &mut self.output_paused,
+                cascade_step: &mut self.cascade_step,
                 event_loop,
             };
             let mut processor = input::Processor::new(context, &self.display.highlighted_url);
@@ -950,8 +1265,10 @@ impl<N: Notify + OnResize> Processor<N> {
                 return;
             }
 
-            if terminal.dirty {
+            // Don't present partial frames while a synchronized update is in progress.
+            if terminal.dirty && terminal.sync_update_timeout().is_none() {
                 terminal.dirty = false;
+                let is_focused = terminal.is_focused;
 
                 // Request immediate re-draw if visual bell animation is not finished yet.
                 if !terminal.visual_bell.completed() {
@@ -970,6 +1287,8 @@ impl<N: Notify + OnResize> Processor<N> {
                     self.modifiers,
                     &self.search_state,
                 );
+
+                self.update_text_blinking(&mut scheduler, is_focused);
             }
         });
 
@@ -977,6 +1296,69 @@ impl<N: Notify + OnResize> Processor<N> {
         if self.config.ui_config.debug.ref_test {
             self.write_ref_test_results(&terminal.lock());
         }
+
+        // The persisted scrollback is only required to recover from crashes.
+        if let Some(writer) = self.scrollback_writer.take() {
+            writer.finish();
+        }
+        if let Some(dir) = &self.config.scrollback_persistence {
+            let path = persist::file(dir);
+            if let Err(err) = persist::clear(&path) {
+                error!("Unable to clear persisted scrollback {:?}: {}", path, err);
+            }
+        }
+    }
+
+    /// Start or stop persisting the scrollback history.
+    fn update_scrollback_persistence(
+        scheduler: &mut Scheduler,
+        writer: &mut Option<ScrollbackWriter>,
+        config: &Config,
+    ) {
+        let dir = match &config.scrollback_persistence {
+            Some(dir) => dir,
+            None => {
+                scheduler.unschedule(TimerId::PersistScrollback);
+                return;
+            },
+        };
+
+        if writer.is_none() {
+            *writer = Some(ScrollbackWriter::new(persist::file(dir)));
+        }
+
+        if !scheduler.scheduled(TimerId::PersistScrollback) {
+            scheduler.schedule(
+                GlutinEvent::UserEvent(Event::PersistScrollback),
+                SCROLLBACK_PERSISTENCE_INTERVAL,
+                true,
+                TimerId::PersistScrollback,
+            );
+        }
+    }
+
+    /// Update the text blinking timer.
+    ///
+    /// Blinking only runs while blinking text is visible in a focused window.
+    fn update_text_blinking(&mut self, scheduler: &mut Scheduler, is_focused: bool) {
+        let blink_interval = match self.config.ui_config.terminal.blink_interval() {
+            Some(blink_interval) if self.display.has_blinking_text && is_focused => blink_interval,
+            _ => {
+                scheduler.unschedule(TimerId::BlinkText);
+                self.display.blink_phase = 0;
+                return;
+            },
+        };
+
+        // Each tick toggles rapidly blinking text, slowly blinking text toggles every second tick.
+        if !scheduler.scheduled(TimerId::BlinkText) {
+            scheduler.schedule(
+                GlutinEvent::UserEvent(Event::BlinkText),
+                blink_interval / 2,
+                true,
+                TimerId::BlinkText,
+            );
+        }
     }
 
     /// Handle events from glutin.
@@ -1012,9 +1394,40 @@ impl<N: Notify + OnResize> Processor<N> {
                 Event::ConfigReload(path) => Self::reload_config(&path, processor),
                 Event::Scroll(scroll) => processor.ctx.scroll(scroll),
                 Event::BlinkCursor => {
-                    *processor.ctx.cursor_hidden ^= true;
+                    if processor.ctx.terminal.advance_cursor_blink(Instant::now()) {
+                        processor.ctx.terminal.dirty = true;
+                    }
+                },
+                Event::BlinkText => {
+                    *processor.ctx.blink_phase = processor.ctx.blink_phase.wrapping_add(1);
                     processor.ctx.terminal.dirty = true;
                 },
+                Event::RepresentedDirectory => {
+                    #[cfg(target_os = "macos")]
+                    processor.ctx.update_represented_directory();
+                },
+                Event::PersistScrollback => {
+                    let ctx = &mut processor.ctx;
+                    if let Some(writer) = ctx.scrollback_writer.as_ref() {
+                        // Only the snapshot is taken with the terminal locked, the writer
+                        // serializes it on its own thread.
+                        if !writer.write(ctx.terminal.scrollback_snapshot()) {
+                            // Pause persistence until the configuration is reloaded.
+                            *ctx.scrollback_writer = None;
+                            ctx.scheduler.unschedule(TimerId::PersistScrollback);
+                        }
+                    }
+                },
+                #[cfg(unix)]
+                Event::Ipc(action) => match action {
+                    IpcAction::SetTitle { title } => processor.ctx.window.set_title(&title),
+                    IpcAction::SetWindowAlpha { alpha } => {
+                        processor.ctx.config.ui_config.set_background_opacity(alpha);
+                        processor.ctx.terminal.dirty = true;
+                    },
+                    IpcAction::Scroll { lines } => processor.ctx.scroll(Scroll::Delta(lines)),
+                    IpcAction::PasteText { text } => input::paste(&mut processor.ctx, &text),
+                },
                 Event::TerminalEvent(event) => match event {
                     TerminalEvent::Title(title) => {
                         let ui_config = &processor.ctx.config.ui_config;
@@ -1031,7 +1444,9 @@ impl<N: Notify + OnResize> Processor<N> {
                     TerminalEvent::Wakeup => processor.ctx.terminal.dirty = true,
                     TerminalEvent::Bell => {
                         let bell_command = processor.ctx.config.bell().command.as_ref();
-                        let _ = bell_command.map(|cmd| start_daemon(cmd.program(), cmd.args()));
+                        if let Some(cmd) = bell_command {
+                            let _ = start_daemon(cmd.program(), cmd.args(), &[], None, None);
+                        }
                         if processor.ctx.terminal.mode().contains(TermMode::URGENCY_HINTS) {
                             processor.ctx.window.set_urgent(!processor.ctx.terminal.is_focused);
                         }
@@ -1043,11 +1458,16 @@ impl<N: Notify + OnResize> Processor<N> {
                         let text = format(processor.ctx.clipboard.load(clipboard_type).as_str());
                         processor.ctx.write_to_pty(text.into_bytes());
                     },
+                    TerminalEvent::PtyWrite(text) => processor.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => processor.reset_mouse_cursor(),
                     TerminalEvent::Exit => (),
                     TerminalEvent::CursorBlinkingChange(_) => {
                         processor.ctx.update_cursor_blinking();
                     },
+                    TerminalEvent::WorkingDirectory(_) => {
+                        #[cfg(target_os = "macos")]
+                        processor.ctx.schedule_represented_directory_update();
+                    },
                 },
             },
             GlutinEvent::RedrawRequested(_) => processor.ctx.terminal.dirty = true,
@@ -1103,8 +1523,21 @@ impl<N: Notify + OnResize> Processor<N> {
                         }
                     },
                     WindowEvent::DroppedFile(path) => {
-                        let path: String = path.to_string_lossy().into();
-                        processor.ctx.write_to_pty((path + " ").into_bytes());
+                        let config = &processor.ctx.config;
+                        let quoting = config.ui_config.terminal.drop_quoting;
+                        let path = quoting.quote(&path.to_string_lossy(), config.shell.as_ref());
+                        input::paste(&mut processor.ctx, &(path + " "));
+
+                        processor.ctx.mouse.hovered_file = false;
+                        processor.ctx.terminal.dirty = true;
+                    },
+                    WindowEvent::HoveredFile(_) => {
+                        processor.ctx.mouse.hovered_file = true;
+                        processor.ctx.terminal.dirty = true;
+                    },
+                    WindowEvent::HoveredFileCancelled => {
+                        processor.ctx.mouse.hovered_file = false;
+                        processor.ctx.terminal.dirty = true;
                     },
                     WindowEvent::CursorLeft { .. } => {
                         processor.ctx.mouse.inside_text_area = false;
@@ -1118,10 +1551,8 @@ impl<N: Notify + OnResize> Processor<N> {
                     | WindowEvent::ScaleFactorChanged { .. }
                     | WindowEvent::CursorEntered { .. }
                     | WindowEvent::AxisMotion { .. }
-                    | WindowEvent::HoveredFileCancelled
                     | WindowEvent::Destroyed
                     | WindowEvent::ThemeChanged(_)
-                    | WindowEvent::HoveredFile(_)
                     | WindowEvent::Touch(_)
                     | WindowEvent::Moved(_) => (),
                 }
@@ -1194,10 +1625,13 @@ impl<N: Notify + OnResize> Processor<N> {
             processor.ctx.display_update_pending.set_font(font);
         }
 
-        // Update display if padding options were changed.
+        // Update display if padding or window size options were changed.
         let window_config = &processor.ctx.config.ui_config.window;
         if window_config.padding(1.) != config.ui_config.window.padding(1.)
             || window_config.dynamic_padding != config.ui_config.window.dynamic_padding
+            || window_config.resize_increments != config.ui_config.window.resize_increments
+            || window_config.minimum_columns() != config.ui_config.window.minimum_columns()
+            || window_config.minimum_lines() != config.ui_config.window.minimum_lines()
         {
             processor.ctx.display_update_pending.dirty = true;
         }
@@ -1218,11 +1652,43 @@ impl<N: Notify + OnResize> Processor<N> {
         #[cfg(target_os = "macos")]
         set_font_smoothing(config.ui_config.font.use_thin_strokes());
 
+        // Restart text blinking with the new interval.
+        let blink_interval = config.ui_config.terminal.blink_interval();
+        if processor.ctx.config.ui_config.terminal.blink_interval() != blink_interval {
+            processor.ctx.scheduler.unschedule(TimerId::BlinkText);
+        }
+
+        // Resume output if flow control is no longer handled by Alacritty.
+        if config.ui_config.terminal.flow_control != FlowControl::Legacy {
+            processor.ctx.set_output_paused(false);
+        }
+
+        // Discard the history persisted at the previous location.
+        if let Some(dir) = &processor.ctx.config.scrollback_persistence {
+            if config.scrollback_persistence.as_ref() != Some(dir) {
+                if let Some(writer) = processor.ctx.scrollback_writer.take() {
+                    writer.finish();
+                }
+                let _ = persist::clear(&persist::file(dir));
+            }
+        }
+
         *processor.ctx.config = config;
 
+        // Restart scrollback persistence.
+        Self::update_scrollback_persistence(
+            processor.ctx.scheduler,
+            processor.ctx.scrollback_writer,
+            processor.ctx.config,
+        );
+
         // Update cursor blinking.
         processor.ctx.update_cursor_blinking();
 
+        // Show or hide the represented directory.
+        #[cfg(target_os = "macos")]
+        processor.ctx.update_represented_directory();
+
         processor.ctx.terminal.dirty = true;
     }
 
@@ -7,6 +7,7 @@
 
 use std::borrow::Cow;
 use std::cmp::{max, min, Ordering};
+use std::collections::HashSet;
 use std::marker::PhantomData;
 use std::time::{Duration, Instant};
 
@@ -23,6 +24,7 @@ use glutin::platform::macos::EventLoopWindowTargetExtMacOS;
 use glutin::window::CursorIcon;
 
 use alacritty_terminal::ansi::{ClearMode, Handler};
+use alacritty_terminal::config::Program;
 use alacritty_terminal::event::EventListener;
 use alacritty_terminal::grid::{Dimensions, Scroll};
 use alacritty_terminal::index::{Column, Direction, Line, Point, Side};
@@ -32,9 +34,11 @@ use alacritty_terminal::term::{ClipboardType, SizeInfo, Term};
 use alacritty_terminal::vi_mode::ViMotion;
 
 use crate::clipboard::Clipboard;
+use crate::config::terminal::FlowControl;
 use crate::config::{Action, Binding, Config, Key, ViAction};
-use crate::daemon::start_daemon;
+use crate::daemon::{session_env, start_daemon};
 use crate::event::{ClickState, Event, Mouse, TYPING_SEARCH_DELAY};
+use crate::keyboard::{self, KeyEventType, Win32KeyEvent};
 use crate::message_bar::{self, Message};
 use crate::scheduler::{Scheduler, TimerId};
 use crate::url::{Url, Urls};
@@ -74,8 +78,11 @@ pub trait ActionContext<T: EventListener> {
     fn mouse_mut(&mut self) -> &mut Mouse;
     fn mouse(&self) -> &Mouse;
     fn mouse_coords(&self) -> Option<Point>;
+    fn logical_point(&self, point: Point) -> Point;
     fn received_count(&mut self) -> &mut usize;
     fn suppress_chars(&mut self) -> &mut bool;
+    fn reported_keys(&mut self) -> &mut HashSet<VirtualKeyCode>;
+    fn pending_win32_key(&mut self) -> &mut Option<Win32KeyEvent>;
     fn modifiers(&mut self) -> &mut ModifiersState;
     fn scroll(&mut self, scroll: Scroll);
     fn window(&self) -> &Window;
@@ -83,6 +90,11 @@ pub trait ActionContext<T: EventListener> {
     fn terminal(&self) -> &Term<T>;
     fn terminal_mut(&mut self) -> &mut Term<T>;
     fn spawn_new_instance(&mut self);
+    fn dump_scrollback(&mut self);
+    fn open_scrollback_in(&mut self, program: &Program);
+    fn copy_last_command_output(&mut self);
+    fn pipe_last_command_output(&mut self, program: &Program);
+    fn inspect_cell(&mut self, point: Point);
     fn change_font_size(&mut self, delta: f32);
     fn reset_font_size(&mut self);
     fn pop_message(&mut self);
@@ -104,6 +116,7 @@ pub trait ActionContext<T: EventListener> {
     fn search_direction(&self) -> Direction;
     fn search_active(&self) -> bool;
     fn on_typing_start(&mut self);
+    fn set_output_paused(&mut self, paused: bool);
 }
 
 trait Execute<T: EventListener> {
@@ -161,7 +174,7 @@ impl<T: EventListener> Execute<T> for Action {
                 let program = program.program();
                 trace!("Running command {} with args {:?}", program, args);
 
-                start_daemon(program, args);
+                let _ = start_daemon(program, args, &session_env(), None, None);
             },
             Action::ClearSelection => ctx.clear_selection(),
             Action::ToggleViMode => ctx.terminal_mut().toggle_vi_mode(),
@@ -306,15 +319,54 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 term.vi_motion(ViMotion::FirstOccupied);
             },
+            Action::ScrollToPreviousPrompt => scroll_to_prompt(ctx, Direction::Left),
+            Action::ScrollToNextPrompt => scroll_to_prompt(ctx, Direction::Right),
             Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
             Action::ClearLogNotice => ctx.pop_message(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
+            Action::DumpScrollback => ctx.dump_scrollback(),
+            Action::OpenScrollbackIn(ref program) => ctx.open_scrollback_in(program),
+            Action::CopyLastCommandOutput => ctx.copy_last_command_output(),
+            Action::PipeLastCommandOutput(ref program) => ctx.pipe_last_command_output(program),
+            Action::InspectCell => {
+                let point = if ctx.terminal().mode().contains(TermMode::VI) {
+                    Some(ctx.terminal().vi_mode_cursor.point)
+                } else {
+                    ctx.mouse_coords()
+                };
+
+                if let Some(point) = point {
+                    ctx.inspect_cell(point);
+                }
+            },
             Action::ReceiveChar | Action::None => (),
         }
     }
 }
 
-fn paste<T: EventListener, A: ActionContext<T>>(ctx: &mut A, contents: &str) {
+/// Scroll the closest prompt in the given direction to the top of the screen.
+///
+/// The vi mode cursor is moved to the start of the prompt.
+fn scroll_to_prompt<T: EventListener, A: ActionContext<T>>(ctx: &mut A, direction: Direction) {
+    let term = ctx.terminal();
+    let prompt = match term.prompt_line(direction) {
+        Some(prompt) => prompt,
+        None => return,
+    };
+
+    let screen_lines = term.screen_lines().0;
+    let display_offset = prompt.saturating_sub(screen_lines - 1).min(term.history_size());
+    let delta = display_offset as isize - term.grid().display_offset() as isize;
+    ctx.scroll(Scroll::Delta(delta));
+
+    // Move vi mode cursor.
+    let term = ctx.terminal_mut();
+    let line = term.grid().display_offset() + screen_lines - 1 - prompt;
+    term.vi_mode_cursor.point = Point::new(Line(line), Column(0));
+    term.vi_motion(ViMotion::FirstOccupied);
+}
+
+pub fn paste<T: EventListener, A: ActionContext<T>>(ctx: &mut A, contents: &str) {
     if ctx.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
         ctx.write_to_pty(&b"\x1b[200~"[..]);
         ctx.write_to_pty(contents.replace("\x1b", "").into_bytes());
@@ -373,7 +425,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         self.ctx.mouse_mut().y = y;
 
         let inside_text_area = size_info.contains_point(x, y);
-        let point = size_info.pixels_to_coords(x, y);
+        let point = self.ctx.logical_point(size_info.pixels_to_coords(x, y));
         let cell_side = self.get_mouse_side();
 
         let cell_changed =
@@ -511,6 +563,17 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         }
     }
 
+    /// Report an unmodified left click at the current mouse position.
+    fn click_report(&mut self) {
+        if self.ctx.terminal().mode().contains(TermMode::SGR_MOUSE) {
+            self.sgr_mouse_report(0, ElementState::Pressed);
+            self.sgr_mouse_report(0, ElementState::Released);
+        } else {
+            self.normal_mouse_report(0);
+            self.normal_mouse_report(3);
+        }
+    }
+
     fn on_mouse_press(&mut self, button: MouseButton) {
         // Handle mouse mode.
         if !self.ctx.modifiers().shift() && self.ctx.mouse_mode() {
@@ -552,6 +615,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             let mouse = self.ctx.mouse();
             let mut point = self.ctx.size_info().pixels_to_coords(mouse.x, mouse.y);
             point.line = min(point.line, self.ctx.terminal().screen_lines() - 1);
+            let point = self.ctx.logical_point(point);
 
             match button {
                 MouseButton::Left => self.on_left_click(point),
@@ -699,10 +763,18 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
                     .faux_multiplier()
                     .unwrap_or_else(|| self.ctx.config().scrolling.multiplier()),
             );
-            self.ctx.mouse_mut().scroll_px += new_scroll_px * multiplier;
+            let invert = self.ctx.config().scrolling.invert_alt_screen;
 
-            let cmd = if new_scroll_px > 0. { b'A' } else { b'B' };
-            let lines = (self.ctx.mouse().scroll_px / height).abs() as i32;
+            // Drop the remainder when the direction changes, so reversing responds immediately.
+            let scroll_px = &mut self.ctx.mouse_mut().scroll_px;
+            let delta = new_scroll_px * multiplier;
+            if *scroll_px * delta < 0. {
+                *scroll_px = 0.;
+            }
+            *scroll_px += delta;
+
+            let cmd = if (*scroll_px > 0.) != invert { b'A' } else { b'B' };
+            let lines = (*scroll_px / height).abs() as i32;
 
             let mut content = Vec::with_capacity(lines as usize * 3);
             for _ in 0..lines {
@@ -741,6 +813,11 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
     }
 
     pub fn on_focus_change(&mut self, is_focused: bool) {
+        // Releases of keys held while unfocused are never received.
+        if !is_focused {
+            self.ctx.reported_keys().clear();
+        }
+
         if self.ctx.terminal().mode().contains(TermMode::FOCUS_IN_OUT) {
             let chr = if is_focused { "I" } else { "O" };
 
@@ -852,10 +929,107 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
                 *self.ctx.received_count() = 0;
                 self.process_key_bindings(input);
             },
-            ElementState::Released => (),
+            ElementState::Released => {
+                if self.win32_key_input(input) {
+                    return;
+                }
+
+                if let Some(key) = input.virtual_keycode {
+                    if self.ctx.reported_keys().remove(&key) {
+                        self.kitty_key_input(key, KeyEventType::Release);
+                    }
+                }
+            },
         }
     }
 
+    /// Report a key event using the kitty keyboard protocol.
+    ///
+    /// Returns `false` if the event is not reported with the active protocol flags.
+    fn kitty_key_input(&mut self, key: VirtualKeyCode, event: KeyEventType) -> bool {
+        let mods = *self.ctx.modifiers();
+        let mode = *self.ctx.terminal().mode();
+        if !mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL) || mode.contains(TermMode::VI) {
+            return false;
+        }
+
+        let sequence = match keyboard::kitty_sequence(key, mods, mode, event) {
+            Some(sequence) => sequence,
+            None => return false,
+        };
+
+        if event != KeyEventType::Release {
+            self.ctx.on_typing_start();
+            self.ctx.clear_selection();
+            self.ctx.scroll(Scroll::Bottom);
+        }
+        self.ctx.write_to_pty(sequence.into_bytes());
+
+        true
+    }
+
+    /// Report a key event using win32-input-mode.
+    ///
+    /// Returns `false` if win32-input-mode is not active.
+    fn win32_key_input(&mut self, input: KeyboardInput) -> bool {
+        let mode = *self.ctx.terminal().mode();
+        if !mode.contains(TermMode::WIN32_INPUT) || mode.contains(TermMode::VI) {
+            return false;
+        }
+
+        // Report presses which did not produce any text without it.
+        if let Some(pending) = self.ctx.pending_win32_key().take() {
+            self.ctx.write_to_pty(pending.sequence(None).into_bytes());
+        }
+
+        // Winit reports every repeat as a separate press.
+        let event = Win32KeyEvent {
+            key: input.virtual_keycode,
+            scancode: input.scancode,
+            mods: *self.ctx.modifiers(),
+            pressed: input.state == ElementState::Pressed,
+            repeat_count: 1,
+        };
+
+        if event.pressed && !event.is_modifier() {
+            self.ctx.on_typing_start();
+            self.ctx.clear_selection();
+            self.ctx.scroll(Scroll::Bottom);
+        }
+
+        // Presses are reported together with their text once it is received.
+        if event.pressed && event.produces_text() {
+            *self.ctx.pending_win32_key() = Some(event);
+        } else {
+            self.ctx.write_to_pty(event.sequence(None).into_bytes());
+        }
+
+        true
+    }
+
+    /// Report a key press using xterm's modifyOtherKeys.
+    ///
+    /// Returns `false` if the key keeps its regular encoding.
+    fn modify_other_keys_input(&mut self, key: VirtualKeyCode) -> bool {
+        let mods = *self.ctx.modifiers();
+        let mode = *self.ctx.terminal().mode();
+        if mode.contains(TermMode::VI) {
+            return false;
+        }
+
+        let sequence = match keyboard::modify_other_keys_sequence(key, mods, mode) {
+            Some(sequence) => sequence,
+            None => return false,
+        };
+
+        self.ctx.on_typing_start();
+        self.ctx.clear_selection();
+        self.ctx.scroll(Scroll::Bottom);
+        self.ctx.write_to_pty(sequence.into_bytes());
+
+        true
+    }
+
     /// Modifier state change.
     pub fn modifiers_input(&mut self, modifiers: ModifiersState) {
         *self.ctx.modifiers() = modifiers;
@@ -875,6 +1049,13 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
 
     /// Process a received character.
     pub fn received_char(&mut self, c: char) {
+        // Text of a key press reported using win32-input-mode.
+        if let Some(pending) = self.ctx.pending_win32_key().take() {
+            *self.ctx.suppress_chars() = false;
+            self.ctx.write_to_pty(pending.sequence(Some(c)).into_bytes());
+            return;
+        }
+
         let suppress_chars = *self.ctx.suppress_chars();
         let search_active = self.ctx.search_active();
         if suppress_chars || self.ctx.terminal().mode().contains(TermMode::VI) || search_active {
@@ -893,6 +1074,15 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             return;
         }
 
+        // Handle XON/XOFF without passing them to the child.
+        if self.ctx.config().ui_config.terminal.flow_control == FlowControl::Legacy {
+            match c {
+                '\x13' => return self.ctx.set_output_paused(true),
+                '\x11' => return self.ctx.set_output_paused(false),
+                _ => (),
+            }
+        }
+
         self.ctx.on_typing_start();
 
         self.ctx.scroll(Scroll::Bottom);
@@ -945,6 +1135,26 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             }
         }
 
+        // Encode keys without bindings using win32-input-mode, the kitty keyboard protocol or
+        // modifyOtherKeys.
+        if !suppress_chars.unwrap_or(false) && self.win32_key_input(input) {
+            suppress_chars = Some(true);
+        } else if let (None, Some(key)) = (suppress_chars, input.virtual_keycode) {
+            // Keys are repeated by sending further presses without a release.
+            let event = if self.ctx.reported_keys().contains(&key) {
+                KeyEventType::Repeat
+            } else {
+                KeyEventType::Press
+            };
+
+            if self.kitty_key_input(key, event) {
+                self.ctx.reported_keys().insert(key);
+                suppress_chars = Some(true);
+            } else if self.modify_other_keys_input(key) {
+                suppress_chars = Some(true);
+            }
+        }
+
         // Don't suppress char if no bindings were triggered.
         *self.ctx.suppress_chars() = suppress_chars.unwrap_or(false);
     }
@@ -972,6 +1182,14 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             }
 
             if binding.is_triggered_by(mode, mods, &button) {
+                // Move the application's cursor to the click position before pasting.
+                if mouse_mode
+                    && binding.action == Action::PasteSelection
+                    && self.ctx.config().ui_config.mouse.paste_at_click
+                {
+                    self.click_report();
+                }
+
                 binding.execute(&mut self.ctx);
             }
         }
@@ -1100,6 +1318,7 @@ mod tests {
 
     use glutin::event::{Event as GlutinEvent, VirtualKeyCode, WindowEvent};
 
+    use alacritty_terminal::ansi::Mode;
     use alacritty_terminal::event::Event as TerminalEvent;
     use alacritty_terminal::selection::Selection;
 
@@ -1123,12 +1342,18 @@ mod tests {
         pub message_buffer: &'a mut MessageBuffer,
         pub received_count: usize,
         pub suppress_chars: bool,
+        pub reported_keys: HashSet<VirtualKeyCode>,
+        pub pending_win32_key: Option<Win32KeyEvent>,
         pub modifiers: ModifiersState,
+        pub pty_writes: Vec<u8>,
+        pub mouse_mode: bool,
         config: &'a Config,
     }
 
     impl<'a, T: EventListener> super::ActionContext<T> for ActionContext<'a, T> {
-        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, _val: B) {}
+        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, val: B) {
+            self.pty_writes.extend_from_slice(&val.into());
+        }
 
         fn update_selection(&mut self, _point: Point, _side: Side) {}
 
@@ -1142,6 +1367,16 @@ mod tests {
 
         fn spawn_new_instance(&mut self) {}
 
+        fn dump_scrollback(&mut self) {}
+
+        fn open_scrollback_in(&mut self, _program: &Program) {}
+
+        fn copy_last_command_output(&mut self) {}
+
+        fn pipe_last_command_output(&mut self, _program: &Program) {}
+
+        fn inspect_cell(&mut self, _point: Point) {}
+
         fn change_font_size(&mut self, _delta: f32) {}
 
         fn reset_font_size(&mut self) {}
@@ -1199,8 +1434,12 @@ mod tests {
             }
         }
 
+        fn logical_point(&self, point: Point) -> Point {
+            point
+        }
+
         fn mouse_mode(&self) -> bool {
-            false
+            self.mouse_mode
         }
 
         #[inline]
@@ -1221,6 +1460,14 @@ mod tests {
             &mut self.suppress_chars
         }
 
+        fn reported_keys(&mut self) -> &mut HashSet<VirtualKeyCode> {
+            &mut self.reported_keys
+        }
+
+        fn pending_win32_key(&mut self) -> &mut Option<Win32KeyEvent> {
+            &mut self.pending_win32_key
+        }
+
         fn modifiers(&mut self) -> &mut ModifiersState {
             &mut self.modifiers
         }
@@ -1268,6 +1515,10 @@ mod tests {
         fn on_typing_start(&mut self) {
             unimplemented!();
         }
+
+        fn set_output_paused(&mut self, _paused: bool) {
+            unimplemented!();
+        }
     }
 
     macro_rules! test_clickstate {
@@ -1290,6 +1541,7 @@ mod tests {
                     },
                     hide_when_typing: false,
                     url: Default::default(),
+                    paste_at_click: false,
                 };
 
                 let size = SizeInfo::new(
@@ -1321,8 +1573,12 @@ mod tests {
                     clipboard: &mut clipboard,
                     received_count: 0,
                     suppress_chars: false,
+                    reported_keys: HashSet::new(),
+                    pending_win32_key: None,
                     modifiers: Default::default(),
                     message_buffer: &mut message_buffer,
+                    pty_writes: Vec::new(),
+                    mouse_mode: false,
                     config: &cfg,
                 };
 
@@ -1461,6 +1717,122 @@ mod tests {
         end_state: ClickState::Click,
     }
 
+    /// Run input through a processor, evaluating to the bytes written to the PTY.
+    macro_rules! test_pty_writes {
+        {
+            config: $cfg:expr,
+            modes: [$($mode:expr),*],
+            mouse: $mouse:expr,
+            modifiers: $modifiers:expr,
+            mouse_mode: $mouse_mode:expr,
+            input: |$processor:ident| $input:block,
+        } => {{
+            let cfg: Config = $cfg;
+
+            let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0., 0., false);
+
+            let mut clipboard = Clipboard::new_nop();
+
+            let mut terminal = Term::new(&cfg, size, MockEventProxy);
+            $(terminal.set_mode($mode);)*
+
+            let mut mouse: Mouse = $mouse;
+
+            let mut selection = None;
+
+            let mut message_buffer = MessageBuffer::new();
+
+            let context = ActionContext {
+                terminal: &mut terminal,
+                selection: &mut selection,
+                mouse: &mut mouse,
+                size_info: &size,
+                clipboard: &mut clipboard,
+                received_count: 0,
+                suppress_chars: false,
+                reported_keys: HashSet::new(),
+                pending_win32_key: None,
+                modifiers: $modifiers,
+                message_buffer: &mut message_buffer,
+                pty_writes: Vec::new(),
+                mouse_mode: $mouse_mode,
+                config: &cfg,
+            };
+
+            let mut $processor = Processor::new(context, &None);
+            $input
+
+            $processor.ctx.pty_writes
+        }};
+    }
+
+    fn paste_with_mouse_mode(paste_at_click: bool) -> Vec<u8> {
+        let mut cfg = Config::default();
+        cfg.ui_config.mouse.paste_at_click = paste_at_click;
+
+        test_pty_writes! {
+            config: cfg,
+            modes: [Mode::ReportMouseClicks, Mode::SgrMouse, Mode::BracketedPaste],
+            mouse: Mouse { line: Line(1), column: Column(2), ..Mouse::default() },
+            modifiers: ModifiersState::SHIFT,
+            mouse_mode: true,
+            input: |processor| {
+                processor.mouse_input(ElementState::Pressed, MouseButton::Middle);
+            },
+        }
+    }
+
+    #[test]
+    fn paste_at_click_reports_click_before_paste() {
+        let written = paste_with_mouse_mode(true);
+        assert_eq!(written, b"\x1b[<0;3;2M\x1b[<0;3;2m\x1b[200~\x1b[201~".to_vec());
+    }
+
+    #[test]
+    fn paste_without_paste_at_click() {
+        let written = paste_with_mouse_mode(false);
+        assert_eq!(written, b"\x1b[200~\x1b[201~".to_vec());
+    }
+
+    fn alt_screen_scroll(invert_alt_screen: bool, deltas: &[f64]) -> Vec<u8> {
+        let mut cfg = Config::default();
+        cfg.scrolling.invert_alt_screen = invert_alt_screen;
+
+        test_pty_writes! {
+            config: cfg,
+            modes: [Mode::SwapScreenAndSetRestoreCursor],
+            mouse: Mouse::default(),
+            modifiers: Default::default(),
+            mouse_mode: false,
+            input: |processor| {
+                for delta in deltas {
+                    let delta = MouseScrollDelta::PixelDelta(PhysicalPosition::new(0., *delta));
+                    processor.mouse_wheel_input(delta, TouchPhase::Moved);
+                }
+            },
+        }
+    }
+
+    #[test]
+    fn alt_screen_scroll_direction_change() {
+        // With the default multiplier of 3, one pixel scrolls a full line of 3 pixels.
+        let written = alt_screen_scroll(false, &[0.5, 0.4, -1., 0.9, 2.]);
+        assert_eq!(written, b"\x1bOB\x1bOA\x1bOA".to_vec());
+
+        // A remainder in the previous direction does not delay the reversal.
+        let written = alt_screen_scroll(false, &[-0.9, 0.5, 0.5]);
+        assert_eq!(written, b"\x1bOA".to_vec());
+    }
+
+    #[test]
+    fn alt_screen_scroll_inverted() {
+        let written = alt_screen_scroll(true, &[0.5, 0.4, -1., 0.9, 2.]);
+        assert_eq!(written, b"\x1bOA\x1bOB\x1bOB".to_vec());
+
+        let written = alt_screen_scroll(true, &[-0.9, 0.5, 0.5]);
+        assert_eq!(written, b"\x1bOB".to_vec());
+    }
+
     test_process_binding! {
         name: process_binding_nomode_shiftmod_require_shift,
         binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE },
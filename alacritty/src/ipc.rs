@@ -0,0 +1,170 @@
+//! Control of running instances through a Unix socket.
+//!
+//! When enabled, every instance listens on `$XDG_RUNTIME_DIR/alacritty-$PID.sock`, falling back to
+//! the temporary directory when `XDG_RUNTIME_DIR` is not set. The socket is only accessible by the
+//! user running Alacritty. The path is exported to child processes as `ALACRITTY_SOCKET`.
+//!
+//! Clients write one JSON message per line, for example:
+//!
+//! ```json
+//! {"version": 1, "action": "PasteText", "text": "echo hello\n"}
+//! ```
+
+use std::env;
+use std::fs::{self, Permissions};
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use alacritty_terminal::thread;
+
+use crate::event::{Event, EventProxy};
+
+/// Version of the message format understood by this instance.
+pub const IPC_VERSION: u32 = 1;
+
+/// Environment variable exposing the socket path to child processes.
+const SOCKET_ENV: &str = "ALACRITTY_SOCKET";
+
+/// Maximum time a client may stall before its connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Message sent by an IPC client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IpcMessage {
+    /// Version of the message format.
+    pub version: u32,
+
+    /// Action which should be performed.
+    #[serde(flatten)]
+    pub action: IpcAction,
+}
+
+/// Actions which can be requested through the IPC socket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action")]
+pub enum IpcAction {
+    /// Change the window title.
+    SetTitle { title: String },
+
+    /// Change the background opacity, from 0.0 to 1.0.
+    SetWindowAlpha { alpha: f32 },
+
+    /// Scroll the viewport, positive values scroll up into the history.
+    Scroll { lines: isize },
+
+    /// Paste text into the terminal.
+    PasteText { text: String },
+}
+
+/// Start listening for IPC messages, returning the path of the socket.
+pub fn spawn_ipc_socket(event_proxy: EventProxy) -> Option<PathBuf> {
+    let path = socket_path();
+
+    let listener = match bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!("Unable to create IPC socket at {:?}: {}", path, err);
+            return None;
+        },
+    };
+
+    env::set_var(SOCKET_ENV, &path);
+
+    thread::spawn_named("IPC listener", move || {
+        listen(listener, |action| event_proxy.send_event(Event::Ipc(action)))
+    });
+
+    Some(path)
+}
+
+/// Create a socket which only the current user can connect to.
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    // Remove sockets left behind by a previous process with the same PID.
+    let _ = fs::remove_file(path);
+
+    let listener = UnixListener::bind(path)?;
+
+    // The temporary directory is shared with other users, who must not be able to send input.
+    if let Err(err) = fs::set_permissions(path, Permissions::from_mode(0o600)) {
+        let _ = fs::remove_file(path);
+        return Err(err);
+    }
+
+    Ok(listener)
+}
+
+/// Path of the socket for the current process.
+fn socket_path() -> PathBuf {
+    let dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(env::temp_dir);
+    dir.join(format!("alacritty-{}.sock", process::id()))
+}
+
+/// Forward all messages received on a socket to a handler.
+///
+/// Connections are handled one at a time.
+fn listen<F: FnMut(IpcAction)>(listener: UnixListener, mut handler: F) {
+    for stream in listener.incoming().filter_map(Result::ok) {
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            match serde_json::from_str::<IpcMessage>(&line) {
+                Ok(message) if message.version == IPC_VERSION => handler(message.action),
+                Ok(message) => warn!("Unsupported IPC message version {}", message.version),
+                Err(err) => warn!("Invalid IPC message {:?}: {}", line, err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+
+    #[test]
+    fn deserialize_message() {
+        let message: IpcMessage =
+            serde_json::from_str(r#"{"version": 1, "action": "Scroll", "lines": -3}"#).unwrap();
+        assert_eq!(message, IpcMessage { version: 1, action: IpcAction::Scroll { lines: -3 } });
+
+        assert!(serde_json::from_str::<IpcMessage>(r#"{"version": 1, "action": "Quit"}"#).is_err());
+    }
+
+    #[test]
+    fn paste_through_socket() {
+        let path = env::temp_dir().join(format!("alacritty-ipc-test-{}.sock", process::id()));
+        let listener = bind(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || listen(listener, |action| tx.send(action).unwrap()));
+
+        let message = IpcMessage {
+            version: IPC_VERSION,
+            action: IpcAction::PasteText { text: String::from("echo hello\n") },
+        };
+        let mut stream = UnixStream::connect(&path).unwrap();
+        writeln!(stream, "{{\"version\": 0, \"action\": \"SetTitle\", \"title\": \"\"}}").unwrap();
+        writeln!(stream, "{}", serde_json::to_string(&message).unwrap()).unwrap();
+
+        let action = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(action, message.action);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,810 @@
+//! Keyboard input encoding for the kitty keyboard protocol and xterm's modifyOtherKeys.
+//!
+//! Applications opt into the protocol's progressive enhancements using `CSI > flags u`. Keys which
+//! are not affected by any of the active enhancements keep their legacy encoding.
+//!
+//! With modifyOtherKeys enabled through `CSI > 4 ; level m`, key presses with modifiers which are
+//! otherwise ambiguous are sent as `CSI 27 ; modifiers ; codepoint ~`.
+//!
+//! Console applications running under ConPTY can enable win32-input-mode using `CSI ? 9001 h`,
+//! which reports every key press and release as `CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _`.
+
+use glutin::event::{ModifiersState, VirtualKeyCode};
+
+use alacritty_terminal::term::TermMode;
+
+/// Kitty key code of F13, the following function keys are numbered sequentially.
+const F13_KEY_CODE: u32 = 57376;
+
+/// Legacy escape sequences for F13 to F20.
+const F13_TO_F20_LEGACY: [u32; 8] = [25, 26, 28, 29, 31, 32, 33, 34];
+
+/// Control key state flags of win32 key events.
+const RIGHT_ALT_PRESSED: u32 = 0x0001;
+const LEFT_ALT_PRESSED: u32 = 0x0002;
+const RIGHT_CTRL_PRESSED: u32 = 0x0004;
+const LEFT_CTRL_PRESSED: u32 = 0x0008;
+const SHIFT_PRESSED: u32 = 0x0010;
+const ENHANCED_KEY: u32 = 0x0100;
+
+/// Type of a key event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyEventType {
+    Press,
+    Repeat,
+    Release,
+}
+
+/// Encoding of a key, before its modifiers and event type are added.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum KeySequence {
+    /// `CSI number ; modifiers terminator`.
+    Csi(u32, char),
+
+    /// `SS3 terminator` without modifiers, otherwise `CSI 1 ; modifiers terminator`.
+    Ss3(char),
+
+    /// Legacy sequence which cannot carry modifiers or event types.
+    Legacy(&'static str),
+}
+
+/// Escape sequence for a key event with the kitty keyboard protocol enabled.
+///
+/// Returns `None` when the event is not reported, or when the key should be reported through the
+/// text it produces instead.
+pub fn kitty_sequence(
+    key: VirtualKeyCode,
+    mods: ModifiersState,
+    mode: TermMode,
+    event: KeyEventType,
+) -> Option<String> {
+    // Without event types, repeats are reported like presses and releases are not reported.
+    let event_type = match event {
+        KeyEventType::Release if !mode.contains(TermMode::REPORT_EVENT_TYPES) => return None,
+        KeyEventType::Repeat if mode.contains(TermMode::REPORT_EVENT_TYPES) => 2,
+        KeyEventType::Release => 3,
+        _ => 1,
+    };
+
+    // Enter, Tab and Backspace only report releases when all keys are reported as escapes.
+    let all_keys = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let text_control =
+        matches!(key, VirtualKeyCode::Return | VirtualKeyCode::Tab | VirtualKeyCode::Back);
+    if event == KeyEventType::Release && text_control && !all_keys {
+        return None;
+    }
+
+    let modifiers = modifiers_param(mods);
+    let plain = modifiers == 1 && event_type == 1;
+    let (number, terminator) = match key_sequence(key, mods, mode, plain)? {
+        KeySequence::Legacy(_) if event == KeyEventType::Release => return None,
+        KeySequence::Legacy(sequence) => return Some(sequence.into()),
+        KeySequence::Ss3(terminator) if plain => return Some(format!("\x1bO{}", terminator)),
+        KeySequence::Ss3(terminator) => (1, terminator),
+        KeySequence::Csi(number, terminator) => (number, terminator),
+    };
+
+    Some(match (modifiers, event_type) {
+        (1, 1) if terminator.is_ascii_uppercase() => format!("\x1b[{}", terminator),
+        (1, 1) => format!("\x1b[{}{}", number, terminator),
+        (_, 1) => format!("\x1b[{};{}{}", number, modifiers, terminator),
+        _ => format!("\x1b[{};{}:{}{}", number, modifiers, event_type, terminator),
+    })
+}
+
+/// Escape sequence for a key press with xterm's modifyOtherKeys enabled.
+///
+/// Returns `None` when the key keeps its regular encoding. The kitty keyboard protocol takes
+/// precedence while any of its enhancements are active.
+pub fn modify_other_keys_sequence(
+    key: VirtualKeyCode,
+    mods: ModifiersState,
+    mode: TermMode,
+) -> Option<String> {
+    let all_keys = mode.contains(TermMode::MODIFY_ALL_KEYS);
+    if mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL)
+        || !(all_keys || mode.contains(TermMode::MODIFY_OTHER_KEYS))
+    {
+        return None;
+    }
+
+    let code = match key {
+        VirtualKeyCode::Return if all_keys && !mods.is_empty() => 13,
+        VirtualKeyCode::Tab if all_keys && !mods.is_empty() => 9,
+        VirtualKeyCode::Back if all_keys && !mods.is_empty() => 127,
+        VirtualKeyCode::Escape if all_keys && !mods.is_empty() => 27,
+        _ => {
+            let c = text_key(key)?;
+
+            // Shift and Alt alone only change the text or prefix it with an escape.
+            let base = mods - ModifiersState::ALT;
+            if base.is_empty() || base == ModifiersState::SHIFT {
+                return None;
+            }
+
+            if !all_keys && base == ModifiersState::CTRL && has_control_code(key) {
+                return None;
+            }
+
+            if mods.shift() {
+                c.to_ascii_uppercase() as u32
+            } else {
+                c as u32
+            }
+        },
+    };
+
+    Some(format!("\x1b[27;{};{}~", modifiers_param(mods), code))
+}
+
+/// Key event reported using win32-input-mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Win32KeyEvent {
+    pub key: Option<VirtualKeyCode>,
+    pub scancode: u32,
+    pub mods: ModifiersState,
+    pub pressed: bool,
+    pub repeat_count: u16,
+}
+
+impl Win32KeyEvent {
+    /// Check if a key press should be reported together with the text it produces.
+    ///
+    /// Keys which are not known to produce text are reported immediately, anything else waits
+    /// for its text to arrive.
+    pub fn produces_text(&self) -> bool {
+        let key = match self.key {
+            Some(key) => key,
+            None => return true,
+        };
+
+        match key {
+            VirtualKeyCode::Return
+            | VirtualKeyCode::Tab
+            | VirtualKeyCode::Back
+            | VirtualKeyCode::Escape
+            | VirtualKeyCode::NumpadAdd
+            | VirtualKeyCode::NumpadDecimal
+            | VirtualKeyCode::NumpadDivide
+            | VirtualKeyCode::NumpadEnter
+            | VirtualKeyCode::NumpadMultiply
+            | VirtualKeyCode::NumpadSubtract => true,
+            _ => text_key(key).is_some() || numpad_digit(key).is_some(),
+        }
+    }
+
+    /// Check if the event is for a modifier key.
+    pub fn is_modifier(&self) -> bool {
+        self.key.map_or(false, |key| matches!(virtual_key_code(key), 0x10..=0x12 | 0x5b | 0x5c))
+    }
+
+    /// Escape sequence `CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _` of the event.
+    ///
+    /// Text outside of the basic multilingual plane is reported as one sequence per UTF-16 code
+    /// unit, like the console's own input records.
+    pub fn sequence(&self, text: Option<char>) -> String {
+        let key = self.key.map_or(0, virtual_key_code);
+        let pressed = if self.pressed { 1 } else { 0 };
+        let state = self.control_key_state();
+
+        let mut units = [0; 2];
+        let units = match text {
+            Some(c) => &*c.encode_utf16(&mut units),
+            None => &[0][..],
+        };
+
+        units
+            .iter()
+            .map(|unit| {
+                format!(
+                    "\x1b[{};{};{};{};{};{}_",
+                    key, self.scancode, unit, pressed, state, self.repeat_count
+                )
+            })
+            .collect()
+    }
+
+    /// Win32 control key state of the modifiers and key.
+    fn control_key_state(&self) -> u32 {
+        let mut state = 0;
+        if self.mods.shift() {
+            state |= SHIFT_PRESSED;
+        }
+        if self.mods.ctrl() {
+            state |= LEFT_CTRL_PRESSED;
+        }
+        if self.mods.alt() {
+            state |= LEFT_ALT_PRESSED;
+        }
+
+        match self.key {
+            // The right modifier keys report their own side.
+            Some(VirtualKeyCode::RControl) => {
+                state = (state & !LEFT_CTRL_PRESSED) | RIGHT_CTRL_PRESSED | ENHANCED_KEY
+            },
+            Some(VirtualKeyCode::RAlt) => {
+                state = (state & !LEFT_ALT_PRESSED) | RIGHT_ALT_PRESSED | ENHANCED_KEY
+            },
+            Some(key) if is_enhanced_key(key) => state |= ENHANCED_KEY,
+            _ => (),
+        }
+
+        state
+    }
+}
+
+/// Check if the default bindings provide the legacy escape sequence for a key.
+///
+/// These bindings are replaced by the kitty keyboard encoding while the protocol is enabled.
+pub fn is_functional_key(key: VirtualKeyCode) -> bool {
+    match key {
+        VirtualKeyCode::Tab | VirtualKeyCode::Back => true,
+        _ => functional_sequence(key, TermMode::empty(), false, true).is_some(),
+    }
+}
+
+/// Encoding of a key for the active protocol flags.
+///
+/// Keys without modifiers which are pressed, not repeated or released, are `plain`.
+fn key_sequence(
+    key: VirtualKeyCode,
+    mods: ModifiersState,
+    mode: TermMode,
+    plain: bool,
+) -> Option<KeySequence> {
+    let all_keys = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let disambiguate = all_keys || mode.contains(TermMode::DISAMBIGUATE_ESC_CODES);
+
+    if let Some(c) = text_key(key) {
+        let modified = mods.ctrl() || mods.alt() || mods.logo();
+        return if all_keys || (disambiguate && modified) {
+            Some(KeySequence::Csi(c as u32, 'u'))
+        } else {
+            None
+        };
+    }
+
+    match key {
+        VirtualKeyCode::Escape if disambiguate => Some(KeySequence::Csi(27, 'u')),
+        VirtualKeyCode::Return | VirtualKeyCode::Tab | VirtualKeyCode::Back
+            if all_keys || (disambiguate && !mods.is_empty()) =>
+        {
+            let code = match key {
+                VirtualKeyCode::Return => 13,
+                VirtualKeyCode::Tab => 9,
+                _ => 127,
+            };
+            Some(KeySequence::Csi(code, 'u'))
+        },
+        VirtualKeyCode::Tab if mods == ModifiersState::SHIFT => Some(KeySequence::Legacy("\x1b[Z")),
+        VirtualKeyCode::Back if mods.is_empty() || mods == ModifiersState::SHIFT => {
+            Some(KeySequence::Legacy("\x7f"))
+        },
+        VirtualKeyCode::Back if mods == ModifiersState::ALT => {
+            Some(KeySequence::Legacy("\x1b\x7f"))
+        },
+        _ => functional_sequence(key, mode, disambiguate, plain),
+    }
+}
+
+/// Encoding of cursor, editing, and function keys.
+fn functional_sequence(
+    key: VirtualKeyCode,
+    mode: TermMode,
+    disambiguate: bool,
+    plain: bool,
+) -> Option<KeySequence> {
+    let all_keys = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let app_cursor = mode.contains(TermMode::APP_CURSOR);
+
+    // Function keys always use SS3, cursor keys only in application cursor mode.
+    let ss3 = |terminator, ss3| {
+        if ss3 && !all_keys {
+            KeySequence::Ss3(terminator)
+        } else {
+            KeySequence::Csi(1, terminator)
+        }
+    };
+
+    let sequence = match key {
+        VirtualKeyCode::Up => ss3('A', app_cursor),
+        VirtualKeyCode::Down => ss3('B', app_cursor),
+        VirtualKeyCode::Right => ss3('C', app_cursor),
+        VirtualKeyCode::Left => ss3('D', app_cursor),
+        VirtualKeyCode::End => ss3('F', app_cursor),
+        VirtualKeyCode::Home => ss3('H', app_cursor),
+        VirtualKeyCode::F1 => ss3('P', true),
+        VirtualKeyCode::F2 => ss3('Q', true),
+        // The modified legacy F3 sequence is indistinguishable from a cursor position report.
+        VirtualKeyCode::F3 if disambiguate && (all_keys || !plain) => KeySequence::Csi(13, '~'),
+        VirtualKeyCode::F3 => ss3('R', true),
+        VirtualKeyCode::F4 => ss3('S', true),
+        VirtualKeyCode::Insert => KeySequence::Csi(2, '~'),
+        VirtualKeyCode::Delete => KeySequence::Csi(3, '~'),
+        VirtualKeyCode::PageUp => KeySequence::Csi(5, '~'),
+        VirtualKeyCode::PageDown => KeySequence::Csi(6, '~'),
+        VirtualKeyCode::F5 => KeySequence::Csi(15, '~'),
+        VirtualKeyCode::F6 => KeySequence::Csi(17, '~'),
+        VirtualKeyCode::F7 => KeySequence::Csi(18, '~'),
+        VirtualKeyCode::F8 => KeySequence::Csi(19, '~'),
+        VirtualKeyCode::F9 => KeySequence::Csi(20, '~'),
+        VirtualKeyCode::F10 => KeySequence::Csi(21, '~'),
+        VirtualKeyCode::F11 => KeySequence::Csi(23, '~'),
+        VirtualKeyCode::F12 => KeySequence::Csi(24, '~'),
+        _ => {
+            let index = high_function_key(key)?;
+            if disambiguate {
+                KeySequence::Csi(F13_KEY_CODE + index, 'u')
+            } else {
+                KeySequence::Csi(*F13_TO_F20_LEGACY.get(index as usize)?, '~')
+            }
+        },
+    };
+
+    Some(sequence)
+}
+
+/// Index of a function key starting at F13.
+fn high_function_key(key: VirtualKeyCode) -> Option<u32> {
+    let index = match key {
+        VirtualKeyCode::F13 => 0,
+        VirtualKeyCode::F14 => 1,
+        VirtualKeyCode::F15 => 2,
+        VirtualKeyCode::F16 => 3,
+        VirtualKeyCode::F17 => 4,
+        VirtualKeyCode::F18 => 5,
+        VirtualKeyCode::F19 => 6,
+        VirtualKeyCode::F20 => 7,
+        VirtualKeyCode::F21 => 8,
+        VirtualKeyCode::F22 => 9,
+        VirtualKeyCode::F23 => 10,
+        VirtualKeyCode::F24 => 11,
+        _ => return None,
+    };
+    Some(index)
+}
+
+/// Win32 virtual key code of a key, `0` for keys without one.
+fn virtual_key_code(key: VirtualKeyCode) -> u32 {
+    if let Some(digit) = numpad_digit(key) {
+        return 0x60 + digit;
+    }
+
+    if let Some(index) = high_function_key(key) {
+        return 0x7c + index;
+    }
+
+    match key {
+        VirtualKeyCode::Back => 0x08,
+        VirtualKeyCode::Tab => 0x09,
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => 0x0d,
+        VirtualKeyCode::LShift | VirtualKeyCode::RShift => 0x10,
+        VirtualKeyCode::LControl | VirtualKeyCode::RControl => 0x11,
+        VirtualKeyCode::LAlt | VirtualKeyCode::RAlt => 0x12,
+        VirtualKeyCode::Pause => 0x13,
+        VirtualKeyCode::Capital => 0x14,
+        VirtualKeyCode::Escape => 0x1b,
+        VirtualKeyCode::Space => 0x20,
+        VirtualKeyCode::PageUp => 0x21,
+        VirtualKeyCode::PageDown => 0x22,
+        VirtualKeyCode::End => 0x23,
+        VirtualKeyCode::Home => 0x24,
+        VirtualKeyCode::Left => 0x25,
+        VirtualKeyCode::Up => 0x26,
+        VirtualKeyCode::Right => 0x27,
+        VirtualKeyCode::Down => 0x28,
+        VirtualKeyCode::Snapshot => 0x2c,
+        VirtualKeyCode::Insert => 0x2d,
+        VirtualKeyCode::Delete => 0x2e,
+        VirtualKeyCode::LWin => 0x5b,
+        VirtualKeyCode::RWin => 0x5c,
+        VirtualKeyCode::Apps => 0x5d,
+        VirtualKeyCode::NumpadMultiply => 0x6a,
+        VirtualKeyCode::NumpadAdd => 0x6b,
+        VirtualKeyCode::NumpadSubtract => 0x6d,
+        VirtualKeyCode::NumpadDecimal => 0x6e,
+        VirtualKeyCode::NumpadDivide => 0x6f,
+        VirtualKeyCode::F1 => 0x70,
+        VirtualKeyCode::F2 => 0x71,
+        VirtualKeyCode::F3 => 0x72,
+        VirtualKeyCode::F4 => 0x73,
+        VirtualKeyCode::F5 => 0x74,
+        VirtualKeyCode::F6 => 0x75,
+        VirtualKeyCode::F7 => 0x76,
+        VirtualKeyCode::F8 => 0x77,
+        VirtualKeyCode::F9 => 0x78,
+        VirtualKeyCode::F10 => 0x79,
+        VirtualKeyCode::F11 => 0x7a,
+        VirtualKeyCode::F12 => 0x7b,
+        VirtualKeyCode::Numlock => 0x90,
+        VirtualKeyCode::Scroll => 0x91,
+        // Punctuation uses the OEM key codes of the US layout.
+        VirtualKeyCode::Semicolon => 0xba,
+        VirtualKeyCode::Equals | VirtualKeyCode::Plus => 0xbb,
+        VirtualKeyCode::Comma => 0xbc,
+        VirtualKeyCode::Minus => 0xbd,
+        VirtualKeyCode::Period => 0xbe,
+        VirtualKeyCode::Slash => 0xbf,
+        VirtualKeyCode::Grave => 0xc0,
+        VirtualKeyCode::LBracket => 0xdb,
+        VirtualKeyCode::Backslash => 0xdc,
+        VirtualKeyCode::RBracket => 0xdd,
+        VirtualKeyCode::Apostrophe => 0xde,
+        // Digits and letters share their ASCII codes.
+        _ => match text_key(key) {
+            Some(c) if c.is_ascii_alphanumeric() => c.to_ascii_uppercase() as u32,
+            _ => 0,
+        },
+    }
+}
+
+/// Check if a key is reported with the enhanced key flag.
+fn is_enhanced_key(key: VirtualKeyCode) -> bool {
+    matches!(
+        key,
+        VirtualKeyCode::Insert
+            | VirtualKeyCode::Delete
+            | VirtualKeyCode::Home
+            | VirtualKeyCode::End
+            | VirtualKeyCode::PageUp
+            | VirtualKeyCode::PageDown
+            | VirtualKeyCode::Left
+            | VirtualKeyCode::Up
+            | VirtualKeyCode::Right
+            | VirtualKeyCode::Down
+            | VirtualKeyCode::NumpadEnter
+            | VirtualKeyCode::NumpadDivide
+            | VirtualKeyCode::LWin
+            | VirtualKeyCode::RWin
+            | VirtualKeyCode::Apps
+    )
+}
+
+/// Digit of a numpad key.
+fn numpad_digit(key: VirtualKeyCode) -> Option<u32> {
+    let digit = match key {
+        VirtualKeyCode::Numpad0 => 0,
+        VirtualKeyCode::Numpad1 => 1,
+        VirtualKeyCode::Numpad2 => 2,
+        VirtualKeyCode::Numpad3 => 3,
+        VirtualKeyCode::Numpad4 => 4,
+        VirtualKeyCode::Numpad5 => 5,
+        VirtualKeyCode::Numpad6 => 6,
+        VirtualKeyCode::Numpad7 => 7,
+        VirtualKeyCode::Numpad8 => 8,
+        VirtualKeyCode::Numpad9 => 9,
+        _ => return None,
+    };
+    Some(digit)
+}
+
+/// Unshifted character produced by a text key.
+fn text_key(key: VirtualKeyCode) -> Option<char> {
+    let c = match key {
+        VirtualKeyCode::Key1 => '1',
+        VirtualKeyCode::Key2 => '2',
+        VirtualKeyCode::Key3 => '3',
+        VirtualKeyCode::Key4 => '4',
+        VirtualKeyCode::Key5 => '5',
+        VirtualKeyCode::Key6 => '6',
+        VirtualKeyCode::Key7 => '7',
+        VirtualKeyCode::Key8 => '8',
+        VirtualKeyCode::Key9 => '9',
+        VirtualKeyCode::Key0 => '0',
+        VirtualKeyCode::Space => ' ',
+        VirtualKeyCode::Apostrophe => '\'',
+        VirtualKeyCode::Asterisk => '*',
+        VirtualKeyCode::At => '@',
+        VirtualKeyCode::Backslash => '\\',
+        VirtualKeyCode::Caret => '^',
+        VirtualKeyCode::Colon => ':',
+        VirtualKeyCode::Comma => ',',
+        VirtualKeyCode::Equals => '=',
+        VirtualKeyCode::Grave => '`',
+        VirtualKeyCode::LBracket => '[',
+        VirtualKeyCode::Minus => '-',
+        VirtualKeyCode::Period => '.',
+        VirtualKeyCode::Plus => '+',
+        VirtualKeyCode::RBracket => ']',
+        VirtualKeyCode::Semicolon => ';',
+        VirtualKeyCode::Slash => '/',
+        VirtualKeyCode::Underline => '_',
+        // Letters are declared in alphabetical order.
+        _ if key >= VirtualKeyCode::A && key <= VirtualKeyCode::Z => {
+            (b'a' + (key as u32 - VirtualKeyCode::A as u32) as u8) as char
+        },
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Check if a text key produces a C0 control code when pressed with Ctrl.
+fn has_control_code(key: VirtualKeyCode) -> bool {
+    match key {
+        VirtualKeyCode::Space
+        | VirtualKeyCode::At
+        | VirtualKeyCode::LBracket
+        | VirtualKeyCode::Backslash
+        | VirtualKeyCode::RBracket
+        | VirtualKeyCode::Caret
+        | VirtualKeyCode::Underline => true,
+        _ => key >= VirtualKeyCode::A && key <= VirtualKeyCode::Z,
+    }
+}
+
+/// Modifier parameter shared by kitty and xterm, `1` without any modifiers.
+fn modifiers_param(mods: ModifiersState) -> u32 {
+    let mut param = 1;
+    if mods.shift() {
+        param += 1;
+    }
+    if mods.alt() {
+        param += 2;
+    }
+    if mods.ctrl() {
+        param += 4;
+    }
+    if mods.logo() {
+        param += 8;
+    }
+    param
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISAMBIGUATE: TermMode = TermMode::DISAMBIGUATE_ESC_CODES;
+
+    fn press(key: VirtualKeyCode, mods: ModifiersState, mode: TermMode) -> Option<String> {
+        kitty_sequence(key, mods, mode, KeyEventType::Press)
+    }
+
+    #[test]
+    fn text_keys() {
+        let none = ModifiersState::empty();
+        assert_eq!(press(VirtualKeyCode::A, none, DISAMBIGUATE), None);
+        assert_eq!(press(VirtualKeyCode::A, ModifiersState::SHIFT, DISAMBIGUATE), None);
+        assert_eq!(
+            press(VirtualKeyCode::I, ModifiersState::CTRL, DISAMBIGUATE).as_deref(),
+            Some("\x1b[105;5u")
+        );
+        assert_eq!(
+            press(VirtualKeyCode::Key1, ModifiersState::ALT, DISAMBIGUATE).as_deref(),
+            Some("\x1b[49;3u")
+        );
+
+        let all_keys = TermMode::REPORT_ALL_KEYS_AS_ESC;
+        assert_eq!(press(VirtualKeyCode::Z, none, all_keys).as_deref(), Some("\x1b[122u"));
+        assert_eq!(
+            press(VirtualKeyCode::Z, ModifiersState::SHIFT, all_keys).as_deref(),
+            Some("\x1b[122;2u")
+        );
+
+        // Only event types do not change the encoding.
+        let events = TermMode::REPORT_EVENT_TYPES;
+        assert_eq!(press(VirtualKeyCode::I, ModifiersState::CTRL, events), None);
+    }
+
+    #[test]
+    fn escape_enter_tab_backspace() {
+        let none = ModifiersState::empty();
+        assert_eq!(press(VirtualKeyCode::Escape, none, DISAMBIGUATE).as_deref(), Some("\x1b[27u"));
+        assert_eq!(press(VirtualKeyCode::Return, none, DISAMBIGUATE), None);
+        assert_eq!(
+            press(VirtualKeyCode::Return, ModifiersState::SHIFT, DISAMBIGUATE).as_deref(),
+            Some("\x1b[13;2u")
+        );
+        assert_eq!(press(VirtualKeyCode::Back, none, DISAMBIGUATE).as_deref(), Some("\x7f"));
+        assert_eq!(
+            press(VirtualKeyCode::Back, ModifiersState::ALT, DISAMBIGUATE).as_deref(),
+            Some("\x1b[127;3u")
+        );
+        assert_eq!(
+            press(VirtualKeyCode::Tab, none, TermMode::REPORT_ALL_KEYS_AS_ESC).as_deref(),
+            Some("\x1b[9u")
+        );
+
+        // Legacy encoding without disambiguation.
+        let events = TermMode::REPORT_EVENT_TYPES;
+        assert_eq!(press(VirtualKeyCode::Escape, none, events), None);
+        assert_eq!(
+            press(VirtualKeyCode::Tab, ModifiersState::SHIFT, events).as_deref(),
+            Some("\x1b[Z")
+        );
+        assert_eq!(
+            press(VirtualKeyCode::Back, ModifiersState::ALT, events).as_deref(),
+            Some("\x1b\x7f")
+        );
+    }
+
+    #[test]
+    fn functional_keys() {
+        let none = ModifiersState::empty();
+        assert_eq!(press(VirtualKeyCode::Up, none, DISAMBIGUATE).as_deref(), Some("\x1b[A"));
+        assert_eq!(
+            press(VirtualKeyCode::Up, none, DISAMBIGUATE | TermMode::APP_CURSOR).as_deref(),
+            Some("\x1bOA")
+        );
+        assert_eq!(
+            press(VirtualKeyCode::Home, ModifiersState::CTRL, DISAMBIGUATE).as_deref(),
+            Some("\x1b[1;5H")
+        );
+        assert_eq!(press(VirtualKeyCode::F1, none, DISAMBIGUATE).as_deref(), Some("\x1bOP"));
+        assert_eq!(
+            press(VirtualKeyCode::F1, none, TermMode::REPORT_ALL_KEYS_AS_ESC).as_deref(),
+            Some("\x1b[P")
+        );
+        assert_eq!(press(VirtualKeyCode::F3, none, DISAMBIGUATE).as_deref(), Some("\x1bOR"));
+        assert_eq!(
+            press(VirtualKeyCode::F3, ModifiersState::SHIFT, DISAMBIGUATE).as_deref(),
+            Some("\x1b[13;2~")
+        );
+        assert_eq!(
+            press(VirtualKeyCode::PageUp, ModifiersState::ALT, DISAMBIGUATE).as_deref(),
+            Some("\x1b[5;3~")
+        );
+        assert_eq!(press(VirtualKeyCode::F13, none, DISAMBIGUATE).as_deref(), Some("\x1b[57376u"));
+        assert_eq!(
+            press(VirtualKeyCode::F13, none, TermMode::REPORT_EVENT_TYPES).as_deref(),
+            Some("\x1b[25~")
+        );
+        assert_eq!(press(VirtualKeyCode::F24, none, TermMode::REPORT_EVENT_TYPES), None);
+        assert_eq!(press(VirtualKeyCode::LShift, none, DISAMBIGUATE), None);
+    }
+
+    #[test]
+    fn event_types() {
+        let none = ModifiersState::empty();
+        let release = |key, mods, mode| kitty_sequence(key, mods, mode, KeyEventType::Release);
+        let repeat = |key, mods, mode| kitty_sequence(key, mods, mode, KeyEventType::Repeat);
+
+        // Releases require event types, while repeats are reported like presses.
+        assert_eq!(release(VirtualKeyCode::Escape, none, DISAMBIGUATE), None);
+        assert_eq!(repeat(VirtualKeyCode::Escape, none, DISAMBIGUATE).as_deref(), Some("\x1b[27u"));
+
+        let events = DISAMBIGUATE | TermMode::REPORT_EVENT_TYPES;
+        assert_eq!(press(VirtualKeyCode::Escape, none, events).as_deref(), Some("\x1b[27u"));
+        assert_eq!(repeat(VirtualKeyCode::Escape, none, events).as_deref(), Some("\x1b[27;1:2u"));
+        assert_eq!(release(VirtualKeyCode::Escape, none, events).as_deref(), Some("\x1b[27;1:3u"));
+        assert_eq!(
+            release(VirtualKeyCode::I, ModifiersState::CTRL, events).as_deref(),
+            Some("\x1b[105;5:3u")
+        );
+
+        // Functional keys switch to their CSI form when reporting events.
+        let app_cursor = events | TermMode::APP_CURSOR;
+        assert_eq!(press(VirtualKeyCode::Up, none, app_cursor).as_deref(), Some("\x1bOA"));
+        assert_eq!(release(VirtualKeyCode::Up, none, app_cursor).as_deref(), Some("\x1b[1;1:3A"));
+        assert_eq!(release(VirtualKeyCode::F3, none, events).as_deref(), Some("\x1b[13;1:3~"));
+        assert_eq!(repeat(VirtualKeyCode::Delete, none, events).as_deref(), Some("\x1b[3;1:2~"));
+
+        // Enter, Tab and Backspace releases require all keys to be reported.
+        assert_eq!(release(VirtualKeyCode::Return, ModifiersState::SHIFT, events), None);
+        assert_eq!(release(VirtualKeyCode::Back, none, events), None);
+        let all_keys = TermMode::REPORT_ALL_KEYS_AS_ESC | TermMode::REPORT_EVENT_TYPES;
+        assert_eq!(
+            release(VirtualKeyCode::Return, none, all_keys).as_deref(),
+            Some("\x1b[13;1:3u")
+        );
+
+        // Keys reported as text have no release events.
+        assert_eq!(release(VirtualKeyCode::A, none, events), None);
+    }
+
+    #[test]
+    fn functional_key_bindings() {
+        assert!(is_functional_key(VirtualKeyCode::Back));
+        assert!(is_functional_key(VirtualKeyCode::F20));
+        assert!(!is_functional_key(VirtualKeyCode::F21));
+        assert!(!is_functional_key(VirtualKeyCode::L));
+        assert!(!is_functional_key(VirtualKeyCode::NumpadEnter));
+    }
+
+    #[test]
+    fn modify_other_keys() {
+        let other_keys = TermMode::MODIFY_OTHER_KEYS;
+        let ctrl_shift = ModifiersState::CTRL | ModifiersState::SHIFT;
+        let sequence = modify_other_keys_sequence;
+
+        assert_eq!(sequence(VirtualKeyCode::Semicolon, ModifiersState::CTRL, TermMode::NONE), None);
+        assert_eq!(
+            sequence(VirtualKeyCode::Semicolon, ModifiersState::CTRL, other_keys).as_deref(),
+            Some("\x1b[27;5;59~")
+        );
+        assert_eq!(
+            sequence(VirtualKeyCode::A, ctrl_shift, other_keys).as_deref(),
+            Some("\x1b[27;6;65~")
+        );
+
+        // Keys with well-known encodings are only affected by the second level.
+        assert_eq!(sequence(VirtualKeyCode::A, ModifiersState::CTRL, other_keys), None);
+        assert_eq!(sequence(VirtualKeyCode::A, ModifiersState::ALT, other_keys), None);
+        assert_eq!(sequence(VirtualKeyCode::A, ModifiersState::SHIFT, other_keys), None);
+        assert_eq!(sequence(VirtualKeyCode::Tab, ModifiersState::CTRL, other_keys), None);
+
+        let all_keys = TermMode::MODIFY_ALL_KEYS;
+        assert_eq!(
+            sequence(VirtualKeyCode::A, ModifiersState::CTRL, all_keys).as_deref(),
+            Some("\x1b[27;5;97~")
+        );
+        assert_eq!(
+            sequence(VirtualKeyCode::Tab, ModifiersState::SHIFT, all_keys).as_deref(),
+            Some("\x1b[27;2;9~")
+        );
+        assert_eq!(sequence(VirtualKeyCode::A, ModifiersState::SHIFT, all_keys), None);
+        assert_eq!(sequence(VirtualKeyCode::Return, ModifiersState::empty(), all_keys), None);
+        assert_eq!(sequence(VirtualKeyCode::Up, ModifiersState::CTRL, all_keys), None);
+
+        // The kitty keyboard protocol takes precedence.
+        let mode = all_keys | DISAMBIGUATE;
+        assert_eq!(sequence(VirtualKeyCode::A, ModifiersState::CTRL, mode), None);
+    }
+
+    fn win32(key: VirtualKeyCode, scancode: u32, mods: ModifiersState) -> Win32KeyEvent {
+        Win32KeyEvent { key: Some(key), scancode, mods, pressed: true, repeat_count: 1 }
+    }
+
+    #[test]
+    fn win32_input_keys() {
+        let none = ModifiersState::empty();
+
+        // Text keys carry the text they produce.
+        let event = win32(VirtualKeyCode::A, 0x1e, none);
+        assert!(event.produces_text());
+        assert_eq!(event.sequence(Some('a')), "\x1b[65;30;97;1;0;1_");
+        assert_eq!(
+            win32(VirtualKeyCode::A, 0x1e, ModifiersState::SHIFT).sequence(Some('A')),
+            "\x1b[65;30;65;1;16;1_"
+        );
+        assert_eq!(
+            win32(VirtualKeyCode::C, 0x2e, ModifiersState::CTRL).sequence(Some('\x03')),
+            "\x1b[67;46;3;1;8;1_"
+        );
+        assert_eq!(
+            win32(VirtualKeyCode::Return, 0x1c, none).sequence(Some('\r')),
+            "\x1b[13;28;13;1;0;1_"
+        );
+        assert_eq!(
+            win32(VirtualKeyCode::Semicolon, 0x27, ModifiersState::ALT).sequence(Some(';')),
+            "\x1b[186;39;59;1;2;1_"
+        );
+
+        // Keys without text are reported immediately.
+        let event = win32(VirtualKeyCode::Up, 0x48, ModifiersState::CTRL);
+        assert!(!event.produces_text());
+        assert_eq!(event.sequence(None), "\x1b[38;72;0;1;264;1_");
+        assert_eq!(win32(VirtualKeyCode::F5, 0x3f, none).sequence(None), "\x1b[116;63;0;1;0;1_");
+        assert_eq!(win32(VirtualKeyCode::F13, 0x64, none).sequence(None), "\x1b[124;100;0;1;0;1_");
+        assert!(!win32(VirtualKeyCode::F1, 0x3b, none).produces_text());
+
+        // Text without a known key, like input method text, uses a zero key code.
+        let event = Win32KeyEvent { key: None, ..win32(VirtualKeyCode::A, 0, none) };
+        assert!(event.produces_text());
+        assert_eq!(event.sequence(Some('é')), "\x1b[0;0;233;1;0;1_");
+    }
+
+    #[test]
+    fn win32_input_events() {
+        let none = ModifiersState::empty();
+
+        // Releases and repeat counts.
+        let release = Win32KeyEvent { pressed: false, ..win32(VirtualKeyCode::A, 0x1e, none) };
+        assert_eq!(release.sequence(None), "\x1b[65;30;0;0;0;1_");
+        let repeat = Win32KeyEvent { repeat_count: 3, ..win32(VirtualKeyCode::Back, 0x0e, none) };
+        assert_eq!(repeat.sequence(Some('\x08')), "\x1b[8;14;8;1;0;3_");
+
+        // Modifier keys report their side.
+        let event = win32(VirtualKeyCode::LShift, 0x2a, ModifiersState::SHIFT);
+        assert!(event.is_modifier());
+        assert_eq!(event.sequence(None), "\x1b[16;42;0;1;16;1_");
+        let event = win32(VirtualKeyCode::RControl, 0x1d, ModifiersState::CTRL);
+        assert!(event.is_modifier());
+        assert_eq!(event.sequence(None), "\x1b[17;29;0;1;260;1_");
+        assert!(!win32(VirtualKeyCode::A, 0x1e, none).is_modifier());
+
+        // Characters outside of the BMP are split into surrogates.
+        let event = Win32KeyEvent { key: None, ..win32(VirtualKeyCode::A, 0, none) };
+        assert_eq!(event.sequence(Some('\u{1f600}')), "\x1b[0;0;55357;1;0;1_\x1b[0;0;56832;1;0;1_");
+    }
+}
@@ -38,6 +38,9 @@ mod daemon;
 mod display;
 mod event;
 mod input;
+mod keyboard;
+#[cfg(unix)]
+mod ipc;
 mod logging;
 #[cfg(target_os = "macos")]
 mod macos;
@@ -140,6 +143,14 @@ fn run(
 
     let event_proxy = EventProxy::new(window_event_loop.create_proxy());
 
+    // Create the IPC socket before spawning the shell, so its path is part of the environment.
+    #[cfg(unix)]
+    let ipc_socket = if config.ui_config.ipc_socket() {
+        ipc::spawn_ipc_socket(event_proxy.clone())
+    } else {
+        None
+    };
+
     // Create a display.
     //
     // The display manages a window and can draw the terminal.
@@ -189,7 +200,7 @@ fn run(
     // The monitor watches the config file for changes and reloads it. Pending
     // config changes are processed in the main loop.
     if config.ui_config.live_config_reload() {
-        monitor::watch(config.ui_config.config_paths.clone(), event_proxy);
+        monitor::watch(config.ui_config.config_paths.clone(), event_proxy.clone());
     }
 
     // Setup storage for message UI.
@@ -201,6 +212,7 @@ fn run(
         message_buffer,
         config,
         display,
+        event_proxy,
         options,
     );
 
@@ -229,6 +241,12 @@ fn run(
     loop_tx.send(Msg::Shutdown).expect("Error sending shutdown to PTY event loop");
     io_thread.join().expect("join io thread");
 
+    // Remove the IPC socket, so it isn't picked up by other clients.
+    #[cfg(unix)]
+    if let Some(path) = ipc_socket {
+        let _ = fs::remove_file(path);
+    }
+
     // FIXME patch notify library to have a shutdown method.
     // config_reloader.join().ok();
 
@@ -0,0 +1,97 @@
+//! Rendering of images displayed using the kitty graphics protocol.
+//!
+//! Images are split into cell-sized tiles, which are loaded into the glyph atlas like any other
+//! colored glyph.
+
+use crossfont::{BitmapBuffer, RasterizedGlyph};
+use log::warn;
+use png::{ColorType, Decoder, Transformations};
+
+use alacritty_terminal::term::image::{Image, ImageData};
+
+/// Maximum number of image tiles kept in the glyph atlas.
+pub const MAX_GRAPHIC_TILES: usize = 4096;
+
+/// Maximum number of decoded images kept in memory.
+pub const MAX_DECODED_IMAGES: usize = 16;
+
+/// Decode an image into premultiplied RGBA pixels.
+pub fn decode(image: &Image) -> Option<Vec<u8>> {
+    let mut rgba = match &image.data {
+        ImageData::Rgba(rgba) => rgba.clone(),
+        ImageData::Png(png) => match decode_png(png, image) {
+            Ok(rgba) => rgba,
+            Err(err) => {
+                warn!("Unable to decode PNG image {}: {}", image.id, err);
+                return None;
+            },
+        },
+    };
+
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = u16::from(pixel[3]);
+        for channel in &mut pixel[..3] {
+            *channel = (u16::from(*channel) * alpha / 255) as u8;
+        }
+    }
+
+    Some(rgba)
+}
+
+/// Cut the tile displayed in a single cell out of an image.
+pub fn tile(
+    rgba: &[u8],
+    image: &Image,
+    col: u32,
+    line: u32,
+    cell_width: usize,
+    cell_height: usize,
+) -> RasterizedGlyph {
+    let image_width = image.width as usize;
+    let image_height = image.height as usize;
+    let left = col as usize * cell_width;
+    let top = line as usize * cell_height;
+
+    let mut buf = vec![0; cell_width * cell_height * 4];
+    for y in 0..cell_height.min(image_height.saturating_sub(top)) {
+        let width = cell_width.min(image_width.saturating_sub(left));
+        let src = ((top + y) * image_width + left) * 4;
+        let dst = y * cell_width * 4;
+        buf[dst..dst + width * 4].copy_from_slice(&rgba[src..src + width * 4]);
+    }
+
+    RasterizedGlyph {
+        c: ' ',
+        top: cell_height as i32,
+        left: 0,
+        height: cell_height as i32,
+        width: cell_width as i32,
+        buf: BitmapBuffer::RGBA(buf),
+    }
+}
+
+/// Decode a PNG image into RGBA pixels.
+fn decode_png(png: &[u8], image: &Image) -> Result<Vec<u8>, String> {
+    let mut decoder = Decoder::new(png);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info().map_err(|err| err.to_string())?;
+
+    if info.width != image.width || info.height != image.height {
+        return Err(String::from("image size does not match header"));
+    }
+
+    let mut buf = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buf).map_err(|err| err.to_string())?;
+
+    let rgba = match info.color_type {
+        ColorType::RGBA => buf,
+        ColorType::RGB => buf.chunks_exact(3).flat_map(|p| vec![p[0], p[1], p[2], 255]).collect(),
+        ColorType::GrayscaleAlpha => {
+            buf.chunks_exact(2).flat_map(|p| vec![p[0], p[0], p[0], p[1]]).collect()
+        },
+        ColorType::Grayscale => buf.iter().flat_map(|&p| vec![p, p, p, 255]).collect(),
+        ColorType::Indexed => return Err(String::from("unexpanded color palette")),
+    };
+
+    Ok(rgba)
+}
@@ -20,8 +20,9 @@ use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
 
 use alacritty_terminal::config::Cursor;
 use alacritty_terminal::index::{Column, Line};
-use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::cell::{Flags, UnderlineStyle};
 use alacritty_terminal::term::color::Rgb;
+use alacritty_terminal::term::image::GraphicCell;
 use alacritty_terminal::term::{CursorKey, RenderableCell, RenderableCellContent, SizeInfo};
 use alacritty_terminal::thread;
 
@@ -32,6 +33,7 @@ use crate::gl;
 use crate::gl::types::*;
 use crate::renderer::rects::RenderRect;
 
+mod graphics;
 pub mod rects;
 
 // Shader paths for live reload.
@@ -146,6 +148,12 @@ pub struct GlyphCache {
     /// Cache of buffered cursor glyphs.
     cursor_cache: HashMap<CursorKey, Glyph, BuildHasherDefault<FnvHasher>>,
 
+    /// Cache of buffered image tiles, keyed by image serial, column and line.
+    graphic_cache: HashMap<(u64, u32, u32), Glyph, BuildHasherDefault<FnvHasher>>,
+
+    /// Decoded pixels of displayed images, keyed by image serial.
+    decoded_images: HashMap<u64, Option<Vec<u8>>, BuildHasherDefault<FnvHasher>>,
+
     /// Rasterizer for loading new glyphs.
     rasterizer: Rasterizer,
 
@@ -192,6 +200,8 @@ impl GlyphCache {
         let mut cache = Self {
             cache: HashMap::default(),
             cursor_cache: HashMap::default(),
+            graphic_cache: HashMap::default(),
+            decoded_images: HashMap::default(),
             rasterizer,
             font_size: font.size,
             font_key: regular,
@@ -298,11 +308,55 @@ impl GlyphCache {
         })
     }
 
+    /// Get the glyph for the part of an image displayed in a cell.
+    ///
+    /// Returns `None` if the image could not be decoded.
+    pub fn get_graphic<L>(
+        &mut self,
+        graphic: &GraphicCell,
+        offset: Delta<i8>,
+        loader: &mut L,
+    ) -> Option<&Glyph>
+    where
+        L: LoadGlyph,
+    {
+        let image = &graphic.image;
+        let key = (image.serial, graphic.col, graphic.line);
+        if !self.graphic_cache.contains_key(&key) {
+            if self.decoded_images.len() >= graphics::MAX_DECODED_IMAGES
+                && !self.decoded_images.contains_key(&image.serial)
+            {
+                self.decoded_images.clear();
+            }
+
+            let rgba = self
+                .decoded_images
+                .entry(image.serial)
+                .or_insert_with(|| graphics::decode(image))
+                .as_ref()?;
+
+            let cell_width = (self.metrics.average_advance + f64::from(offset.x)).max(1.) as usize;
+            let cell_height = (self.metrics.line_height + f64::from(offset.y)).max(1.) as usize;
+            let tile =
+                graphics::tile(rgba, image, graphic.col, graphic.line, cell_width, cell_height);
+            self.graphic_cache.insert(key, loader.load_glyph(&tile));
+        }
+
+        self.graphic_cache.get(&key)
+    }
+
+    /// Check if the image tiles should be removed from the glyph atlas.
+    pub fn graphics_full(&self) -> bool {
+        self.graphic_cache.len() >= graphics::MAX_GRAPHIC_TILES
+    }
+
     /// Clear currently cached data in both GL and the registry.
     pub fn clear_glyph_cache<L: LoadGlyph>(&mut self, loader: &mut L) {
         loader.clear();
         self.cache = HashMap::default();
         self.cursor_cache = HashMap::default();
+        self.graphic_cache = HashMap::default();
+        self.decoded_images = HashMap::default();
 
         self.load_common_glyphs(loader);
     }
@@ -360,12 +414,14 @@ impl GlyphCache {
     }
 }
 
-// NOTE: These flags must be in sync with their usage in the text.*.glsl shaders.
+// NOTE: These flags must be in sync with their usage in the text.*.glsl shaders and with the
+// constants of `VertexStyle`.
 bitflags! {
     #[repr(C)]
     struct RenderingGlyphFlags: u8 {
         const WIDE_CHAR = 0b0000_0001;
         const COLORED   = 0b0000_0010;
+        const GRAYSCALE = 0b0000_0100;
     }
 }
 
@@ -461,6 +517,7 @@ impl Batch {
         let mut cell_flags = RenderingGlyphFlags::empty();
         cell_flags.set(RenderingGlyphFlags::COLORED, glyph.multicolor);
         cell_flags.set(RenderingGlyphFlags::WIDE_CHAR, cell.flags.contains(Flags::WIDE_CHAR));
+        cell_flags.set(RenderingGlyphFlags::GRAYSCALE, !cell.allows_subpixel);
 
         self.instances.push(InstanceData {
             col: cell.column.0 as u16,
@@ -979,7 +1036,11 @@ impl<'a> RenderApi<'a> {
                 bg_alpha,
                 fg,
                 bg: bg.unwrap_or(Rgb { r: 0, g: 0, b: 0 }),
+                underline: fg,
+                underline_style: UnderlineStyle::None,
                 is_match: false,
+                hyperlink: None,
+                allows_subpixel: true,
             })
             .collect::<Vec<_>>();
 
@@ -1021,6 +1082,13 @@ impl<'a> RenderApi<'a> {
                 self.add_render_item(&cell, glyph);
                 return;
             },
+            RenderableCellContent::Graphic(ref graphic) => {
+                let offset = self.config.font.offset;
+                if let Some(glyph) = glyph_cache.get_graphic(graphic, offset, self) {
+                    self.add_render_item(&cell, glyph);
+                }
+                return;
+            },
             RenderableCellContent::Chars((c, ref mut zerowidth)) => (c, zerowidth.take()),
         };
 
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
+use std::f32::consts::PI;
 
 use crossfont::Metrics;
 
 use alacritty_terminal::index::{Column, Point};
-use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::cell::{Flags, UnderlineStyle};
 use alacritty_terminal::term::color::Rgb;
 use alacritty_terminal::term::{RenderableCell, SizeInfo};
 
@@ -23,6 +24,14 @@ impl RenderRect {
     }
 }
 
+/// Decoration drawn along a run of cells.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LineKind {
+    Underline(UnderlineStyle),
+    Strikeout,
+    Overline,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct RenderLine {
     pub start: Point,
@@ -31,16 +40,16 @@ pub struct RenderLine {
 }
 
 impl RenderLine {
-    pub fn rects(&self, flag: Flags, metrics: &Metrics, size: &SizeInfo) -> Vec<RenderRect> {
+    pub fn rects(&self, kind: LineKind, metrics: &Metrics, size: &SizeInfo) -> Vec<RenderRect> {
         let mut rects = Vec::new();
 
         let mut start = self.start;
         while start.line < self.end.line {
             let end = Point::new(start.line, size.cols() - 1);
-            Self::push_rects(&mut rects, metrics, size, flag, start, end, self.color);
+            Self::push_rects(&mut rects, metrics, size, kind, start, end, self.color);
             start = Point::new(start.line + 1, Column(0));
         }
-        Self::push_rects(&mut rects, metrics, size, flag, start, self.end, self.color);
+        Self::push_rects(&mut rects, metrics, size, kind, start, self.end, self.color);
 
         rects
     }
@@ -50,13 +59,13 @@ impl RenderLine {
         rects: &mut Vec<RenderRect>,
         metrics: &Metrics,
         size: &SizeInfo,
-        flag: Flags,
+        kind: LineKind,
         start: Point,
         end: Point,
         color: Rgb,
     ) {
-        let (position, thickness) = match flag {
-            Flags::DOUBLE_UNDERLINE => {
+        let (position, thickness) = match kind {
+            LineKind::Underline(UnderlineStyle::Double) => {
                 // Position underlines so each one has 50% of descent available.
                 let top_pos = 0.25 * metrics.descent;
                 let bottom_pos = 0.75 * metrics.descent;
@@ -73,9 +82,19 @@ impl RenderLine {
 
                 (bottom_pos, metrics.underline_thickness)
             },
-            Flags::UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
-            Flags::STRIKEOUT => (metrics.strikeout_position, metrics.strikeout_thickness),
-            _ => unimplemented!("Invalid flag for cell line drawing specified"),
+            LineKind::Underline(UnderlineStyle::Curly) => {
+                Self::push_undercurl_rects(rects, metrics, size, start, end, color);
+                return;
+            },
+            // Dotted and dashed underlines are drawn as straight lines.
+            LineKind::Underline(_) => (metrics.underline_position, metrics.underline_thickness),
+            LineKind::Strikeout => (metrics.strikeout_position, metrics.strikeout_thickness),
+            LineKind::Overline => {
+                // Align the top of the line with the top of the cell.
+                let thickness = metrics.underline_thickness.max(1.);
+                let position = size.cell_height() + metrics.descent - thickness / 2.;
+                (position, metrics.underline_thickness)
+            },
         };
 
         rects.push(Self::create_rect(
@@ -89,6 +108,48 @@ impl RenderLine {
         ));
     }
 
+    /// Push the rects required to draw a curly underline.
+    ///
+    /// The wave is approximated using one rect per pixel column, with one full period per cell.
+    fn push_undercurl_rects(
+        rects: &mut Vec<RenderRect>,
+        metrics: &Metrics,
+        size: &SizeInfo,
+        start: Point,
+        end: Point,
+        color: Rgb,
+    ) {
+        let start_x = start.col.0 as f32 * size.cell_width();
+        let end_x = (end.col.0 + 1) as f32 * size.cell_width();
+
+        // Make sure lines are always visible.
+        let thickness = metrics.underline_thickness.max(1.);
+
+        let line_bottom = (start.line.0 as f32 + 1.) * size.cell_height();
+        let baseline = line_bottom + metrics.descent;
+
+        // Center the wave inside the descent, using half of it as peak-to-peak amplitude.
+        let center = baseline - 0.5 * metrics.descent - thickness / 2.;
+        let amplitude = (0.25 * metrics.descent).abs();
+        let max_y = line_bottom - thickness;
+
+        let frequency = 2. * PI / size.cell_width();
+
+        let mut x = start_x;
+        while x < end_x {
+            let y = (center + amplitude * (x * frequency).sin()).round().min(max_y);
+            rects.push(RenderRect::new(
+                x + size.padding_x(),
+                y + size.padding_y(),
+                1.,
+                thickness,
+                color,
+                1.,
+            ));
+            x += 1.;
+        }
+    }
+
     /// Create a line's rect at a position relative to the baseline.
     fn create_rect(
         size: &SizeInfo,
@@ -126,10 +187,50 @@ impl RenderLine {
     }
 }
 
-/// Lines for underline and strikeout.
+/// Outline of the hollow cursor.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HollowCursor {
+    pub point: Point,
+    pub is_wide: bool,
+    pub color: Rgb,
+}
+
+impl HollowCursor {
+    pub fn new(cell: &RenderableCell, is_wide: bool) -> Self {
+        Self { point: cell.into(), is_wide, color: cell.fg }
+    }
+
+    /// Rects outlining the cursor's cell, spanning both cells of wide characters.
+    ///
+    /// The outline is `thickness` cell widths wide, but never thinner than one pixel per DPI
+    /// scale factor to keep it visible on high DPI displays.
+    pub fn rects(&self, thickness: f64, scale_factor: f64, size: &SizeInfo) -> [RenderRect; 4] {
+        let columns = if self.is_wide { 2. } else { 1. };
+        let x = self.point.col.0 as f32 * size.cell_width() + size.padding_x();
+        let y = self.point.line.0 as f32 * size.cell_height() + size.padding_y();
+        let width = columns * size.cell_width();
+        let height = size.cell_height();
+
+        // Keep the outline from overlapping itself.
+        let min_width = scale_factor.round().max(1.);
+        let line_width = (thickness * f64::from(size.cell_width())).round().max(min_width) as f32;
+        let line_width = line_width.min((width / 2.).floor()).min((height / 2.).floor());
+        let side_height = height - 2. * line_width;
+        let right = x + width - line_width;
+
+        [
+            RenderRect::new(x, y, width, line_width, self.color, 1.),
+            RenderRect::new(x, y + height - line_width, width, line_width, self.color, 1.),
+            RenderRect::new(x, y + line_width, line_width, side_height, self.color, 1.),
+            RenderRect::new(right, y + line_width, line_width, side_height, self.color, 1.),
+        ]
+    }
+}
+
+/// Lines for underline, strikeout and overline.
 #[derive(Default)]
 pub struct RenderLines {
-    inner: HashMap<Flags, Vec<RenderLine>>,
+    inner: HashMap<LineKind, Vec<RenderLine>>,
 }
 
 impl RenderLines {
@@ -142,8 +243,8 @@ impl RenderLines {
     pub fn rects(&self, metrics: &Metrics, size: &SizeInfo) -> Vec<RenderRect> {
         self.inner
             .iter()
-            .flat_map(|(flag, lines)| {
-                lines.iter().flat_map(move |line| line.rects(*flag, metrics, size))
+            .flat_map(|(kind, lines)| {
+                lines.iter().flat_map(move |line| line.rects(*kind, metrics, size))
             })
             .collect()
     }
@@ -151,28 +252,34 @@ impl RenderLines {
     /// Update the stored lines with the next cell info.
     #[inline]
     pub fn update(&mut self, cell: &RenderableCell) {
-        self.update_flag(&cell, Flags::UNDERLINE);
-        self.update_flag(&cell, Flags::DOUBLE_UNDERLINE);
-        self.update_flag(&cell, Flags::STRIKEOUT);
-    }
-
-    /// Update the lines for a specific flag.
-    fn update_flag(&mut self, cell: &RenderableCell, flag: Flags) {
-        if !cell.flags.contains(flag) {
-            return;
+        if cell.flags.contains(Flags::UNDERLINE) {
+            self.update_line(&cell, LineKind::Underline(cell.underline_style));
+        }
+        if cell.flags.contains(Flags::STRIKEOUT) {
+            self.update_line(&cell, LineKind::Strikeout);
+        }
+        if cell.flags.contains(Flags::OVERLINE) {
+            self.update_line(&cell, LineKind::Overline);
         }
+    }
 
+    /// Update the lines of a specific kind.
+    fn update_line(&mut self, cell: &RenderableCell, kind: LineKind) {
         // Include wide char spacer if the current cell is a wide char.
         let mut end: Point = cell.into();
         if cell.flags.contains(Flags::WIDE_CHAR) {
             end.col += 1;
         }
 
+        // Underlines can have a color independent from the text.
+        let color = match kind {
+            LineKind::Underline(_) => cell.underline,
+            _ => cell.fg,
+        };
+
         // Check if there's an active line.
-        if let Some(line) = self.inner.get_mut(&flag).and_then(|lines| lines.last_mut()) {
-            if cell.fg == line.color
-                && cell.column == line.end.col + 1
-                && cell.line == line.end.line
+        if let Some(line) = self.inner.get_mut(&kind).and_then(|lines| lines.last_mut()) {
+            if color == line.color && cell.column == line.end.col + 1 && cell.line == line.end.line
             {
                 // Update the length of the line.
                 line.end = end;
@@ -181,12 +288,99 @@ impl RenderLines {
         }
 
         // Start new line if there currently is none.
-        let line = RenderLine { start: cell.into(), end, color: cell.fg };
-        match self.inner.get_mut(&flag) {
+        let line = RenderLine { start: cell.into(), end, color };
+        match self.inner.get_mut(&kind) {
             Some(lines) => lines.push(line),
             None => {
-                self.inner.insert(flag, vec![line]);
+                self.inner.insert(kind, vec![line]);
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::index::Line;
+
+    const COLOR: Rgb = Rgb { r: 0xff, g: 0x00, b: 0x00 };
+
+    fn hollow_cursor(col: usize, is_wide: bool) -> HollowCursor {
+        HollowCursor { point: Point::new(Line(1), Column(col)), is_wide, color: COLOR }
+    }
+
+    fn geometry(rects: &[RenderRect]) -> Vec<(f32, f32, f32, f32)> {
+        rects.iter().map(|rect| (rect.x, rect.y, rect.width, rect.height)).collect()
+    }
+
+    #[test]
+    fn hollow_cursor_scale_factors() {
+        for &scale_factor in &[1., 2., 3.] {
+            let cell_width = 10. * scale_factor as f32;
+            let cell_height = 20. * scale_factor as f32;
+            let size = SizeInfo::new(100., 100., cell_width, cell_height, 0., 0., false);
+
+            // A tenth of the cell width rounds to exactly one pixel per scale factor.
+            let rects = hollow_cursor(2, false).rects(0.1, scale_factor, &size);
+
+            let line = scale_factor as f32;
+            let (x, y) = (2. * cell_width, cell_height);
+            assert_eq!(geometry(&rects), vec![
+                (x, y, cell_width, line),
+                (x, y + cell_height - line, cell_width, line),
+                (x, y + line, line, cell_height - 2. * line),
+                (x + cell_width - line, y + line, line, cell_height - 2. * line),
+            ]);
+            assert!(rects.iter().all(|rect| rect.color == COLOR && rect.alpha == 1.));
+        }
+    }
+
+    #[test]
+    fn hollow_cursor_minimum_thickness() {
+        let size = SizeInfo::new(100., 100., 20., 40., 0., 0., false);
+
+        // Thin outlines are widened to the DPI scale factor.
+        let rects = hollow_cursor(0, false).rects(0., 2., &size);
+        assert_eq!(rects[0].height, 2.);
+        assert_eq!(rects[2].width, 2.);
+
+        // Fractional scale factors round to whole pixels.
+        let rects = hollow_cursor(0, false).rects(0., 1.25, &size);
+        assert_eq!(rects[0].height, 1.);
+
+        // Thick outlines are not affected by the scale factor.
+        let rects = hollow_cursor(0, false).rects(0.3, 2., &size);
+        assert_eq!(rects[0].height, 6.);
+        assert_eq!(rects[2].width, 6.);
+    }
+
+    #[test]
+    fn hollow_cursor_wide_char() {
+        let size = SizeInfo::new(100., 100., 10., 20., 5., 3., false);
+
+        let rects = hollow_cursor(1, true).rects(0.2, 1., &size);
+
+        assert_eq!(geometry(&rects), vec![
+            (15., 23., 20., 2.),
+            (15., 41., 20., 2.),
+            (15., 25., 2., 16.),
+            (33., 25., 2., 16.),
+        ]);
+    }
+
+    #[test]
+    fn hollow_cursor_clamped() {
+        let size = SizeInfo::new(100., 100., 9., 20., 0., 0., false);
+
+        // Outlines never grow beyond half of the cell.
+        let rects = hollow_cursor(0, false).rects(1., 1., &size);
+
+        assert_eq!(geometry(&rects), vec![
+            (0., 20., 9., 4.),
+            (0., 36., 9., 4.),
+            (0., 24., 4., 12.),
+            (5., 24., 4., 12.),
+        ]);
+    }
+}
@@ -15,6 +15,9 @@ pub enum TimerId {
     SelectionScrolling,
     DelayedSearch,
     BlinkCursor,
+    BlinkText,
+    RepresentedDirectory,
+    PersistScrollback,
 }
 
 /// Event scheduled to be emitted at a specific time.
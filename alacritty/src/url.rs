@@ -1,24 +1,26 @@
 use std::cmp::min;
 use std::mem;
+use std::sync::Arc;
 
 use crossfont::Metrics;
 use glutin::event::{ElementState, ModifiersState};
 use urlocator::{UrlLocation, UrlLocator};
 
 use alacritty_terminal::index::{Column, Point};
-use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::cell::{Flags, Hyperlink, UnderlineStyle};
 use alacritty_terminal::term::color::Rgb;
 use alacritty_terminal::term::{RenderableCell, RenderableCellContent, SizeInfo};
 
 use crate::config::Config;
 use crate::event::Mouse;
-use crate::renderer::rects::{RenderLine, RenderRect};
+use crate::renderer::rects::{LineKind, RenderLine, RenderRect};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Url {
     lines: Vec<RenderLine>,
     end_offset: u16,
     num_cols: Column,
+    hyperlink: Option<Arc<Hyperlink>>,
 }
 
 impl Url {
@@ -30,7 +32,7 @@ impl Url {
             .map(|line| {
                 let mut rect_line = *line;
                 rect_line.end = min(line.end, end);
-                rect_line.rects(Flags::UNDERLINE, metrics, size)
+                rect_line.rects(LineKind::Underline(UnderlineStyle::Single), metrics, size)
             })
             .flatten()
             .collect()
@@ -43,6 +45,21 @@ impl Url {
     pub fn end(&self) -> Point {
         self.lines[self.lines.len() - 1].end.sub(self.num_cols, self.end_offset as usize)
     }
+
+    /// OSC 8 hyperlink, `None` for URLs detected in the cell text.
+    pub fn hyperlink(&self) -> Option<&Hyperlink> {
+        self.hyperlink.as_deref()
+    }
+
+    /// Check if a point is part of the URL.
+    pub fn contains(&self, point: Point) -> bool {
+        if self.hyperlink.is_none() {
+            return (self.start()..=self.end()).contains(&point);
+        }
+
+        // Hyperlinks sharing an ID are not necessarily contiguous.
+        self.lines.iter().any(|line| (line.start..=line.end).contains(&point))
+    }
 }
 
 pub struct Urls {
@@ -51,6 +68,7 @@ pub struct Urls {
     scheme_buffer: Vec<(Point, Rgb)>,
     last_point: Option<Point>,
     state: UrlLocation,
+    hyperlink: Option<Arc<Hyperlink>>,
 }
 
 impl Default for Urls {
@@ -61,6 +79,7 @@ impl Default for Urls {
             urls: Vec::new(),
             state: UrlLocation::Reset,
             last_point: None,
+            hyperlink: None,
         }
     }
 }
@@ -75,7 +94,7 @@ impl Urls {
         // Convert cell to character.
         let c = match &cell.inner {
             RenderableCellContent::Chars((c, _zerowidth)) => *c,
-            RenderableCellContent::Cursor(_) => return,
+            RenderableCellContent::Cursor(_) | RenderableCellContent::Graphic(_) => return,
         };
 
         let point: Point = cell.into();
@@ -93,6 +112,20 @@ impl Urls {
 
         self.last_point = Some(end);
 
+        // Use the hyperlink's URI directly instead of parsing the cell text.
+        if let Some(hyperlink) = &cell.hyperlink {
+            self.extend_hyperlink(num_cols, point, end, cell.fg, hyperlink);
+
+            // Reset at un-wrapped linebreak.
+            if cell.column + 1 == num_cols && !cell.flags.contains(Flags::WRAPLINE) {
+                self.reset();
+            }
+
+            return;
+        } else if self.hyperlink.is_some() {
+            self.reset();
+        }
+
         // Extend current state if a leading wide char spacer is encountered.
         if cell.flags.intersects(Flags::LEADING_WIDE_CHAR_SPACER) {
             if let UrlLocation::Url(_, mut end_offset) = self.state {
@@ -111,7 +144,7 @@ impl Urls {
         match (self.state, last_state) {
             (UrlLocation::Url(_length, end_offset), UrlLocation::Scheme) => {
                 // Create empty URL.
-                self.urls.push(Url { lines: Vec::new(), end_offset, num_cols });
+                self.urls.push(Url { lines: Vec::new(), end_offset, num_cols, hyperlink: None });
 
                 // Push schemes into URL.
                 for (scheme_point, scheme_fg) in self.scheme_buffer.split_off(0) {
@@ -135,6 +168,51 @@ impl Urls {
         }
     }
 
+    /// Add a cell to the URL of its hyperlink.
+    ///
+    /// Cells are added to the previous cell's URL if they share the same hyperlink. Hyperlinks
+    /// with an ID are also joined with earlier cells using the same hyperlink, even if they're
+    /// not adjacent.
+    fn extend_hyperlink(
+        &mut self,
+        num_cols: Column,
+        start: Point,
+        end: Point,
+        color: Rgb,
+        hyperlink: &Arc<Hyperlink>,
+    ) {
+        let is_adjacent =
+            self.hyperlink.as_ref().map_or(false, |previous| previous.is_same_link(hyperlink));
+        if !is_adjacent {
+            self.reset();
+            self.hyperlink = Some(hyperlink.clone());
+        }
+
+        let index = if is_adjacent {
+            Some(self.urls.len() - 1)
+        } else if hyperlink.id.is_some() {
+            self.urls.iter().rposition(|url| {
+                url.hyperlink.as_ref().map_or(false, |previous| previous.is_same_link(hyperlink))
+            })
+        } else {
+            None
+        };
+
+        let url = match index {
+            Some(index) => &mut self.urls[index],
+            None => {
+                let hyperlink = Some(hyperlink.clone());
+                self.urls.push(Url { lines: Vec::new(), end_offset: 0, num_cols, hyperlink });
+                self.urls.last_mut().unwrap()
+            },
+        };
+
+        match url.lines.last_mut() {
+            Some(line) if is_adjacent && line.color == color => line.end = end,
+            _ => url.lines.push(RenderLine { color, start, end }),
+        }
+    }
+
     /// Extend the last URL.
     fn extend_url(&mut self, start: Point, end: Point, color: Rgb, end_offset: u16) {
         let url = self.urls.last_mut().unwrap();
@@ -180,18 +258,14 @@ impl Urls {
 
     /// Find URL at location.
     pub fn find_at(&self, point: Point) -> Option<Url> {
-        for url in &self.urls {
-            if (url.start()..=url.end()).contains(&point) {
-                return Some(url.clone());
-            }
-        }
-        None
+        self.urls.iter().find(|url| url.contains(point)).cloned()
     }
 
     fn reset(&mut self) {
         self.locator = UrlLocator::new();
         self.state = UrlLocation::Reset;
         self.scheme_buffer.clear();
+        self.hyperlink = None;
     }
 }
 
@@ -211,8 +285,12 @@ mod tests {
                 fg: Default::default(),
                 bg: Default::default(),
                 bg_alpha: 0.,
+                underline: Default::default(),
+                underline_style: UnderlineStyle::None,
                 flags: Flags::empty(),
                 is_match: false,
+                hyperlink: None,
+                allows_subpixel: true,
             })
             .collect()
     }
@@ -277,4 +355,65 @@ mod tests {
         assert_eq!(urls.urls[1].start().col, Column(20));
         assert_eq!(urls.urls[1].end().col, Column(28));
     }
+
+    #[test]
+    fn hyperlinks() {
+        let mut input = text_to_cells("test link https://a.org ing");
+        let num_cols = input.len();
+
+        let hyperlink = Hyperlink { id: None, uri: String::from("https://example.org") };
+        let hyperlink = Arc::new(hyperlink);
+        for cell in &mut input[5..9] {
+            cell.hyperlink = Some(hyperlink.clone());
+        }
+
+        let mut urls = Urls::new();
+
+        for cell in input {
+            urls.update(Column(num_cols), &cell);
+        }
+
+        assert_eq!(urls.urls.len(), 2);
+
+        assert_eq!(urls.urls[0].start().col, Column(5));
+        assert_eq!(urls.urls[0].end().col, Column(8));
+        assert_eq!(urls.urls[0].hyperlink(), Some(&*hyperlink));
+
+        assert_eq!(urls.urls[1].start().col, Column(10));
+        assert_eq!(urls.urls[1].end().col, Column(22));
+        assert_eq!(urls.urls[1].hyperlink(), None);
+    }
+
+    #[test]
+    fn hyperlink_ids() {
+        let mut input = text_to_cells("one two one");
+        let num_cols = input.len();
+
+        let id = Some(String::from("a"));
+        let one = Arc::new(Hyperlink { id, uri: String::from("file:///one") });
+        let two = Arc::new(Hyperlink { id: None, uri: String::from("file:///two") });
+        for (i, cell) in input.iter_mut().enumerate() {
+            cell.hyperlink = match i {
+                0..=2 | 8..=10 => Some(one.clone()),
+                4..=6 => Some(two.clone()),
+                _ => None,
+            };
+        }
+
+        let mut urls = Urls::new();
+
+        for cell in input {
+            urls.update(Column(num_cols), &cell);
+        }
+
+        assert_eq!(urls.urls.len(), 2);
+
+        let url = urls.find_at(Point::new(Line(0), Column(9))).unwrap();
+        assert_eq!(url.hyperlink(), Some(&*one));
+        assert!(url.contains(Point::new(Line(0), Column(1))));
+        assert!(!url.contains(Point::new(Line(0), Column(5))));
+
+        let url = urls.find_at(Point::new(Line(0), Column(5))).unwrap();
+        assert_eq!(url.hyperlink(), Some(&*two));
+    }
 }
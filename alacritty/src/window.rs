@@ -20,9 +20,20 @@ use {
 };
 
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
-use x11_dl::xlib::{Display as XDisplay, PropModeReplace, XErrorEvent, Xlib};
+use x11_dl::xlib::{
+    Display as XDisplay, PBaseSize, PResizeInc, PropModeReplace, XErrorEvent, Xlib,
+};
+
+#[cfg(target_os = "macos")]
+use objc::runtime::{Class, Object, YES};
+#[cfg(target_os = "macos")]
+use objc::{msg_send, sel, sel_impl};
 
+#[cfg(target_os = "macos")]
+use std::ffi::{CStr, CString};
 use std::fmt::{self, Display, Formatter};
+#[cfg(target_os = "macos")]
+use std::ptr;
 
 use glutin::dpi::{PhysicalPosition, PhysicalSize};
 use glutin::event_loop::EventLoop;
@@ -35,6 +46,8 @@ use glutin::{self, ContextBuilder, PossiblyCurrent, WindowedContext};
 #[cfg(windows)]
 use winapi::shared::minwindef::WORD;
 
+#[cfg(target_os = "macos")]
+use alacritty_terminal::ansi::WorkingDirectory;
 use alacritty_terminal::index::Point;
 use alacritty_terminal::term::SizeInfo;
 
@@ -127,6 +140,73 @@ fn create_gl_window<E>(
     Ok(windowed_context)
 }
 
+/// Steps in which the window can be resized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResizeIncrements {
+    /// Size of a single resize step.
+    pub step: PhysicalSize<u32>,
+
+    /// Window size without any steps.
+    pub base: PhysicalSize<u32>,
+}
+
+impl ResizeIncrements {
+    /// Round a window size down to the closest size reachable through the resize steps.
+    pub fn snap(&self, size: PhysicalSize<u32>) -> PhysicalSize<u32> {
+        let snap = |size: u32, step: u32, base: u32| {
+            if size <= base || step == 0 {
+                return size;
+            }
+
+            size - (size - base) % step
+        };
+
+        PhysicalSize::new(
+            snap(size.width, self.step.width, self.base.width),
+            snap(size.height, self.step.height, self.base.height),
+        )
+    }
+}
+
+/// Position of a window spawned from another window.
+///
+/// The new window is offset by `step` cells from the `origin` window. Once it would no longer fit
+/// into the monitor's work area, it wraps around to the top left corner of the work area.
+///
+/// Since winit does not report the area left free by panels and docks, the whole monitor is
+/// passed as the work area. New windows can therefore be placed partially below a panel.
+pub fn cascade_position(
+    origin: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    cell: PhysicalSize<u32>,
+    step: usize,
+    work_area: (PhysicalPosition<i32>, PhysicalSize<u32>),
+) -> PhysicalPosition<i32> {
+    let (area_position, area_size) = work_area;
+
+    let cascade = |origin: i32, cell: u32, start: i32, size: u32, area_size: u32| {
+        let position = i64::from(origin) + i64::from(cell) * step as i64;
+        let start = i64::from(start);
+
+        // Range of positions at which the window still fits into the work area.
+        let free = i64::from(area_size.saturating_sub(size)) + 1;
+
+        (start + (position - start).max(0) % free) as i32
+    };
+
+    PhysicalPosition::new(
+        cascade(origin.x, cell.width, area_position.x, size.width, area_size.width),
+        cascade(origin.y, cell.height, area_position.y, size.height, area_size.height),
+    )
+}
+
+#[cfg(target_os = "macos")]
+#[repr(C)]
+struct NSSize {
+    width: f64,
+    height: f64,
+}
+
 /// A window which can be used for displaying the terminal.
 ///
 /// Wraps the underlying windowing library to provide a stable API in Alacritty.
@@ -145,6 +225,7 @@ pub struct Window {
     windowed_context: WindowedContext<PossiblyCurrent>,
     current_mouse_cursor: CursorIcon,
     mouse_visible: bool,
+    resize_increments: Option<ResizeIncrements>,
 }
 
 impl Window {
@@ -207,6 +288,7 @@ impl Window {
         Ok(Self {
             current_mouse_cursor,
             mouse_visible: true,
+            resize_increments: None,
             windowed_context,
             #[cfg(not(any(target_os = "macos", windows)))]
             should_draw: Arc::new(AtomicBool::new(true)),
@@ -224,6 +306,10 @@ impl Window {
         self.window().inner_size()
     }
 
+    pub fn set_min_inner_size(&self, size: Option<PhysicalSize<u32>>) {
+        self.window().set_min_inner_size(size);
+    }
+
     #[inline]
     pub fn set_visible(&self, visibility: bool) {
         self.window().set_visible(visibility);
@@ -345,10 +431,88 @@ impl Window {
     #[cfg(any(windows, not(any(feature = "x11", target_os = "macos"))))]
     pub fn set_urgent(&self, _is_urgent: bool) {}
 
+    /// Current resize increments.
+    #[inline]
+    pub fn resize_increments(&self) -> Option<ResizeIncrements> {
+        self.resize_increments
+    }
+
+    /// Restrict interactive resizes to the specified increments.
+    ///
+    /// This is not supported on Wayland and Windows, where the increments are only stored.
+    pub fn set_resize_increments(&mut self, increments: Option<ResizeIncrements>) {
+        if self.resize_increments == increments {
+            return;
+        }
+        self.resize_increments = increments;
+
+        #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+        x_set_resize_increments(self.window(), increments);
+
+        #[cfg(target_os = "macos")]
+        {
+            // NSWindow uses `1x1` to disable the increments.
+            let step = match increments {
+                Some(increments) => {
+                    let step = increments.step.to_logical::<f64>(self.dpr);
+                    NSSize { width: step.width, height: step.height }
+                },
+                None => NSSize { width: 1., height: 1. },
+            };
+
+            let ns_window = self.window().ns_window() as *mut Object;
+            unsafe {
+                let _: () = msg_send![ns_window, setContentResizeIncrements: step];
+            }
+        }
+    }
+
+    /// Set the directory represented by the window's title bar icon, `None` removes the icon.
+    ///
+    /// Directories on other hosts are treated like an unknown directory.
+    #[cfg(target_os = "macos")]
+    pub fn set_represented_directory(&self, directory: Option<&WorkingDirectory>) {
+        let path = directory
+            .filter(|directory| is_local_host(&directory.host))
+            .and_then(|directory| directory.path.to_str())
+            .and_then(|path| CString::new(path).ok());
+
+        let ns_window = self.window().ns_window() as *mut Object;
+        unsafe {
+            let url: *mut Object = match path {
+                Some(path) => {
+                    let string_class = Class::get("NSString").unwrap();
+                    let url_class = Class::get("NSURL").unwrap();
+                    let path: *mut Object =
+                        msg_send![string_class, stringWithUTF8String: path.as_ptr()];
+                    msg_send![url_class, fileURLWithPath: path isDirectory: YES]
+                },
+                None => ptr::null_mut(),
+            };
+            let _: () = msg_send![ns_window, setRepresentedURL: url];
+        }
+    }
+
     pub fn set_outer_position(&self, pos: PhysicalPosition<i32>) {
         self.window().set_outer_position(pos);
     }
 
+    /// Position of the window's top left corner, if the platform exposes it.
+    pub fn outer_position(&self) -> Option<PhysicalPosition<i32>> {
+        self.window().outer_position().ok()
+    }
+
+    pub fn outer_size(&self) -> PhysicalSize<u32> {
+        self.window().outer_size()
+    }
+
+    /// Position and size of the monitor the window is currently on.
+    ///
+    /// This includes the space taken up by panels and docks.
+    pub fn monitor_area(&self) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+        self.window().current_monitor().map(|monitor| (monitor.position(), monitor.size()))
+    }
+
     #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
     pub fn x11_window_id(&self) -> Option<usize> {
         self.window().xlib_window().map(|xlib_window| xlib_window as usize)
@@ -441,6 +605,25 @@ impl Window {
     }
 }
 
+/// Check if a host name refers to the local machine.
+#[cfg(target_os = "macos")]
+fn is_local_host(host: &str) -> bool {
+    if host.is_empty() || host == "localhost" {
+        return true;
+    }
+
+    let mut buf = [0u8; 256];
+    unsafe {
+        // Keep the last byte as NUL, since truncated names are not terminated.
+        if libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len() - 1) != 0 {
+            return false;
+        }
+
+        let hostname = CStr::from_ptr(buf.as_ptr() as *const libc::c_char);
+        hostname.to_bytes().eq_ignore_ascii_case(host.as_bytes())
+    }
+}
+
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 fn x_embed_window(window: &GlutinWindow, parent_id: std::os::raw::c_ulong) {
     let (xlib_display, xlib_window) = match (window.xlib_display(), window.xlib_window()) {
@@ -475,8 +658,105 @@ fn x_embed_window(window: &GlutinWindow, parent_id: std::os::raw::c_ulong) {
     }
 }
 
+#[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
+fn x_set_resize_increments(window: &GlutinWindow, increments: Option<ResizeIncrements>) {
+    let (xlib_display, xlib_window) = match (window.xlib_display(), window.xlib_window()) {
+        (Some(display), Some(window)) => (display, window),
+        _ => return,
+    };
+
+    let xlib = Xlib::open().expect("get xlib");
+
+    unsafe {
+        let hints = (xlib.XAllocSizeHints)();
+        if hints.is_null() {
+            return;
+        }
+
+        // Preserve the hints set by the windowing library.
+        let mut supplied = 0;
+        (xlib.XGetWMNormalHints)(xlib_display as _, xlib_window as _, hints, &mut supplied);
+
+        match increments {
+            Some(increments) => {
+                (*hints).flags |= PResizeInc | PBaseSize;
+                (*hints).width_inc = increments.step.width as i32;
+                (*hints).height_inc = increments.step.height as i32;
+                (*hints).base_width = increments.base.width as i32;
+                (*hints).base_height = increments.base.height as i32;
+            },
+            None => (*hints).flags &= !(PResizeInc | PBaseSize),
+        }
+
+        (xlib.XSetWMNormalHints)(xlib_display as _, xlib_window as _, hints);
+        (xlib.XFlush)(xlib_display as _);
+        (xlib.XFree)(hints as _);
+    }
+}
+
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 unsafe extern "C" fn xembed_error_handler(_: *mut XDisplay, _: *mut XErrorEvent) -> i32 {
     log::error!("Could not embed into specified window.");
     std::process::exit(1);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELL: PhysicalSize<u32> = PhysicalSize { width: 10, height: 20 };
+    const SIZE: PhysicalSize<u32> = PhysicalSize { width: 800, height: 600 };
+
+    fn area(x: i32, y: i32, width: u32, height: u32) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+        (PhysicalPosition::new(x, y), PhysicalSize::new(width, height))
+    }
+
+    #[test]
+    fn cascade_offsets_by_cells() {
+        let origin = PhysicalPosition::new(100, 50);
+        let work_area = area(0, 0, 1920, 1080);
+
+        let position = cascade_position(origin, SIZE, CELL, 1, work_area);
+        assert_eq!(position, PhysicalPosition::new(110, 70));
+
+        let position = cascade_position(origin, SIZE, CELL, 3, work_area);
+        assert_eq!(position, PhysicalPosition::new(130, 110));
+    }
+
+    #[test]
+    fn cascade_wraps_inside_work_area() {
+        let origin = PhysicalPosition::new(1100, 500);
+        let work_area = area(0, 30, 1920, 1050);
+
+        // Window would extend past the bottom of the work area.
+        let position = cascade_position(origin, SIZE, CELL, 1, work_area);
+        assert_eq!(position, PhysicalPosition::new(1110, 69));
+    }
+
+    #[test]
+    fn cascade_on_secondary_monitor() {
+        let origin = PhysicalPosition::new(2000, 100);
+        let work_area = area(1920, 0, 1280, 1024);
+
+        let position = cascade_position(origin, SIZE, CELL, 2, work_area);
+        assert_eq!(position, PhysicalPosition::new(2020, 140));
+    }
+
+    #[test]
+    fn cascade_origin_outside_work_area() {
+        let origin = PhysicalPosition::new(-50, 0);
+        let work_area = area(0, 40, 1920, 1040);
+
+        let position = cascade_position(origin, SIZE, CELL, 1, work_area);
+        assert_eq!(position, PhysicalPosition::new(0, 40));
+    }
+
+    #[test]
+    fn cascade_window_larger_than_work_area() {
+        let origin = PhysicalPosition::new(10, 10);
+        let work_area = area(0, 0, 640, 480);
+
+        let position = cascade_position(origin, SIZE, CELL, 5, work_area);
+        assert_eq!(position, PhysicalPosition::new(0, 0));
+    }
+}
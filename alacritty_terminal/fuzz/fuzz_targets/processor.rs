@@ -0,0 +1,53 @@
+//! Feed arbitrary bytes through the escape sequence parser and verify the grid after every chunk.
+//!
+//! The first byte of every chunk selects an additional operation which is performed before the
+//! rest of the chunk is parsed, allowing the fuzzer to interleave resizes and resets with the
+//! terminal's input.
+
+#![no_main]
+
+use std::io;
+
+use libfuzzer_sys::fuzz_target;
+
+use alacritty_terminal::ansi::{Handler, Processor};
+use alacritty_terminal::config::MockConfig;
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::term::{SizeInfo, Term};
+
+/// Number of bytes processed between operations.
+const CHUNK_SIZE: usize = 64;
+
+/// Largest grid dimensions used for resizing.
+const MAX_COLUMNS: u8 = 100;
+const MAX_LINES: u8 = 50;
+
+fuzz_target!(|data: &[u8]| {
+    let size = SizeInfo::new(80.0, 24.0, 1.0, 1.0, 0.0, 0.0, false);
+    let mut term = Term::new(&MockConfig::default(), size, VoidListener);
+    let mut parser = Processor::new();
+    let mut sink = io::sink();
+
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let (op, bytes) = match chunk.split_first() {
+            Some((op, bytes)) => (*op, bytes),
+            None => continue,
+        };
+
+        match op {
+            0..=7 if bytes.len() >= 2 => {
+                let columns = 1 + bytes[0] % MAX_COLUMNS;
+                let lines = 1 + bytes[1] % MAX_LINES;
+                term.resize(SizeInfo::new(columns as f32, lines as f32, 1.0, 1.0, 0.0, 0.0, false));
+            },
+            8 => term.reset_state(),
+            _ => (),
+        }
+
+        for byte in bytes {
+            parser.advance(&mut term, *byte, &mut sink);
+        }
+
+        term.verify_invariants();
+    }
+});
@@ -1,14 +1,40 @@
 //! ANSI Terminal Stream Parsing.
 
 use std::convert::TryFrom;
-use std::{io, iter, str};
+use std::path::PathBuf;
+use std::{io, iter, mem, str};
 
+use bitflags::bitflags;
 use log::{debug, trace};
 use serde::{Deserialize, Serialize};
-use vte::{Params, ParamsIter};
+use vte::Params;
 
 use crate::index::{Column, Line};
+use crate::term::cell::Hyperlink;
 use crate::term::color::Rgb;
+use crate::term::image::{GraphicsCommand, SixelImage};
+use crate::term::semantic::SemanticZone;
+use crate::term::sixel::SixelDecoder;
+
+/// Maximum length of an APC string, longer strings are discarded.
+const MAX_APC_LEN: usize = 1024 * 1024;
+
+/// Maximum length of an XTGETTCAP query, longer queries are truncated.
+const MAX_XTGETTCAP_LEN: usize = 4096;
+
+/// Decode a string of hexadecimal byte pairs.
+fn decode_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    hex.chunks(2)
+        .map(|pair| match str::from_utf8(pair) {
+            Ok(pair) if pair.len() == 2 => u8::from_str_radix(pair, 16).ok(),
+            _ => None,
+        })
+        .collect()
+}
 
 /// Parse colors in XParseColor format.
 fn xparse_color(color: &[u8]) -> Option<Rgb> {
@@ -79,6 +105,46 @@ fn parse_number(input: &[u8]) -> Option<u8> {
     Some(num)
 }
 
+/// Working directory reported through OSC 7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingDirectory {
+    /// Host the directory is located on, empty for the local host.
+    pub host: String,
+
+    /// Absolute path of the directory.
+    pub path: PathBuf,
+}
+
+/// Parse a working directory from a `file://host/path` URL.
+fn parse_working_directory(url: &str) -> Option<WorkingDirectory> {
+    if !url.starts_with("file://") {
+        return None;
+    }
+
+    let url = &url["file://".len()..];
+    let path_start = url.find('/')?;
+    let path = percent_decode(&url[path_start..])?;
+
+    Some(WorkingDirectory { host: url[..path_start].into(), path: path.into() })
+}
+
+/// Decode all `%XX` escapes of a URL component.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = char::from(iter.next()?).to_digit(16)?;
+            let low = char::from(iter.next()?).to_digit(16)?;
+            bytes.push((high * 16 + low) as u8);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
 /// The processor wraps a `vte::Parser` to ultimately call methods on a Handler.
 pub struct Processor {
     state: ProcessorState,
@@ -88,6 +154,160 @@ pub struct Processor {
 /// Internal state for VTE processor.
 struct ProcessorState {
     preceding_char: Option<char>,
+
+    /// Progress of the APC string and OSC 52 detection.
+    string_state: StringState,
+
+    /// Content of the APC string being received.
+    apc: Vec<u8>,
+
+    /// Clipboard selected by the OSC 52 sequence being received.
+    osc52_clipboard: Option<u8>,
+
+    /// Whether the OSC 52 payload is a clipboard query.
+    osc52_query: bool,
+
+    /// Decoded payload of the OSC 52 sequence being received.
+    osc52_payload: Base64Decoder,
+
+    /// Decoder for the sixel DCS sequence being received.
+    sixel: Option<SixelDecoder>,
+
+    /// Hex-encoded capability names of the XTGETTCAP query being received.
+    xtgettcap: Option<Vec<u8>>,
+}
+
+/// State of the APC string and OSC 52 detection.
+///
+/// The VTE parser ignores the content of APC strings and truncates long OSC strings, so APC strings
+/// and clipboard payloads are collected separately. Bytes are only inspected after an ESC, until
+/// the sequence turns out to be neither.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum StringState {
+    /// Not inside an APC string or OSC 52 sequence.
+    Ground,
+
+    /// ESC received, which might start an APC or OSC string.
+    Escape,
+
+    /// Inside an APC string.
+    Apc,
+
+    /// ESC received inside an APC string.
+    ApcEscape,
+
+    /// Inside an OSC string, with the number of matching bytes of the `52;` prefix.
+    Osc52Prefix(usize),
+
+    /// Receiving the clipboard selection.
+    Osc52Selection,
+
+    /// Receiving the base64 payload.
+    Osc52Payload,
+
+    /// ESC received inside the payload.
+    Osc52PayloadEscape,
+}
+
+/// Incremental decoder for base64 encoded clipboard data.
+#[derive(Debug, Default)]
+struct Base64Decoder {
+    /// Data decoded so far.
+    data: Vec<u8>,
+
+    /// Maximum size of the decoded data.
+    limit: usize,
+
+    /// Sextets of the incomplete quad.
+    quad: [u8; 4],
+    quad_len: usize,
+
+    /// Padding characters in the incomplete quad.
+    padding: usize,
+
+    /// Whether a padded quad was received, which must be the last one.
+    finished: bool,
+
+    /// Reason for rejecting the payload.
+    error: Option<&'static str>,
+}
+
+impl Base64Decoder {
+    fn new(limit: usize) -> Self {
+        Self { limit, ..Default::default() }
+    }
+
+    /// Whether no data has been received.
+    fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.quad_len == 0 && self.error.is_none()
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let sextet = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => 0,
+            _ => return self.fail("invalid base64 character"),
+        };
+
+        // Padding is only allowed at the end of the last quad.
+        let padding = byte == b'=';
+        if self.finished || (padding && self.quad_len < 2) || (!padding && self.padding > 0) {
+            return self.fail("invalid base64 padding");
+        }
+
+        self.quad[self.quad_len] = sextet;
+        self.quad_len += 1;
+        self.padding += padding as usize;
+
+        if self.quad_len == 4 {
+            self.flush(3 - self.padding);
+            self.finished = self.padding > 0;
+            self.quad_len = 0;
+        }
+    }
+
+    /// Complete decoding, returning the decoded data.
+    fn finish(&mut self) -> Result<Vec<u8>, &'static str> {
+        // Missing padding is tolerated, but padding must not be incomplete.
+        match (self.quad_len, self.padding) {
+            (0, _) => (),
+            (2, 0) | (3, 0) => self.flush(self.quad_len - 1),
+            _ => self.fail("invalid base64 padding"),
+        }
+
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(mem::take(&mut self.data)),
+        }
+    }
+
+    /// Append `len` bytes of the current quad to the decoded data.
+    fn flush(&mut self, len: usize) {
+        if self.error.is_some() {
+            return;
+        }
+
+        if self.data.len() + len > self.limit {
+            return self.fail("payload exceeds size limit");
+        }
+
+        let [a, b, c, d] = self.quad;
+        let bytes = [a << 2 | b >> 4, b << 4 | c >> 2, c << 6 | d];
+        self.data.extend_from_slice(&bytes[..len]);
+    }
+
+    fn fail(&mut self, error: &'static str) {
+        self.error = Some(error);
+        self.data = Vec::new();
+    }
 }
 
 /// Helper type that implements `vte::Perform`.
@@ -110,11 +330,52 @@ impl<'a, H: Handler + 'a, W: io::Write> Performer<'a, H, W> {
     ) -> Performer<'b, H, W> {
         Performer { state, handler, writer }
     }
+
+    /// Dispatch a complete APC string.
+    fn apc_dispatch(&mut self) {
+        match self.state.apc.split_first() {
+            Some((b'G', command)) => match GraphicsCommand::parse(command) {
+                Some(command) => self.handler.graphics_command(command),
+                None => debug!("[unhandled apc_dispatch] invalid graphics command"),
+            },
+            _ => debug!(
+                "[unhandled apc_dispatch] apc={:?}",
+                String::from_utf8_lossy(&self.state.apc)
+            ),
+        }
+    }
+
+    /// Dispatch a complete OSC 52 sequence.
+    fn osc52_dispatch(&mut self, terminator: &str) {
+        let clipboard = self.state.osc52_clipboard.unwrap_or(b'c');
+
+        if self.state.osc52_query {
+            self.handler.clipboard_load(clipboard, terminator);
+            return;
+        }
+
+        match self.state.osc52_payload.finish() {
+            Ok(data) => self.handler.clipboard_store(clipboard, &data),
+            Err(err) => debug!("[unhandled osc52_dispatch] {}", err),
+        }
+    }
 }
 
 impl Default for Processor {
     fn default() -> Processor {
-        Processor { state: ProcessorState { preceding_char: None }, parser: vte::Parser::new() }
+        Processor {
+            state: ProcessorState {
+                preceding_char: None,
+                string_state: StringState::Ground,
+                apc: Vec::new(),
+                osc52_clipboard: None,
+                osc52_query: false,
+                osc52_payload: Base64Decoder::default(),
+                sixel: None,
+                xtgettcap: None,
+            },
+            parser: vte::Parser::new(),
+        }
     }
 }
 
@@ -131,6 +392,96 @@ impl Processor {
     {
         let mut performer = Performer::new(&mut self.state, handler, writer);
         self.parser.advance(&mut performer, byte);
+
+        if self.state.string_state != StringState::Ground || byte == C0::ESC {
+            self.advance_string(handler, byte, writer);
+        }
+    }
+
+    /// Track APC strings and OSC 52 payloads alongside the VTE parser.
+    fn advance_string<H, W>(&mut self, handler: &mut H, byte: u8, writer: &mut W)
+    where
+        H: Handler,
+        W: io::Write,
+    {
+        let state = &mut self.state;
+
+        // Only the string terminator completes a string, any other escape aborts it.
+        let string_state = match state.string_state {
+            StringState::ApcEscape | StringState::Osc52PayloadEscape if byte != b'\\' => {
+                StringState::Escape
+            },
+            string_state => string_state,
+        };
+
+        state.string_state = match (string_state, byte) {
+            (StringState::Escape, b'_') => {
+                state.apc.clear();
+                StringState::Apc
+            },
+            (StringState::Escape, b']') => StringState::Osc52Prefix(0),
+            (StringState::ApcEscape, _) => {
+                if state.apc.len() < MAX_APC_LEN {
+                    Performer::new(state, handler, writer).apc_dispatch();
+                } else {
+                    debug!("[unhandled apc_dispatch] APC string exceeds {} bytes", MAX_APC_LEN);
+                }
+                StringState::Ground
+            },
+            (StringState::Apc, C0::ESC) => StringState::ApcEscape,
+            (StringState::Apc, C0::CAN) | (StringState::Apc, C0::SUB) => StringState::Ground,
+            (StringState::Apc, _) => {
+                if state.apc.len() < MAX_APC_LEN {
+                    state.apc.push(byte);
+                }
+                StringState::Apc
+            },
+            (StringState::Osc52Prefix(2), b';') => {
+                state.osc52_clipboard = None;
+                StringState::Osc52Selection
+            },
+            (StringState::Osc52Prefix(matched), _) if matched < 2 && byte == b"52"[matched] => {
+                StringState::Osc52Prefix(matched + 1)
+            },
+            (StringState::Osc52Selection, b';') => {
+                state.osc52_query = false;
+                state.osc52_payload = Base64Decoder::new(handler.clipboard_store_limit());
+                StringState::Osc52Payload
+            },
+            (StringState::Osc52Selection, _) if byte >= 0x20 => {
+                state.osc52_clipboard = state.osc52_clipboard.or(Some(byte));
+                StringState::Osc52Selection
+            },
+            (StringState::Osc52Payload, C0::BEL) => {
+                Performer::new(state, handler, writer).osc52_dispatch("\x07");
+                StringState::Ground
+            },
+            (StringState::Osc52Payload, C0::ESC) => StringState::Osc52PayloadEscape,
+            (StringState::Osc52Payload, b'?')
+                if !state.osc52_query && state.osc52_payload.is_empty() =>
+            {
+                state.osc52_query = true;
+                StringState::Osc52Payload
+            },
+            (StringState::Osc52Payload, _) if byte >= 0x20 => {
+                if state.osc52_query {
+                    state.osc52_query = false;
+                    state.osc52_payload.fail("invalid clipboard query");
+                }
+                state.osc52_payload.push(byte);
+                StringState::Osc52Payload
+            },
+            // Like in the parser, other C0 controls are ignored inside the string.
+            (StringState::Osc52Payload, _) if byte != C0::CAN && byte != C0::SUB => {
+                StringState::Osc52Payload
+            },
+            (StringState::Osc52PayloadEscape, _) => {
+                Performer::new(state, handler, writer).osc52_dispatch("\x1b\\");
+                StringState::Ground
+            },
+            (_, C0::ESC) => StringState::Escape,
+            (..) => StringState::Ground,
+        };
     }
 }
 
@@ -177,6 +528,9 @@ pub trait Handler {
     /// Report device status.
     fn device_status<W: io::Write>(&mut self, _: &mut W, _: usize) {}
 
+    /// Report the terminal name and version (XTVERSION).
+    fn report_version<W: io::Write>(&mut self, _: &mut W) {}
+
     /// Move cursor forward `cols`.
     fn move_forward(&mut self, _: Column) {}
 
@@ -279,6 +633,9 @@ pub trait Handler {
     /// Unset mode.
     fn unset_mode(&mut self, _: Mode) {}
 
+    /// Report whether a mode is set (DECRQM).
+    fn report_mode<W: io::Write>(&mut self, _: &mut W, _intermediate: Option<&u8>, _mode: u16) {}
+
     /// DECSTBM - Set the terminal scrolling region.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
@@ -312,6 +669,20 @@ pub trait Handler {
     /// Store data into clipboard.
     fn clipboard_store(&mut self, _: u8, _: &[u8]) {}
 
+    /// Maximum size of the data stored into clipboard by a single sequence.
+    fn clipboard_store_limit(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Set the hyperlink for all following cells, `None` ends the hyperlink.
+    fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
+
+    /// Set the semantic zone of all following cells, `None` ends the command output.
+    fn set_semantic_zone(&mut self, _: Option<SemanticZone>) {}
+
+    /// Set the current working directory, `None` if it is unknown.
+    fn set_working_directory(&mut self, _: Option<WorkingDirectory>) {}
+
     /// Load data from clipboard.
     fn clipboard_load(&mut self, _: u8, _: &str) {}
 
@@ -329,6 +700,78 @@ pub trait Handler {
 
     /// Report text area size in characters.
     fn text_area_size_chars<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Handle a kitty graphics protocol command.
+    fn graphics_command(&mut self, _: GraphicsCommand) {}
+
+    /// Display a sixel image at the cursor.
+    fn sixel_image(&mut self, _: SixelImage) {}
+
+    /// Push the current kitty keyboard protocol flags onto the stack and replace them.
+    fn push_keyboard_mode(&mut self, _: KittyKeyboardFlags) {}
+
+    /// Restore the kitty keyboard protocol flags from the stack, popping `to_pop` entries.
+    fn pop_keyboard_modes(&mut self, _to_pop: u16) {}
+
+    /// Change the current kitty keyboard protocol flags.
+    fn set_keyboard_mode(&mut self, _: KittyKeyboardFlags, _: KeyboardModeBehavior) {}
+
+    /// Report the current kitty keyboard protocol flags.
+    fn report_keyboard_mode<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Set xterm's modifyOtherKeys resource.
+    fn set_modify_other_keys(&mut self, _: ModifyOtherKeys) {}
+
+    /// Report the current modifyOtherKeys resource.
+    fn report_modify_other_keys<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Report the value of a terminfo capability (XTGETTCAP).
+    ///
+    /// The name is `None` if it could not be decoded, the hex encoded name is passed as it was
+    /// received.
+    fn report_capability(&mut self, _name: Option<&str>, _hex_name: &[u8]) {}
+}
+
+/// Level of xterm's modifyOtherKeys resource.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModifyOtherKeys {
+    /// Keys with modifiers use their regular encoding.
+    Reset,
+
+    /// Encode keys with modifiers which do not have a well-known encoding.
+    EnableExceptWellDefined,
+
+    /// Encode all keys with modifiers, including those with well-known control codes.
+    EnableAll,
+}
+
+bitflags! {
+    /// Progressive enhancements of the kitty keyboard protocol.
+    pub struct KittyKeyboardFlags: u8 {
+        /// Report Esc and keys with modifiers using `CSI u`, so they are not ambiguous.
+        const DISAMBIGUATE_ESC_CODES = 0b0000_0001;
+        /// Report key repeat and release events.
+        const REPORT_EVENT_TYPES     = 0b0000_0010;
+        /// Report the shifted and base layout key in addition to the key code.
+        const REPORT_ALTERNATE_KEYS  = 0b0000_0100;
+        /// Report all keys using escapes, including keys which produce text.
+        const REPORT_ALL_KEYS_AS_ESC = 0b0000_1000;
+        /// Report the text produced by a key along with its escape.
+        const REPORT_ASSOCIATED_TEXT = 0b0001_0000;
+    }
+}
+
+/// How the flags of `CSI = flags ; mode u` are applied to the current flags.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyboardModeBehavior {
+    /// Replace all current flags.
+    Replace,
+
+    /// Set the specified flags, keeping all others.
+    Union,
+
+    /// Clear the specified flags, keeping all others.
+    Difference,
 }
 
 /// Terminal cursor configuration.
@@ -389,6 +832,8 @@ pub enum Mode {
     /// * `CSI 4 h` change to insert mode
     /// * `CSI 4 l` reset to replacement mode
     Insert = 4,
+    /// ?5
+    ReverseVideo = 5,
     /// ?6
     Origin = 6,
     /// ?7
@@ -422,6 +867,10 @@ pub enum Mode {
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
     BracketedPaste = 2004,
+    /// ?2026
+    SyncUpdate = 2026,
+    /// ?9001
+    Win32Input = 9001,
 }
 
 impl Mode {
@@ -439,6 +888,7 @@ impl Mode {
             Some(match num {
                 1 => Mode::CursorKeys,
                 3 => Mode::DECCOLM,
+                5 => Mode::ReverseVideo,
                 6 => Mode::Origin,
                 7 => Mode::LineWrap,
                 12 => Mode::BlinkingCursor,
@@ -453,6 +903,8 @@ impl Mode {
                 1042 => Mode::UrgencyHints,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
+                2026 => Mode::SyncUpdate,
+                9001 => Mode::Win32Input,
                 _ => {
                     trace!("[unimplemented] primitive mode: {}", num);
                     return None;
@@ -643,9 +1095,11 @@ pub enum Attr {
     Underline,
     /// Underlined twice.
     DoubleUnderline,
-    /// Blink cursor slowly.
+    /// Curly underline.
+    Undercurl,
+    /// Blink text slowly.
     BlinkSlow,
-    /// Blink cursor fast.
+    /// Blink text fast.
     BlinkFast,
     /// Invert colors.
     Reverse,
@@ -653,6 +1107,8 @@ pub enum Attr {
     Hidden,
     /// Strikeout text.
     Strike,
+    /// Overline text.
+    Overline,
     /// Cancel bold.
     CancelBold,
     /// Cancel bold and dim.
@@ -669,10 +1125,14 @@ pub enum Attr {
     CancelHidden,
     /// Cancel strikeout.
     CancelStrike,
+    /// Cancel overline.
+    CancelOverline,
     /// Set indexed foreground color.
     Foreground(Color),
     /// Set indexed background color.
     Background(Color),
+    /// Set underline color, `None` resets it to the foreground color.
+    UnderlineColor(Option<Color>),
 }
 
 /// Identifiers which can be assigned to a graphic character set.
@@ -776,21 +1236,49 @@ where
     }
 
     #[inline]
-    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, _c: char) {
-        debug!(
-            "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
-            params, intermediates, ignore
-        );
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, c: char) {
+        match (c, intermediates) {
+            ('q', []) if !ignore => {
+                let params: Vec<u16> = params.iter().map(|param| param[0]).collect();
+                self.state.sixel = Some(SixelDecoder::new(&params));
+            },
+            ('q', [b'+']) if !ignore => self.state.xtgettcap = Some(Vec::new()),
+            _ => debug!(
+                "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
+                params, intermediates, ignore, c
+            ),
+        }
     }
 
     #[inline]
     fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
+        match (&mut self.state.sixel, &mut self.state.xtgettcap) {
+            (Some(sixel), _) => sixel.put(byte),
+            (None, Some(query)) if query.len() < MAX_XTGETTCAP_LEN => query.push(byte),
+            (None, Some(_)) => (),
+            (None, None) => debug!("[unhandled put] byte={:?}", byte),
+        }
     }
 
     #[inline]
     fn unhook(&mut self) {
-        debug!("[unhandled unhook]");
+        match self.state.sixel.take() {
+            Some(sixel) => match sixel.finish() {
+                Some(image) => self.handler.sixel_image(image),
+                None => debug!("[unhandled unhook] empty sixel image"),
+            },
+            None => match self.state.xtgettcap.take() {
+                Some(query) => {
+                    // Names are separated by semicolons, but commas are accepted too.
+                    for hex_name in query.split(|&b| b == b';' || b == b',') {
+                        let name = decode_hex(hex_name);
+                        let name = name.as_ref().and_then(|name| str::from_utf8(name).ok());
+                        self.handler.report_capability(name, hex_name);
+                    }
+                },
+                None => debug!("[unhandled unhook]"),
+            },
+        }
     }
 
     // TODO replace OSC parsing with parser combinators.
@@ -900,17 +1388,53 @@ where
                 unhandled(params);
             },
 
-            // Set clipboard.
-            b"52" => {
-                if params.len() < 3 {
-                    return unhandled(params);
-                }
+            // Set working directory.
+            b"7" => {
+                // Paths may contain semicolons, which are split up by the parser.
+                let url = params[1..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
 
-                let clipboard = params[1].get(0).unwrap_or(&b'c');
-                match params[2] {
-                    b"?" => self.handler.clipboard_load(*clipboard, terminator),
-                    base64 => self.handler.clipboard_store(*clipboard, base64),
+                self.handler.set_working_directory(parse_working_directory(&url));
+            },
+
+            // Hyperlink.
+            b"8" if params.len() > 2 => {
+                // URIs may contain semicolons, which are split up by the parser.
+                let uri = params[2..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+
+                if uri.is_empty() {
+                    self.handler.set_hyperlink(None);
+                    return;
                 }
+
+                // Parameters are `key=value` pairs separated by colons, only `id` is used.
+                let id = params[1]
+                    .split(|&b| b == b':')
+                    .filter(|param| param.starts_with(b"id=") && param.len() > 3)
+                    .flat_map(|param| str::from_utf8(&param[3..]))
+                    .next()
+                    .map(String::from);
+
+                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+            },
+
+            // Clipboard access is handled by the processor, since the parser truncates the data.
+            b"52" => (),
+
+            // Shell integration marks, additional parameters like exit codes are ignored.
+            b"133" if params.len() > 1 => match params[1] {
+                b"A" => self.handler.set_semantic_zone(Some(SemanticZone::Prompt)),
+                b"B" => self.handler.set_semantic_zone(Some(SemanticZone::Input)),
+                b"C" => self.handler.set_semantic_zone(Some(SemanticZone::Output)),
+                b"D" => self.handler.set_semantic_zone(None),
+                _ => unhandled(params),
             },
 
             // Reset color index.
@@ -963,15 +1487,25 @@ where
             }};
         }
 
+        let mut params_iter = params.iter();
+        let handler = &mut self.handler;
+        let writer = &mut self.writer;
+
+        // DECRQM is the only escape with two intermediates, for private modes.
+        if !has_ignored_intermediates && action == 'p' {
+            let mode = params_iter.next().map(|param| param[0]).unwrap_or(0);
+            match intermediates {
+                [b'$'] => return handler.report_mode(writer, None, mode),
+                [b'?', b'$'] => return handler.report_mode(writer, Some(&b'?'), mode),
+                _ => (),
+            }
+        }
+
         if has_ignored_intermediates || intermediates.len() > 1 {
             unhandled!();
             return;
         }
 
-        let mut params_iter = params.iter();
-        let handler = &mut self.handler;
-        let writer = &mut self.writer;
-
         let mut next_param_or = |default: u16| {
             params_iter.next().map(|param| param[0]).filter(|&param| param != 0).unwrap_or(default)
         };
@@ -1075,8 +1609,23 @@ where
                     }
                 }
             },
+            ('m', Some(b'>')) => match (next_param_or(0), next_param_or(0)) {
+                (4, 0) => handler.set_modify_other_keys(ModifyOtherKeys::Reset),
+                (4, 1) => handler.set_modify_other_keys(ModifyOtherKeys::EnableExceptWellDefined),
+                (4, 2) => handler.set_modify_other_keys(ModifyOtherKeys::EnableAll),
+                _ => unhandled!(),
+            },
+            ('m', Some(b'?')) => match next_param_or(0) {
+                4 => handler.report_modify_other_keys(writer),
+                _ => unhandled!(),
+            },
             ('n', None) => handler.device_status(writer, next_param_or(0) as usize),
+            ('n', Some(b'>')) => match next_param_or(0) {
+                4 => handler.set_modify_other_keys(ModifyOtherKeys::Reset),
+                _ => unhandled!(),
+            },
             ('P', None) => handler.delete_chars(Column(next_param_or(1) as usize)),
+            ('q', Some(b'>')) if next_param_or(0) == 0 => handler.report_version(writer),
             ('q', Some(b' ')) => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style.
                 let cursor_style_id = next_param_or(0);
@@ -1113,6 +1662,27 @@ where
                 _ => unhandled!(),
             },
             ('u', None) => handler.restore_cursor_position(),
+            ('u', Some(b'?')) => handler.report_keyboard_mode(writer),
+            ('u', Some(b'>')) => {
+                handler.push_keyboard_mode(KittyKeyboardFlags::from_bits_truncate(
+                    next_param_or(0) as u8,
+                ));
+            },
+            ('u', Some(b'<')) => handler.pop_keyboard_modes(next_param_or(1)),
+            ('u', Some(b'=')) => {
+                let flags = KittyKeyboardFlags::from_bits_truncate(next_param_or(0) as u8);
+                let behavior = match next_param_or(1) {
+                    1 => KeyboardModeBehavior::Replace,
+                    2 => KeyboardModeBehavior::Union,
+                    3 => KeyboardModeBehavior::Difference,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.set_keyboard_mode(flags, behavior);
+            },
             ('X', None) => handler.erase_chars(Column(next_param_or(1) as usize)),
             ('Z', None) => handler.move_backward_tabs(next_param_or(1)),
             _ => unhandled!(),
@@ -1172,7 +1742,11 @@ where
     }
 }
 
-fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
+/// Parse SGR parameters, with each parameter consisting of its colon separated subparameters.
+pub(crate) fn attrs_from_sgr_parameters<'a, I>(params: &mut I) -> Vec<Option<Attr>>
+where
+    I: Iterator<Item = &'a [u16]>,
+{
     let mut attrs = Vec::with_capacity(params.size_hint().0);
 
     while let Some(param) = params.next() {
@@ -1183,6 +1757,7 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
             [3] => Some(Attr::Italic),
             [4, 0] => Some(Attr::CancelUnderline),
             [4, 2] => Some(Attr::DoubleUnderline),
+            [4, 3] => Some(Attr::Undercurl),
             [4, ..] => Some(Attr::Underline),
             [5] => Some(Attr::BlinkSlow),
             [6] => Some(Attr::BlinkFast),
@@ -1237,6 +1812,20 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
                 parse_sgr_color(&mut iter).map(Attr::Background)
             },
             [49] => Some(Attr::Background(Color::Named(NamedColor::Background))),
+            [53] => Some(Attr::Overline),
+            [55] => Some(Attr::CancelOverline),
+            [58] => {
+                let mut iter = params.map(|param| param[0]);
+                parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
+            },
+            [58, params @ ..] => {
+                let rgb_start = if params.len() > 4 { 2 } else { 1 };
+                let rgb_iter = params[rgb_start..].iter().copied();
+                let mut iter = iter::once(params[0]).chain(rgb_iter);
+
+                parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
+            },
+            [59] => Some(Attr::UnderlineColor(None)),
             [90] => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlack))),
             [91] => Some(Attr::Foreground(Color::Named(NamedColor::BrightRed))),
             [92] => Some(Attr::Foreground(Color::Named(NamedColor::BrightGreen))),
@@ -1352,8 +1941,11 @@ pub mod C0 {
 mod tests {
     use super::{
         parse_number, xparse_color, Attr, CharsetIndex, Color, Handler, Processor, StandardCharset,
+        WorkingDirectory,
     };
+    use crate::term::cell::Hyperlink;
     use crate::term::color::Rgb;
+    use crate::term::image::{Action, GraphicsCommand};
     use std::io;
 
     struct MockHandler {
@@ -1361,6 +1953,12 @@ mod tests {
         charset: StandardCharset,
         attr: Option<Attr>,
         identity_reported: bool,
+        hyperlink: Option<Hyperlink>,
+        working_directory: Option<WorkingDirectory>,
+        graphics: Vec<GraphicsCommand>,
+        clipboard: Vec<(u8, Vec<u8>)>,
+        clipboard_queries: Vec<(u8, String)>,
+        clipboard_limit: usize,
     }
 
     impl Handler for MockHandler {
@@ -1384,6 +1982,30 @@ mod tests {
         fn reset_state(&mut self) {
             *self = Self::default();
         }
+
+        fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+            self.hyperlink = hyperlink;
+        }
+
+        fn set_working_directory(&mut self, working_directory: Option<WorkingDirectory>) {
+            self.working_directory = working_directory;
+        }
+
+        fn graphics_command(&mut self, command: GraphicsCommand) {
+            self.graphics.push(command);
+        }
+
+        fn clipboard_store(&mut self, clipboard: u8, data: &[u8]) {
+            self.clipboard.push((clipboard, data.to_vec()));
+        }
+
+        fn clipboard_store_limit(&self) -> usize {
+            self.clipboard_limit
+        }
+
+        fn clipboard_load(&mut self, clipboard: u8, terminator: &str) {
+            self.clipboard_queries.push((clipboard, terminator.to_owned()));
+        }
     }
 
     impl Default for MockHandler {
@@ -1393,6 +2015,12 @@ mod tests {
                 charset: StandardCharset::Ascii,
                 attr: None,
                 identity_reported: false,
+                hyperlink: None,
+                working_directory: None,
+                graphics: Vec::new(),
+                clipboard: Vec::new(),
+                clipboard_queries: Vec::new(),
+                clipboard_limit: usize::MAX,
             }
         }
     }
@@ -1411,6 +2039,184 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Bold));
     }
 
+    #[test]
+    fn parse_graphics_command() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b_Ga=q,i=1;AAAA\x1b\\\x1b_Xignored\x1b\\\x1b[1m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.graphics.len(), 1);
+        assert_eq!(handler.graphics[0].action, Action::Query);
+        assert_eq!(handler.graphics[0].id, 1);
+        assert_eq!(handler.graphics[0].payload, b"AAAA");
+        assert_eq!(handler.attr, Some(Attr::Bold));
+    }
+
+    #[test]
+    fn parse_large_osc52() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let data = (0..100_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let base64 = base64::encode(&data);
+        let bytes = format!("\x1b]52;c;{}\x07\x1b]52;p;{}\x1b\\\x1b]52;s;?\x1b\\", base64, base64);
+        for byte in bytes.bytes() {
+            parser.advance(&mut handler, byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.clipboard, vec![(b'c', data.clone()), (b'p', data)]);
+        assert_eq!(handler.clipboard_queries, vec![(b's', String::from("\x1b\\"))]);
+    }
+
+    #[test]
+    fn parse_invalid_osc52() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for payload in &["YQ=", "Y===", "YQ==YQ==", "YW=j", "YW*j", "?YWJj", "Y"] {
+            for byte in format!("\x1b]52;c;{}\x07", payload).bytes() {
+                parser.advance(&mut handler, byte, &mut io::sink());
+            }
+        }
+
+        assert!(handler.clipboard.is_empty());
+        assert!(handler.clipboard_queries.is_empty());
+
+        // Missing padding is tolerated.
+        for byte in b"\x1b]52;;YWI\x07\x1b]52;c;\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.clipboard, vec![(b'c', b"ab".to_vec()), (b'c', Vec::new())]);
+    }
+
+    #[test]
+    fn parse_unterminated_strings() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        // Escapes other than the string terminator abort the string.
+        for byte in b"\x1b]52;c;YWJj\x1b[1m\x1b_Ga=q,i=1;AAAA\x1b]52;c;YWI\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.attr, Some(Attr::Bold));
+        assert!(handler.graphics.is_empty());
+        assert_eq!(handler.clipboard, vec![(b'c', b"ab".to_vec())]);
+    }
+
+    #[test]
+    fn parse_osc52_size_limit() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler { clipboard_limit: 3, ..MockHandler::default() };
+
+        // The limit applies to every sequence separately.
+        for byte in b"\x1b]52;c;YWJj\x07\x1b]52;c;YWJjZA==\x07\x1b]52;c;YWJj\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.clipboard, vec![(b'c', b"abc".to_vec()), (b'c', b"abc".to_vec())]);
+    }
+
+    #[test]
+    fn parse_undercurl_attribute() {
+        static BYTES: &[u8] = b"\x1b[4:3m";
+
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.attr, Some(Attr::Undercurl));
+    }
+
+    #[test]
+    fn parse_underline_color_attribute() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[58;2;128;66;255m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let spec = Color::Spec(Rgb { r: 128, g: 66, b: 255 });
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(spec))));
+
+        for byte in b"\x1b[58:5:42m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(Color::Indexed(42)))));
+
+        for byte in b"\x1b[59m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(None)));
+    }
+
+    #[test]
+    fn parse_hyperlink() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]8;id=1;https://example.org/?a=1;b=2\x1b\\" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let hyperlink = Hyperlink {
+            id: Some(String::from("1")),
+            uri: String::from("https://example.org/?a=1;b=2"),
+        };
+        assert_eq!(handler.hyperlink, Some(hyperlink));
+
+        for byte in b"\x1b]8;;file:///tmp\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let hyperlink = Hyperlink { id: None, uri: String::from("file:///tmp") };
+        assert_eq!(handler.hyperlink, Some(hyperlink));
+
+        for byte in b"\x1b]8;;\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.hyperlink, None);
+    }
+
+    #[test]
+    fn parse_working_directory() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b]7;file://host/tmp/a%20b;c\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let working_directory =
+            WorkingDirectory { host: String::from("host"), path: "/tmp/a b;c".into() };
+        assert_eq!(handler.working_directory, Some(working_directory));
+
+        for byte in b"\x1b]7;file:///\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let working_directory = WorkingDirectory { host: String::new(), path: "/".into() };
+        assert_eq!(handler.working_directory, Some(working_directory));
+
+        // Unsupported URLs and invalid escapes reset the working directory.
+        for byte in b"\x1b]7;file:///tmp/%zz\x07" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.working_directory, None);
+    }
+
     #[test]
     fn parse_terminal_identity_csi() {
         let bytes: &[u8] = &[0x1b, b'[', b'1', b'c'];
@@ -61,6 +61,18 @@ pub struct Config<T> {
     #[serde(default, deserialize_with = "option_explicit_none")]
     pub working_directory: Option<PathBuf>,
 
+    /// Directory used to persist the scrollback history.
+    #[serde(default, deserialize_with = "option_explicit_none")]
+    pub scrollback_persistence: Option<PathBuf>,
+
+    /// Support for displaying images using the kitty graphics protocol.
+    #[serde(default, deserialize_with = "failure_default")]
+    pub kitty_graphics: bool,
+
+    /// Support for displaying images using sixel graphics.
+    #[serde(default, deserialize_with = "failure_default")]
+    pub sixel_graphics: bool,
+
     /// Additional configuration options not directly required by the terminal.
     #[serde(flatten)]
     pub ui_config: T,
@@ -102,12 +114,75 @@ pub struct Selection {
     semantic_escape_chars: EscapeChars,
     #[serde(deserialize_with = "failure_default")]
     pub save_to_clipboard: bool,
+    #[serde(deserialize_with = "failure_default")]
+    pub line_selection: LineSelection,
+    #[serde(deserialize_with = "failure_default")]
+    pub osc52: Osc52,
+    #[serde(deserialize_with = "failure_default")]
+    osc52_max_size: Osc52MaxSize,
 }
 
 impl Selection {
     pub fn semantic_escape_chars(&self) -> &str {
         &self.semantic_escape_chars.0
     }
+
+    /// Maximum size of the clipboard data written by a single OSC 52 sequence.
+    pub fn osc52_max_size(&self) -> usize {
+        self.osc52_max_size.0
+    }
+}
+
+/// Extent of line selections.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineSelection {
+    /// Select a single row of the grid.
+    Visual,
+
+    /// Select the entire line, including all rows it wraps over.
+    Logical,
+}
+
+impl Default for LineSelection {
+    fn default() -> Self {
+        LineSelection::Logical
+    }
+}
+
+/// Clipboard access granted to applications through `OSC 52`.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Osc52 {
+    /// Clipboard access is denied.
+    Disabled,
+
+    /// Applications may only write to the clipboard.
+    OnlyCopy,
+
+    /// Applications may only read from the clipboard.
+    OnlyPaste,
+
+    /// Applications may read and write the clipboard.
+    CopyPaste,
+}
+
+impl Default for Osc52 {
+    fn default() -> Self {
+        Osc52::OnlyCopy
+    }
+}
+
+impl Osc52 {
+    /// Check if applications may write to the clipboard.
+    #[inline]
+    pub fn allows_copy(self) -> bool {
+        matches!(self, Osc52::OnlyCopy | Osc52::CopyPaste)
+    }
+
+    /// Check if applications may read from the clipboard.
+    #[inline]
+    pub fn allows_paste(self) -> bool {
+        matches!(self, Osc52::OnlyPaste | Osc52::CopyPaste)
+    }
 }
 
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -119,6 +194,15 @@ impl Default for EscapeChars {
     }
 }
 
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+struct Osc52MaxSize(usize);
+
+impl Default for Osc52MaxSize {
+    fn default() -> Self {
+        Osc52MaxSize(10 * 1024 * 1024)
+    }
+}
+
 #[serde(default)]
 #[derive(Deserialize, Copy, Clone, Debug, PartialEq)]
 pub struct Cursor {
@@ -132,6 +216,8 @@ pub struct Cursor {
     thickness: Percentage,
     #[serde(deserialize_with = "failure_default")]
     unfocused_hollow: DefaultTrueBool,
+    #[serde(deserialize_with = "failure_default")]
+    unfocused_thickness: Option<Percentage>,
 }
 
 impl Cursor {
@@ -145,6 +231,12 @@ impl Cursor {
         self.thickness.0 as f64
     }
 
+    /// Outline thickness of the hollow cursor, falling back to the regular cursor thickness.
+    #[inline]
+    pub fn unfocused_thickness(self) -> f64 {
+        self.unfocused_thickness.unwrap_or(self.thickness).0 as f64
+    }
+
     #[inline]
     pub fn style(self) -> CursorStyle {
         self.style.into()
@@ -168,6 +260,7 @@ impl Default for Cursor {
             vi_mode_style: Default::default(),
             thickness: Percentage::new(DEFAULT_CURSOR_THICKNESS),
             unfocused_hollow: Default::default(),
+            unfocused_thickness: Default::default(),
             blink_interval: Default::default(),
         }
     }
@@ -12,6 +12,10 @@ pub struct Scrolling {
     #[serde(deserialize_with = "failure_default")]
     multiplier: ScrollingMultiplier,
 
+    /// Invert the arrow keys sent when scrolling in the alternate screen.
+    #[serde(deserialize_with = "failure_default")]
+    pub invert_alt_screen: bool,
+
     // TODO: REMOVED
     #[serde(deserialize_with = "failure_default")]
     pub auto_scroll: Option<bool>,
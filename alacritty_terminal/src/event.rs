@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
+use crate::ansi::WorkingDirectory;
 use crate::term::{ClipboardType, SizeInfo};
 
 #[derive(Clone)]
@@ -12,6 +13,9 @@ pub enum Event {
     ClipboardStore(ClipboardType, String),
     ClipboardLoad(ClipboardType, Arc<dyn Fn(&str) -> String + Sync + Send + 'static>),
     CursorBlinkingChange(bool),
+    WorkingDirectory(Option<WorkingDirectory>),
+    /// Write a response to the PTY.
+    PtyWrite(String),
     Wakeup,
     Bell,
     Exit,
@@ -29,6 +33,8 @@ impl Debug for Event {
             Event::Bell => write!(f, "Bell"),
             Event::Exit => write!(f, "Exit"),
             Event::CursorBlinkingChange(blinking) => write!(f, "CursorBlinking({})", blinking),
+            Event::WorkingDirectory(directory) => write!(f, "WorkingDirectory({:?})", directory),
+            Event::PtyWrite(text) => write!(f, "PtyWrite({:?})", text),
         }
     }
 }
@@ -46,7 +52,20 @@ pub trait OnResize {
     fn on_resize(&mut self, size: &SizeInfo);
 }
 
+/// Types that can stop and resume reading output from the PTY.
+pub trait OnPause {
+    fn on_pause(&mut self, paused: bool);
+}
+
 /// Event Loop for notifying the renderer about terminal events.
 pub trait EventListener {
     fn send_event(&self, event: Event);
 }
+
+/// Event listener which discards all events.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct VoidListener;
+
+impl EventListener for VoidListener {
+    fn send_event(&self, _event: Event) {}
+}
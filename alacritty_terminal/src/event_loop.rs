@@ -7,6 +7,7 @@ use std::io::{self, ErrorKind, Read, Write};
 use std::marker::Send;
 use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::Instant;
 
 use log::error;
 #[cfg(not(windows))]
@@ -35,6 +36,9 @@ pub enum Msg {
 
     /// Instruction to resize the PTY.
     Resize(SizeInfo),
+
+    /// Stop or resume reading output from the PTY.
+    Pause(bool),
 }
 
 /// The main event!.. loop.
@@ -66,6 +70,8 @@ pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
     parser: ansi::Processor,
+    paused: bool,
+    sync_timeout: Option<Instant>,
 }
 
 pub struct Notifier(pub Sender<Msg>);
@@ -91,9 +97,21 @@ impl event::OnResize for Notifier {
     }
 }
 
+impl event::OnPause for Notifier {
+    fn on_pause(&mut self, paused: bool) {
+        self.0.send(Msg::Pause(paused)).expect("expected send event loop msg");
+    }
+}
+
 impl Default for State {
     fn default() -> State {
-        State { write_list: VecDeque::new(), parser: ansi::Processor::new(), writing: None }
+        State {
+            write_list: VecDeque::new(),
+            parser: ansi::Processor::new(),
+            writing: None,
+            paused: false,
+            sync_timeout: None,
+        }
     }
 }
 
@@ -186,7 +204,18 @@ where
             match msg {
                 Msg::Input(input) => state.write_list.push_back(input),
                 Msg::Shutdown => return false,
-                Msg::Resize(size) => self.pty.on_resize(&size),
+                Msg::Resize(size) => {
+                    self.pty.on_resize(&size);
+
+                    // Wake up once the repaint after the resize is due, even without output.
+                    #[cfg(windows)]
+                    {
+                        let mut terminal = self.terminal.lock();
+                        terminal.start_resize_sync();
+                        state.sync_timeout = terminal.sync_update_timeout();
+                    }
+                },
+                Msg::Pause(paused) => state.paused = paused,
             }
         }
 
@@ -261,8 +290,19 @@ where
             }
         }
 
-        if processed > 0 {
+        if let Some(mut terminal) = terminal {
+            // Delay the redraw until the synchronized update is complete or has timed out.
+            match terminal.sync_update_timeout() {
+                Some(timeout) if timeout > Instant::now() => {
+                    state.sync_timeout = Some(timeout);
+                    return Ok(());
+                },
+                Some(_) => terminal.stop_sync_update(),
+                None => (),
+            }
+
             // Queue terminal redraw.
+            state.sync_timeout = None;
             self.event_proxy.send_event(Event::Wakeup);
         }
 
@@ -325,13 +365,23 @@ where
             };
 
             'event_loop: loop {
-                if let Err(err) = self.poll.poll(&mut events, None) {
+                let timeout = state
+                    .sync_timeout
+                    .map(|timeout| timeout.saturating_duration_since(Instant::now()));
+                if let Err(err) = self.poll.poll(&mut events, timeout) {
                     match err.kind() {
                         ErrorKind::Interrupted => continue,
                         _ => panic!("EventLoop polling error: {:?}", err),
                     }
                 }
 
+                // Present the synchronized update once its timeout has been reached.
+                if matches!(state.sync_timeout, Some(timeout) if timeout <= Instant::now()) {
+                    self.terminal.lock().stop_sync_update();
+                    self.event_proxy.send_event(Event::Wakeup);
+                    state.sync_timeout = None;
+                }
+
                 for event in events.iter() {
                     match event.token() {
                         token if token == channel_token => {
@@ -360,7 +410,7 @@ where
                                 continue;
                             }
 
-                            if event.readiness().is_readable() {
+                            if event.readiness().is_readable() && !state.paused {
                                 if let Err(err) = self.pty_read(&mut state, &mut buf, pipe.as_mut())
                                 {
                                     // On Linux, a `read` on the master side of a PTY can fail
@@ -390,7 +440,13 @@ where
                 }
 
                 // Register write interest if necessary.
-                let mut interest = Ready::readable();
+                //
+                // While output is paused, read interest is dropped so the child blocks once the PTY
+                // buffer is full, regardless of the PTY's own flow control settings. Hangups are
+                // still watched, so the child exiting is not missed.
+                let mut interest = if state.paused { Ready::empty() } else { Ready::readable() };
+                #[cfg(unix)]
+                interest.insert(UnixReady::hup());
                 if state.needs_write() {
                     interest.insert(Ready::writable());
                 }
@@ -15,7 +15,7 @@ mod storage;
 #[cfg(test)]
 mod tests;
 
-pub use self::row::Row;
+pub use self::row::{CompressedRow, Row};
 use self::storage::Storage;
 
 /// Bidirectional iterator.
@@ -189,6 +189,21 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         self.max_scroll_limit = history_size;
     }
 
+    /// Insert rows above the oldest line in the scrollback history.
+    ///
+    /// The rows are ordered from the oldest to the newest and must match the grid's width. Only the
+    /// newest rows are kept if they exceed the remaining history capacity.
+    pub fn prepend_history(&mut self, rows: Vec<Row<T>>) {
+        let count = min(rows.len(), self.max_scroll_limit - self.history_size());
+        let top = self.total_lines();
+        self.raw.initialize(count, self.cols);
+
+        for (index, row) in rows.into_iter().rev().take(count).enumerate() {
+            debug_assert_eq!(row.len(), self.cols.0);
+            self.raw[top + index] = row;
+        }
+    }
+
     pub fn scroll_display(&mut self, scroll: Scroll) {
         self.display_offset = match scroll {
             Scroll::Delta(count) => min(
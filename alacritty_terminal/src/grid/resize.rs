@@ -82,8 +82,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
     {
         // Scroll up to keep content inside the window.
         let required_scrolling = (self.cursor.point.line + 1).saturating_sub(target.0);
-        if required_scrolling > 0 {
-            self.scroll_up(&(Line(0)..self.lines), Line(required_scrolling));
+        if required_scrolling > Line(0) {
+            self.scroll_up(&(Line(0)..self.lines), required_scrolling);
 
             // Clamp cursors to the new viewport size.
             self.cursor.point.line = min(self.cursor.point.line, target - 1);
@@ -111,11 +111,20 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         let mut cursor_line_delta = 0;
 
         // Remove the linewrap special case, by moving the cursor outside of the grid.
-        if self.cursor.input_needs_wrap && reflow {
+        //
+        // Without reflow this moves the cursor into the newly added columns instead, since the
+        // pending wrap would otherwise happen in the middle of the line.
+        if self.cursor.input_needs_wrap {
             self.cursor.input_needs_wrap = false;
             self.cursor.point.col += 1;
         }
 
+        // The saved cursor is never reflown, so its pending wrap moves into the new columns.
+        if self.saved_cursor.input_needs_wrap {
+            self.saved_cursor.input_needs_wrap = false;
+            self.saved_cursor.point.col += 1;
+        }
+
         let mut rows = self.raw.take_all();
 
         for (i, mut row) in rows.drain(..).enumerate().rev() {
@@ -172,8 +181,10 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
 
                 // Clamp to the last column, if no content was reflown with the cursor.
                 if target.col.0 == 0 && row.is_clear() {
-                    self.cursor.input_needs_wrap = true;
                     target = target.sub(cols, 1);
+
+                    // The cursor cannot be moved into the scrollback history.
+                    self.cursor.input_needs_wrap = target.col + 1 == cols;
                 }
                 self.cursor.point.col = target.col;
 
@@ -212,7 +223,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         // Make sure we have at least the viewport filled.
         if reversed.len() < self.lines.0 {
             let delta = self.lines.0 - reversed.len();
-            self.cursor.point.line.0 = self.cursor.point.line.saturating_sub(delta);
+            self.cursor.point.line = self.cursor.point.line.saturating_sub(delta);
             reversed.resize_with(self.lines.0, || Row::new(cols));
         }
 
@@ -223,14 +234,25 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
             let available = min(cursor_buffer_line, reversed.len() - self.lines.0);
             let overflow = cursor_line_delta.saturating_sub(available);
             reversed.truncate(reversed.len() + overflow - cursor_line_delta);
-            self.cursor.point.line.0 = self.cursor.point.line.saturating_sub(overflow);
+            self.cursor.point.line = self.cursor.point.line.saturating_sub(overflow);
         }
 
         // Reverse iterator and fill all rows that are still too short.
         let mut new_raw = Vec::with_capacity(reversed.len());
         for mut row in reversed.drain(..).rev() {
             if row.len() < cols.0 {
+                // Move the wrap flag of rows which could not be reflown to the new last column.
+                let mut wrapped = false;
+                if let Some(cell) = row.last_mut() {
+                    wrapped = cell.flags().contains(Flags::WRAPLINE);
+                    cell.flags_mut().remove(Flags::WRAPLINE);
+                }
+
                 row.grow(cols);
+
+                if wrapped {
+                    row[cols - 1].flags_mut().insert(Flags::WRAPLINE);
+                }
             }
             new_raw.push(row);
         }
@@ -279,6 +301,13 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                             // whitespace and need to wrap it like normal content.
                             Vec::new()
                         } else {
+                            // Remove wide chars which lost their spacer to the truncation.
+                            if row.len() >= cols.0
+                                && row[cols - 1].flags().contains(Flags::WIDE_CHAR)
+                            {
+                                row[cols - 1] = T::default();
+                            }
+
                             // Since it fits, just push the existing line without any reflow.
                             new_raw.push(row);
                             break;
@@ -336,7 +365,7 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                     if (i == cursor_buffer_line && self.cursor.point.col < cols)
                         || i < cursor_buffer_line
                     {
-                        self.cursor.point.line.0 = self.cursor.point.line.saturating_sub(1);
+                        self.cursor.point.line = self.cursor.point.line.saturating_sub(1);
                     }
 
                     // Reflow the cursor if it is on this line beyond the width.
@@ -349,6 +378,11 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
                     // Make sure new row is at least as long as new width.
                     let occ = wrapped.len();
                     if occ < cols.0 {
+                        // Without a row below, the wrap flag can't be moved to the last column.
+                        if let Some(cell) = wrapped.last_mut() {
+                            cell.flags_mut().remove(Flags::WRAPLINE);
+                        }
+
                         wrapped.resize_with(cols.0, T::default);
                     }
                     row = Row::from_vec(wrapped, occ);
@@ -365,7 +399,8 @@ impl<T: GridCell + Default + PartialEq + Clone> Grid<T> {
         if !reflow {
             self.cursor.point.col = min(self.cursor.point.col, cols - 1);
         } else if self.cursor.point.col == cols
-            && !self[self.cursor.point.line][cols - 1].flags().contains(Flags::WRAPLINE)
+            && (self.cursor.point.line + 1 == self.lines
+                || !self[self.cursor.point.line][cols - 1].flags().contains(Flags::WRAPLINE))
         {
             self.cursor.input_needs_wrap = true;
             self.cursor.point.col -= 1;
@@ -114,6 +114,53 @@ impl<T: Clone + Default> Row<T> {
     }
 }
 
+impl<T: Clone + PartialEq> Row<T> {
+    /// Run-length encode the cells of this row.
+    ///
+    /// Rows are mostly made up of long runs of identical blank cells, so this is much cheaper to
+    /// clone and serialize than the row itself.
+    ///
+    /// History rows in the grid's storage are kept uncompressed, since they are borrowed directly
+    /// by everything indexing the grid and rows leaving the history are reused without allocating.
+    pub fn compress(&self) -> CompressedRow<T> {
+        let mut runs: Vec<(T, u16)> = Vec::new();
+        for cell in &self.inner {
+            match runs.last_mut() {
+                Some((last, count)) if last == cell && *count < u16::MAX => *count += 1,
+                _ => runs.push((cell.clone(), 1)),
+            }
+        }
+        CompressedRow { runs }
+    }
+}
+
+/// Run-length encoded row, created by [`Row::compress`].
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressedRow<T> {
+    /// Cells with the number of times they are repeated.
+    runs: Vec<(T, u16)>,
+}
+
+impl<T: Clone + Default + PartialEq> CompressedRow<T> {
+    /// Expand the runs into a row with `width` columns.
+    ///
+    /// Cells beyond `width` are discarded, missing cells are filled with the default cell.
+    pub fn decompress(&self, width: usize) -> Row<T> {
+        let mut inner = Vec::with_capacity(width);
+        for (cell, count) in &self.runs {
+            let len = min(inner.len() + *count as usize, width);
+            inner.resize(len, cell.clone());
+        }
+        inner.resize_with(width, T::default);
+
+        // Trailing default cells are not occupied.
+        let template = T::default();
+        let occ = inner.iter().rposition(|cell| *cell != template).map_or(0, |index| index + 1);
+
+        Row { inner, occ }
+    }
+}
+
 #[allow(clippy::len_without_is_empty)]
 impl<T> Row<T> {
     #[inline]
@@ -285,3 +332,66 @@ impl<T> IndexMut<RangeToInclusive<Column>> for Row<T> {
         &mut self.inner[..=(index.end.0)]
     }
 }
+
+#[cfg(all(test, feature = "bench"))]
+mod benches {
+    extern crate test;
+
+    use super::*;
+
+    use crate::term::cell::Cell;
+
+    const COLUMNS: usize = 220;
+
+    fn text_row() -> Row<Cell> {
+        let mut row = Row::<Cell>::new(Column(COLUMNS));
+        let text = "fn main() { println!(\"x\"); }".chars().cycle();
+        for (cell, c) in row.inner.iter_mut().zip(text) {
+            cell.c = c;
+        }
+        row
+    }
+
+    #[bench]
+    fn clone_blank_row(b: &mut test::Bencher) {
+        let row = Row::<Cell>::new(Column(COLUMNS));
+        b.iter(|| test::black_box(row.clone()));
+    }
+
+    #[bench]
+    fn clone_blank_row_compressed(b: &mut test::Bencher) {
+        let row = Row::<Cell>::new(Column(COLUMNS)).compress();
+        b.iter(|| test::black_box(row.clone()));
+    }
+
+    #[bench]
+    fn clone_text_row(b: &mut test::Bencher) {
+        let row = text_row();
+        b.iter(|| test::black_box(row.clone()));
+    }
+
+    #[bench]
+    fn clone_text_row_compressed(b: &mut test::Bencher) {
+        let row = text_row().compress();
+        b.iter(|| test::black_box(row.clone()));
+    }
+
+    #[bench]
+    fn serialize_blank_row(b: &mut test::Bencher) {
+        let row = Row::<Cell>::new(Column(COLUMNS));
+        b.iter(|| {
+            let bytes = bincode::serialize(&row).unwrap();
+            test::black_box(bincode::deserialize::<Row<Cell>>(&bytes).unwrap());
+        });
+    }
+
+    #[bench]
+    fn serialize_blank_row_compressed(b: &mut test::Bencher) {
+        let row = Row::<Cell>::new(Column(COLUMNS));
+        b.iter(|| {
+            let bytes = bincode::serialize(&row.compress()).unwrap();
+            let compressed = bincode::deserialize::<CompressedRow<Cell>>(&bytes).unwrap();
+            test::black_box(compressed.decompress(COLUMNS));
+        });
+    }
+}
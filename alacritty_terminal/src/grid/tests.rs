@@ -56,6 +56,42 @@ fn visible_to_buffer() {
     assert_eq!(point, Point::new(4, Column(3)));
 }
 
+#[test]
+fn prepend_history() {
+    let mut grid = Grid::<usize>::new(Line(2), Column(2), 3);
+    grid.scroll_up(&(Line(0)..Line(2)), Line(1));
+    grid[1][Column(0)] = 1;
+
+    let rows = (2..5).map(|value| Row::from_vec(vec![value; 2], 2)).collect();
+    grid.prepend_history(rows);
+
+    // Only the newest rows fit into the remaining history.
+    assert_eq!(grid.history_size(), 3);
+    assert_eq!(grid[1][Column(0)], 1);
+    assert_eq!(grid[3][Column(0)], 4);
+    assert_eq!(grid[4][Column(0)], 3);
+}
+
+#[test]
+fn compress_row() {
+    let row = Row::from_vec(vec![1, 1, 2, 0, 0, 0], 3);
+    let compressed = row.compress();
+    assert_eq!(compressed.decompress(6), row);
+    assert_eq!(compressed.decompress(6).occ, 3);
+
+    // Rows are truncated or padded to the requested width.
+    assert_eq!(compressed.decompress(2), Row::from_vec(vec![1, 1], 2));
+    assert_eq!(compressed.decompress(8), Row::from_vec(vec![1, 1, 2, 0, 0, 0, 0, 0], 3));
+}
+
+#[test]
+fn compress_long_run() {
+    let width = u16::MAX as usize + 10;
+    let row = Row::<usize>::new(Column(width));
+    let compressed = row.compress();
+    assert_eq!(compressed.decompress(width), row);
+}
+
 // Scroll up moves lines upward.
 #[test]
 fn scroll_up() {
@@ -307,6 +343,20 @@ fn grow_reflow_multiline() {
     }
 }
 
+#[test]
+fn grow_reflow_cursor_at_top() {
+    let mut grid = Grid::<Cell>::new(Line(1), Column(2), 1);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = wrap_cell('2');
+    grid.scroll_up(&(Line(0)..Line(1)), Line(1));
+
+    grid.resize(true, Line(1), Column(3));
+
+    // The cursor can't follow the wrapped line into the history.
+    assert_eq!(grid.cursor.point, Point::new(Line(0), Column(0)));
+    assert!(!grid.cursor.input_needs_wrap);
+}
+
 #[test]
 fn grow_reflow_disabled() {
     let mut grid = Grid::<Cell>::new(Line(2), Column(2), 0);
@@ -321,8 +371,8 @@ fn grow_reflow_disabled() {
 
     assert_eq!(grid[1].len(), 3);
     assert_eq!(grid[1][Column(0)], cell('1'));
-    assert_eq!(grid[1][Column(1)], wrap_cell('2'));
-    assert_eq!(grid[1][Column(2)], Cell::default());
+    assert_eq!(grid[1][Column(1)], cell('2'));
+    assert_eq!(grid[1][Column(2)], wrap_cell(' '));
 
     assert_eq!(grid[0].len(), 3);
     assert_eq!(grid[0][Column(0)], cell('3'));
@@ -348,6 +398,51 @@ fn shrink_reflow_disabled() {
     assert_eq!(grid[0][Column(1)], cell('2'));
 }
 
+#[test]
+fn shrink_reflow_wrapped_last_line() {
+    let mut grid = Grid::<Cell>::new(Line(1), Column(4), 1);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = cell('2');
+    grid[Line(0)][Column(2)] = cell('3');
+    grid[Line(0)][Column(3)] = wrap_cell('4');
+
+    grid.resize(true, Line(1), Column(3));
+
+    // The reflown tail has no line below it to wrap into.
+    assert_eq!(grid[1][Column(2)], wrap_cell('3'));
+    assert_eq!(grid[0][Column(0)], cell('4'));
+
+    let mut grid = Grid::<Cell>::new(Line(1), Column(6), 1);
+    for (i, c) in "12345".chars().enumerate() {
+        grid[Line(0)][Column(i)] = cell(c);
+    }
+    grid[Line(0)][Column(5)] = wrap_cell('6');
+    grid.cursor.point = Point::new(Line(0), Column(5));
+    grid.cursor.input_needs_wrap = true;
+
+    grid.resize(true, Line(1), Column(3));
+
+    // The cursor can't move below the last line.
+    assert_eq!(grid.cursor.point, Point::new(Line(0), Column(2)));
+    assert!(grid.cursor.input_needs_wrap);
+}
+
+#[test]
+fn shrink_reflow_disabled_wide_char() {
+    let mut grid = Grid::<Cell>::new(Line(1), Column(3), 0);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = cell('字');
+    grid[Line(0)][Column(1)].flags.insert(Flags::WIDE_CHAR);
+    grid[Line(0)][Column(2)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+    grid.resize(false, Line(1), Column(2));
+
+    // The wide char can't be kept without its spacer.
+    assert_eq!(grid[0].len(), 2);
+    assert_eq!(grid[0][Column(0)], cell('1'));
+    assert_eq!(grid[0][Column(1)], Cell::default());
+}
+
 fn cell(c: char) -> Cell {
     let mut cell = Cell::default();
     cell.c = c;
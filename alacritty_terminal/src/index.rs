@@ -86,6 +86,23 @@ impl<L> Point<L> {
     }
 }
 
+impl Point {
+    /// Move the point forward by `rhs` cells, stopping at the last cell of the viewport.
+    #[inline]
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn clamped_add<D: Dimensions>(self, dimensions: &D, rhs: usize) -> Point {
+        let cols = dimensions.cols();
+        let last_line = dimensions.screen_lines() - 1;
+
+        let line_delta = self.col.0.saturating_add(rhs) / cols.0;
+        if self.line.0.saturating_add(line_delta) > last_line.0 {
+            Point::new(last_line, cols - 1)
+        } else {
+            self.add(cols, rhs)
+        }
+    }
+}
+
 impl Point<usize> {
     #[inline]
     #[must_use = "this returns the result of the operation, without modifying the original"]
@@ -290,6 +307,17 @@ macro_rules! add {
     };
 }
 
+/// Subtract two indices, panicking on underflow even when overflow checks are disabled.
+///
+/// An underflowed index would otherwise silently wrap around and point far outside of the grid.
+#[inline]
+fn checked_index_sub(lhs: usize, rhs: usize) -> usize {
+    match lhs.checked_sub(rhs) {
+        Some(result) => result,
+        None => panic!("index underflow: {} - {}", lhs, rhs),
+    }
+}
+
 macro_rules! sub {
     ($ty:ty, $construct:expr) => {
         impl ops::Sub<$ty> for $ty {
@@ -297,7 +325,7 @@ macro_rules! sub {
 
             #[inline]
             fn sub(self, rhs: $ty) -> $ty {
-                $construct(self.0 - rhs.0)
+                $construct(checked_index_sub(self.0, rhs.0))
             }
         }
 
@@ -306,7 +334,7 @@ macro_rules! sub {
 
             #[inline]
             fn sub(self, rhs: $ty) -> $ty {
-                $construct(self.0 - rhs.0)
+                $construct(checked_index_sub(self.0, rhs.0))
             }
         }
 
@@ -315,7 +343,7 @@ macro_rules! sub {
 
             #[inline]
             fn sub(self, rhs: &'a $ty) -> $ty {
-                $construct(self.0 - rhs.0)
+                $construct(checked_index_sub(self.0, rhs.0))
             }
         }
 
@@ -324,7 +352,7 @@ macro_rules! sub {
 
             #[inline]
             fn sub(self, rhs: &'a $ty) -> $ty {
-                $construct(self.0 - rhs.0)
+                $construct(checked_index_sub(self.0, rhs.0))
             }
         }
     };
@@ -350,6 +378,26 @@ macro_rules! ops {
         forward_ref_binop!(impl Add, add for $ty, $ty);
 
         impl $ty {
+            /// Smallest possible index.
+            pub const MIN: $ty = $construct(0);
+
+            /// Largest possible index.
+            pub const MAX: $ty = $construct(usize::MAX);
+
+            /// Subtract `rhs`, stopping at zero instead of underflowing.
+            #[inline]
+            #[must_use = "this returns the result of the operation, without modifying the original"]
+            pub fn saturating_sub(self, rhs: usize) -> $ty {
+                $construct(self.0.saturating_sub(rhs))
+            }
+
+            /// Subtract `rhs`, returning `None` if the result would be negative.
+            #[inline]
+            #[must_use = "this returns the result of the operation, without modifying the original"]
+            pub fn checked_sub(self, rhs: usize) -> Option<$ty> {
+                self.0.checked_sub(rhs).map($construct)
+            }
+
             #[inline]
             fn steps_between(start: $ty, end: $ty, by: $ty) -> Option<usize> {
                 if by == $construct(0) { return None; }
@@ -416,7 +464,7 @@ macro_rules! ops {
         impl SubAssign<$ty> for $ty {
             #[inline]
             fn sub_assign(&mut self, rhs: $ty) {
-                self.0 -= rhs.0
+                self.0 = checked_index_sub(self.0, rhs.0)
             }
         }
 
@@ -430,7 +478,7 @@ macro_rules! ops {
         impl SubAssign<usize> for $ty {
             #[inline]
             fn sub_assign(&mut self, rhs: usize) {
-                self.0 -= rhs
+                self.0 = checked_index_sub(self.0, rhs)
             }
         }
 
@@ -455,7 +503,7 @@ macro_rules! ops {
 
             #[inline]
             fn sub(self, rhs: usize) -> $ty {
-                $construct(self.0 - rhs)
+                $construct(checked_index_sub(self.0, rhs))
             }
         }
     }
@@ -626,4 +674,30 @@ mod tests {
 
         assert_eq!(result, Point::new(1, Column(9)));
     }
+
+    #[test]
+    fn saturating_and_checked_sub() {
+        assert_eq!(Line(3).saturating_sub(5), Line(0));
+        assert_eq!(Column(3).saturating_sub(1), Column(2));
+        assert_eq!(Line(3).checked_sub(4), None);
+        assert_eq!(Column(3).checked_sub(3), Some(Column::MIN));
+    }
+
+    #[test]
+    #[should_panic(expected = "index underflow")]
+    fn sub_underflow_panics() {
+        let _ = Column(0) - 1;
+    }
+
+    #[test]
+    fn clamped_add() {
+        let dimensions = (Line(3), Column(10));
+
+        let point = Point::new(Line(1), Column(5));
+        assert_eq!(point.clamped_add(&dimensions, 7), Point::new(Line(2), Column(2)));
+
+        let point = Point::new(Line(2), Column(9));
+        assert_eq!(point.clamped_add(&dimensions, 1), Point::new(Line(2), Column(9)));
+        assert_eq!(point.clamped_add(&dimensions, usize::MAX), Point::new(Line(2), Column(9)));
+    }
 }
@@ -9,6 +9,7 @@ use std::convert::TryFrom;
 use std::mem;
 use std::ops::{Bound, Range, RangeBounds};
 
+use crate::config::LineSelection;
 use crate::grid::Dimensions;
 use crate::index::{Column, Line, Point, Side};
 use crate::term::Term;
@@ -176,12 +177,19 @@ impl Selection {
                         && start.point.col + 1 == end.point.col)
             },
             SelectionType::Block => {
-                let (start, end) = (self.region.start, self.region.end);
+                let (mut start, mut end) = (self.region.start, self.region.end);
+                if Self::points_need_swap(start.point, end.point) {
+                    mem::swap(&mut start, &mut end);
+                }
 
-                // Block selection is empty when the points' columns and sides are identical
-                // or two cells with adjacent columns have the sides right -> left,
-                // regardless of their lines
+                // Block selection is empty when the points' columns and sides are identical,
+                // when the points share a column with the sides right -> left, or when two
+                // cells with adjacent columns have the sides right -> left, regardless of their
+                // lines.
                 (start.point.col == end.point.col && start.side == end.side)
+                    || (start.point.col == end.point.col
+                        && start.side == Side::Right
+                        && end.side == Side::Left)
                     || (start.point.col + 1 == end.point.col
                         && start.side == Side::Right
                         && end.side == Side::Left)
@@ -320,8 +328,13 @@ impl Selection {
         mut start: Point<usize>,
         mut end: Point<usize>,
     ) -> Option<SelectionRange> {
-        start = term.line_search_left(start);
-        end = term.line_search_right(end);
+        if term.line_selection() == LineSelection::Logical {
+            start = term.line_search_left(start);
+            end = term.line_search_right(end);
+        }
+
+        start.col = Column(0);
+        end.col = term.cols() - 1;
 
         Some(SelectionRange { start, end, is_block: false })
     }
@@ -398,9 +411,12 @@ impl Selection {
 mod tests {
     use super::*;
 
+    use crate::ansi::Color;
     use crate::config::MockConfig;
     use crate::event::{Event, EventListener};
+    use crate::grid::Grid;
     use crate::index::{Column, Line, Point, Side};
+    use crate::term::cell::{Cell, Flags};
     use crate::term::{SizeInfo, Term};
 
     struct Mock;
@@ -424,11 +440,10 @@ mod tests {
         let mut selection = Selection::new(SelectionType::Simple, location, Side::Left);
         selection.update(location, Side::Right);
 
-        assert_eq!(selection.to_range(&term(1, 2)).unwrap(), SelectionRange {
-            start: location,
-            end: location,
-            is_block: false
-        });
+        assert_eq!(
+            selection.to_range(&term(1, 2)).unwrap(),
+            SelectionRange { start: location, end: location, is_block: false }
+        );
     }
 
     /// Test case of single cell selection.
@@ -442,11 +457,10 @@ mod tests {
         let mut selection = Selection::new(SelectionType::Simple, location, Side::Right);
         selection.update(location, Side::Left);
 
-        assert_eq!(selection.to_range(&term(1, 2)).unwrap(), SelectionRange {
-            start: location,
-            end: location,
-            is_block: false
-        });
+        assert_eq!(
+            selection.to_range(&term(1, 2)).unwrap(),
+            SelectionRange { start: location, end: location, is_block: false }
+        );
     }
 
     /// Test adjacent cell selection from left to right.
@@ -491,11 +505,14 @@ mod tests {
             Selection::new(SelectionType::Simple, Point::new(1, Column(1)), Side::Right);
         selection.update(Point::new(0, Column(1)), Side::Right);
 
-        assert_eq!(selection.to_range(&term(2, 5)).unwrap(), SelectionRange {
-            start: Point::new(1, Column(2)),
-            end: Point::new(0, Column(1)),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(2, 5)).unwrap(),
+            SelectionRange {
+                start: Point::new(1, Column(2)),
+                end: Point::new(0, Column(1)),
+                is_block: false,
+            }
+        );
     }
 
     /// Test selection across adjacent lines.
@@ -515,11 +532,14 @@ mod tests {
         selection.update(Point::new(1, Column(1)), Side::Right);
         selection.update(Point::new(1, Column(0)), Side::Right);
 
-        assert_eq!(selection.to_range(&term(2, 5)).unwrap(), SelectionRange {
-            start: Point::new(1, Column(1)),
-            end: Point::new(0, Column(1)),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(2, 5)).unwrap(),
+            SelectionRange {
+                start: Point::new(1, Column(1)),
+                end: Point::new(0, Column(1)),
+                is_block: false,
+            }
+        );
     }
 
     #[test]
@@ -530,11 +550,81 @@ mod tests {
         selection.update(Point::new(5, Column(1)), Side::Right);
         selection = selection.rotate(&size, &(Line(0)..size.0), 7).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(9, Column(0)),
-            end: Point::new(7, Column(4)),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(9, Column(0)),
+                end: Point::new(7, Column(4)),
+                is_block: false,
+            }
+        );
+    }
+
+    /// Term with a single line wrapping over rows 4 to 1, starting in the scrollback history.
+    fn wrapped_term(line_selection: LineSelection) -> Term<Mock> {
+        let mut config = MockConfig::default();
+        config.selection.line_selection = line_selection;
+        let size = SizeInfo::new(5., 3., 1.0, 1.0, 0.0, 0.0, false);
+        let mut term = Term::new(&config, size, Mock);
+
+        // Push two rows into the scrollback history.
+        let mut grid: Grid<Cell> = Grid::new(Line(3), Column(5), 2);
+        grid.scroll_up::<Color>(&(Line(0)..Line(3)), Line(2));
+        for line in 2..=4 {
+            grid[line][Column(4)].flags.insert(Flags::WRAPLINE);
+        }
+        mem::swap(term.grid_mut(), &mut grid);
+
+        term
+    }
+
+    #[test]
+    fn logical_line_selection() {
+        let term = wrapped_term(LineSelection::Logical);
+
+        // Clicking any row selects the entire logical line.
+        for line in 1..=4 {
+            let selection =
+                Selection::new(SelectionType::Lines, Point::new(line, Column(2)), Side::Left);
+            assert_eq!(
+                selection.to_range(&term).unwrap(),
+                SelectionRange {
+                    start: Point::new(4, Column(0)),
+                    end: Point::new(1, Column(4)),
+                    is_block: false,
+                }
+            );
+        }
+
+        // Dragging extends by entire logical lines.
+        let mut selection =
+            Selection::new(SelectionType::Lines, Point::new(0, Column(2)), Side::Left);
+        selection.update(Point::new(2, Column(3)), Side::Right);
+        assert_eq!(
+            selection.to_range(&term).unwrap(),
+            SelectionRange {
+                start: Point::new(4, Column(0)),
+                end: Point::new(0, Column(4)),
+                is_block: false,
+            }
+        );
+    }
+
+    #[test]
+    fn visual_line_selection() {
+        let term = wrapped_term(LineSelection::Visual);
+
+        let mut selection =
+            Selection::new(SelectionType::Lines, Point::new(3, Column(2)), Side::Left);
+        selection.update(Point::new(2, Column(0)), Side::Right);
+        assert_eq!(
+            selection.to_range(&term).unwrap(),
+            SelectionRange {
+                start: Point::new(3, Column(0)),
+                end: Point::new(2, Column(4)),
+                is_block: false,
+            }
+        );
     }
 
     #[test]
@@ -545,11 +635,14 @@ mod tests {
         selection.update(Point::new(5, Column(1)), Side::Right);
         selection = selection.rotate(&size, &(Line(0)..size.0), 7).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(9, Column(0)),
-            end: Point::new(7, Column(3)),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(9, Column(0)),
+                end: Point::new(7, Column(3)),
+                is_block: false,
+            }
+        );
     }
 
     #[test]
@@ -560,11 +653,14 @@ mod tests {
         selection.update(Point::new(5, Column(1)), Side::Right);
         selection = selection.rotate(&size, &(Line(0)..size.0), 7).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(9, Column(0)),
-            end: Point::new(7, Column(3)),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(9, Column(0)),
+                end: Point::new(7, Column(3)),
+                is_block: false,
+            }
+        );
     }
 
     #[test]
@@ -575,11 +671,14 @@ mod tests {
         selection.update(Point::new(5, Column(1)), Side::Right);
         selection = selection.rotate(&size, &(Line(0)..size.0), 7).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(9, Column(2)),
-            end: Point::new(7, Column(3)),
-            is_block: true
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(9, Column(2)),
+                end: Point::new(7, Column(3)),
+                is_block: true
+            }
+        );
     }
 
     #[test]
@@ -618,11 +717,14 @@ mod tests {
         selection.update(Point::new(5, Column(1)), Side::Right);
         selection = selection.rotate(&size, &(Line(1)..(size.0 - 1)), 4).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(8, Column(0)),
-            end: Point::new(6, Column(3)),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(8, Column(0)),
+                end: Point::new(6, Column(3)),
+                is_block: false,
+            }
+        );
     }
 
     #[test]
@@ -633,11 +735,14 @@ mod tests {
         selection.update(Point::new(8, Column(1)), Side::Left);
         selection = selection.rotate(&size, &(Line(1)..(size.0 - 1)), -5).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(3, Column(1)),
-            end: Point::new(1, size.1 - 1),
-            is_block: false,
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(3, Column(1)),
+                end: Point::new(1, size.1 - 1),
+                is_block: false,
+            }
+        );
     }
 
     #[test]
@@ -648,11 +753,14 @@ mod tests {
         selection.update(Point::new(5, Column(1)), Side::Right);
         selection = selection.rotate(&size, &(Line(1)..(size.0 - 1)), 4).unwrap();
 
-        assert_eq!(selection.to_range(&term(*size.0, *size.1)).unwrap(), SelectionRange {
-            start: Point::new(8, Column(2)),
-            end: Point::new(6, Column(3)),
-            is_block: true,
-        });
+        assert_eq!(
+            selection.to_range(&term(*size.0, *size.1)).unwrap(),
+            SelectionRange {
+                start: Point::new(8, Column(2)),
+                end: Point::new(6, Column(3)),
+                is_block: true,
+            }
+        );
     }
 
     #[test]
@@ -671,4 +779,45 @@ mod tests {
         assert!(!selection.intersects_range(..=2));
         assert!(!selection.intersects_range(7..=8));
     }
+
+    #[test]
+    fn selections_at_grid_corners() {
+        let term = term(3, 3);
+        let corners = [
+            Point::new(0, Column(0)),
+            Point::new(0, Column(2)),
+            Point::new(2, Column(0)),
+            Point::new(2, Column(2)),
+        ];
+        let types = [
+            SelectionType::Simple,
+            SelectionType::Block,
+            SelectionType::Semantic,
+            SelectionType::Lines,
+        ];
+        let sides = [
+            (Side::Left, Side::Left),
+            (Side::Left, Side::Right),
+            (Side::Right, Side::Left),
+            (Side::Right, Side::Right),
+        ];
+
+        for &ty in &types {
+            for (&start, &end) in corners.iter().flat_map(|a| corners.iter().map(move |b| (a, b))) {
+                for &(start_side, end_side) in &sides {
+                    let mut selection = Selection::new(ty, start, start_side);
+                    selection.update(end, end_side);
+
+                    if let Some(range) = selection.to_range(&term) {
+                        assert!(range.start.line < 3 && range.end.line < 3);
+                        assert!(range.start.col < Column(3) && range.end.col < Column(3));
+                    }
+
+                    for delta in -4..=4 {
+                        let _ = selection.clone().rotate(&term, &(Line(0)..Line(3)), delta);
+                    }
+                }
+            }
+        }
+    }
 }
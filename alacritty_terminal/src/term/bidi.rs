@@ -0,0 +1,286 @@
+//! Visual reordering of bidirectional text.
+//!
+//! The grid always stores text in logical order, so this only computes the order in which the
+//! columns of a row should be displayed, following the Unicode Bidirectional Algorithm (UAX #9).
+//! Neither shaping nor mirroring of paired brackets is performed.
+
+use std::cmp::min;
+use std::ops::Range;
+
+use unicode_bidi::{BidiInfo, Level};
+
+use crate::grid::{Dimensions, Grid, Row};
+use crate::index::Column;
+use crate::term::cell::{Cell, Flags};
+
+/// Maximum number of wrapped rows outside of the viewport used to resolve paragraph direction.
+const MAX_WRAPPED_LINES: usize = 100;
+
+/// Mapping between the logical and visual columns of a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiMap {
+    /// Logical column displayed at every visual column.
+    logical: Vec<Column>,
+
+    /// Visual column of every logical column.
+    visual: Vec<Column>,
+}
+
+impl BidiMap {
+    /// Visual column at which a logical column is displayed.
+    #[inline]
+    pub fn to_visual(&self, col: Column) -> Column {
+        self.visual.get(col.0).copied().unwrap_or(col)
+    }
+
+    /// Logical column which is displayed at a visual column.
+    #[inline]
+    pub fn to_logical(&self, col: Column) -> Column {
+        self.logical.get(col.0).copied().unwrap_or(col)
+    }
+}
+
+/// Cells which are reordered as a single unit.
+#[derive(Debug)]
+struct Unit {
+    /// First logical column of the unit.
+    col: usize,
+
+    /// Number of columns, wide chars are kept together with their spacer.
+    width: usize,
+
+    /// Byte offset of the unit's character in the logical line's text.
+    byte: usize,
+}
+
+/// Compute the visual order of the rows which form a single logical line.
+///
+/// The paragraph direction is detected from the entire logical line, but every row is reordered
+/// on its own. Rows which are displayed in logical order do not have a mapping.
+pub fn line_bidi_maps(rows: &[&Row<Cell>]) -> Vec<Option<BidiMap>> {
+    let mut text = String::new();
+    let mut lines: Vec<(Range<usize>, Vec<Unit>)> = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let start = text.len();
+        let mut units = Vec::with_capacity(row.len());
+        for (col, cell) in row[..].iter().enumerate() {
+            if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            let width = if cell.flags.contains(Flags::WIDE_CHAR) { 2 } else { 1 };
+            units.push(Unit { col, width: min(width, row.len() - col), byte: text.len() });
+
+            // Control characters could be treated as paragraph separators.
+            text.push(if cell.c < ' ' { ' ' } else { cell.c });
+        }
+        lines.push((start..text.len(), units));
+    }
+
+    let info = BidiInfo::new(&text, None);
+    let para = match info.paragraphs.first() {
+        Some(para) if info.has_rtl() => para,
+        _ => return vec![None; rows.len()],
+    };
+
+    lines
+        .into_iter()
+        .zip(rows)
+        .map(|((range, units), row)| {
+            let byte_levels = info.reordered_levels(para, range);
+            let levels = units.iter().map(|unit| byte_levels[unit.byte]).collect::<Vec<_>>();
+            reorder(&units, &levels, row.len())
+        })
+        .collect()
+}
+
+/// Compute the visual order of all rows in the viewport, from top to bottom.
+///
+/// Logical lines which are only partially visible take up to [`MAX_WRAPPED_LINES`] rows outside
+/// of the viewport into account.
+pub fn visible_bidi_maps(grid: &Grid<Cell>) -> Vec<Option<BidiMap>> {
+    let screen_lines = grid.screen_lines().0;
+    let bottom = grid.display_offset();
+    let lowest = bottom.saturating_sub(MAX_WRAPPED_LINES);
+
+    // Buffer indices grow upwards, so the topmost visible row has the highest index.
+    let top = bottom + screen_lines - 1;
+    let mut start = top;
+    while start + 1 < grid.total_lines()
+        && start - top < MAX_WRAPPED_LINES
+        && is_wrapped(&grid[start + 1])
+    {
+        start += 1;
+    }
+
+    let mut maps = Vec::with_capacity(screen_lines);
+    let mut line = top;
+    loop {
+        let mut end = line;
+        while end > lowest && is_wrapped(&grid[end]) {
+            end -= 1;
+        }
+
+        let rows = (end..=start).rev().map(|index| &grid[index]).collect::<Vec<_>>();
+        maps.extend(line_bidi_maps(&rows).into_iter().skip(start - line));
+
+        if end <= bottom || maps.len() >= screen_lines {
+            break;
+        }
+
+        line = end - 1;
+        start = line;
+    }
+
+    maps.truncate(screen_lines);
+    maps
+}
+
+/// Check if a row continues in the row below it.
+fn is_wrapped(row: &Row<Cell>) -> bool {
+    row[Column(row.len() - 1)].flags.contains(Flags::WRAPLINE)
+}
+
+/// Reorder the units of a row based on their resolved embedding levels.
+///
+/// This applies rule L2 of UAX #9, reversing every sequence of units at or above each odd level,
+/// starting with the highest level.
+fn reorder(units: &[Unit], levels: &[Level], columns: usize) -> Option<BidiMap> {
+    let max_level = levels.iter().map(Level::number).max()?;
+    let min_odd_level = levels.iter().map(Level::number).min()? | 1;
+
+    let mut order = (0..units.len()).collect::<Vec<_>>();
+    for level in (min_odd_level..=max_level).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]].number() < level {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < order.len() && levels[order[i]].number() >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+
+    if order.iter().enumerate().all(|(i, &unit)| i == unit) {
+        return None;
+    }
+
+    let mut logical = (0..columns).map(Column).collect::<Vec<_>>();
+    let mut visual = logical.clone();
+    let mut col = 0;
+    for unit in order.iter().map(|&i| &units[i]) {
+        for offset in 0..min(unit.width, columns - col) {
+            logical[col + offset] = Column(unit.col + offset);
+            visual[unit.col + offset] = Column(col + offset);
+        }
+        col += unit.width;
+    }
+
+    Some(BidiMap { logical, visual })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::index::Line;
+
+    fn row(text: &str, columns: usize) -> Row<Cell> {
+        let mut row = Row::<Cell>::new(Column(columns));
+        let mut col = 0;
+        for c in text.chars() {
+            row[Column(col)].c = c;
+            if c == '字' {
+                row[Column(col)].flags.insert(Flags::WIDE_CHAR);
+                row[Column(col + 1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+                col += 1;
+            }
+            col += 1;
+        }
+        row
+    }
+
+    fn logical_columns(map: &BidiMap) -> Vec<usize> {
+        map.logical.iter().map(|col| col.0).collect()
+    }
+
+    #[test]
+    fn ltr_row_has_no_mapping() {
+        let row = row("hello", 8);
+        assert_eq!(line_bidi_maps(&[&row]), vec![None]);
+    }
+
+    #[test]
+    fn rtl_run_in_ltr_paragraph() {
+        let row = row("abc אבג", 8);
+        let map = line_bidi_maps(&[&row]).remove(0).unwrap();
+
+        assert_eq!(logical_columns(&map), vec![0, 1, 2, 3, 6, 5, 4, 7]);
+        assert_eq!(map.to_visual(Column(4)), Column(6));
+        assert_eq!(map.to_logical(Column(6)), Column(4));
+    }
+
+    #[test]
+    fn ltr_run_in_rtl_paragraph() {
+        let row = row("אבג abc", 8);
+        let map = line_bidi_maps(&[&row]).remove(0).unwrap();
+
+        assert_eq!(logical_columns(&map), vec![7, 4, 5, 6, 3, 2, 1, 0]);
+        assert_eq!(map.to_visual(Column(0)), Column(7));
+    }
+
+    #[test]
+    fn wide_chars_keep_spacer() {
+        let row = row("א字", 4);
+        let map = line_bidi_maps(&[&row]).remove(0).unwrap();
+
+        assert_eq!(logical_columns(&map), vec![3, 1, 2, 0]);
+        assert_eq!(map.to_visual(Column(1)), Column(1));
+        assert_eq!(map.to_visual(Column(2)), Column(2));
+    }
+
+    #[test]
+    fn mapping_is_inverse() {
+        let row = row("x אב 12 cd ג", 12);
+        let map = line_bidi_maps(&[&row]).remove(0).unwrap();
+
+        for col in 0..12 {
+            assert_eq!(map.to_logical(map.to_visual(Column(col))), Column(col));
+        }
+    }
+
+    #[test]
+    fn paragraph_direction_of_wrapped_line() {
+        let mut first = row("אבגד", 4);
+        first[Column(3)].flags.insert(Flags::WRAPLINE);
+        let second = row("abc", 4);
+
+        // The second row alone would be displayed in logical order.
+        assert_eq!(line_bidi_maps(&[&second]), vec![None]);
+
+        let maps = line_bidi_maps(&[&first, &second]);
+        assert_eq!(logical_columns(maps[0].as_ref().unwrap()), vec![3, 2, 1, 0]);
+        assert_eq!(logical_columns(maps[1].as_ref().unwrap()), vec![3, 0, 1, 2]);
+    }
+
+    #[test]
+    fn visible_maps_follow_wrapped_lines() {
+        let mut grid: Grid<Cell> = Grid::new(Line(3), Column(4), 0);
+        grid[Line(0)] = row("ab", 4);
+        grid[Line(1)] = row("אבגד", 4);
+        grid[Line(1)][Column(3)].flags.insert(Flags::WRAPLINE);
+        grid[Line(2)] = row("abc", 4);
+
+        let maps = visible_bidi_maps(&grid);
+        assert_eq!(maps.len(), 3);
+        assert_eq!(maps[0], None);
+        assert_eq!(logical_columns(maps[1].as_ref().unwrap()), vec![3, 2, 1, 0]);
+        assert_eq!(logical_columns(maps[2].as_ref().unwrap()), vec![3, 0, 1, 2]);
+    }
+}
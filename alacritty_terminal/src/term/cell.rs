@@ -1,32 +1,66 @@
 use std::boxed::Box;
+use std::cmp::{max, min};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Weak};
+use std::{iter, mem};
 
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use unicode_width::UnicodeWidthChar;
 
-use crate::ansi::{Color, NamedColor};
+use crate::ansi::{self, Attr, Color, NamedColor};
 use crate::grid::{self, GridCell};
 use crate::index::Column;
+use crate::term::color::{ColorResolver, Rgb};
+use crate::term::image::GraphicCell;
+use crate::term::semantic::SemanticZone;
 
 bitflags! {
     #[derive(Serialize, Deserialize)]
-    pub struct Flags: u16 {
-        const INVERSE                   = 0b0000_0000_0000_0001;
-        const BOLD                      = 0b0000_0000_0000_0010;
-        const ITALIC                    = 0b0000_0000_0000_0100;
-        const BOLD_ITALIC               = 0b0000_0000_0000_0110;
-        const UNDERLINE                 = 0b0000_0000_0000_1000;
-        const WRAPLINE                  = 0b0000_0000_0001_0000;
-        const WIDE_CHAR                 = 0b0000_0000_0010_0000;
-        const WIDE_CHAR_SPACER          = 0b0000_0000_0100_0000;
-        const DIM                       = 0b0000_0000_1000_0000;
-        const DIM_BOLD                  = 0b0000_0000_1000_0010;
-        const HIDDEN                    = 0b0000_0001_0000_0000;
-        const STRIKEOUT                 = 0b0000_0010_0000_0000;
-        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0100_0000_0000;
-        const DOUBLE_UNDERLINE          = 0b0000_1000_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE                   = 0b0000_0000_0000_0000_0000_0000_0000_0001;
+        const BOLD                      = 0b0000_0000_0000_0000_0000_0000_0000_0010;
+        const ITALIC                    = 0b0000_0000_0000_0000_0000_0000_0000_0100;
+        const BOLD_ITALIC               = 0b0000_0000_0000_0000_0000_0000_0000_0110;
+        const UNDERLINE                 = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+        const WRAPLINE                  = 0b0000_0000_0000_0000_0000_0000_0001_0000;
+        const WIDE_CHAR                 = 0b0000_0000_0000_0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER          = 0b0000_0000_0000_0000_0000_0000_0100_0000;
+        const DIM                       = 0b0000_0000_0000_0000_0000_0000_1000_0000;
+        const DIM_BOLD                  = 0b0000_0000_0000_0000_0000_0000_1000_0010;
+        const HIDDEN                    = 0b0000_0000_0000_0000_0000_0001_0000_0000;
+        const STRIKEOUT                 = 0b0000_0000_0000_0000_0000_0010_0000_0000;
+        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0000_0000_0000_0000_0100_0000_0000;
+        const OVERLINE                  = 0b0000_0000_0000_0000_0010_0000_0000_0000;
+        const BLINKING                  = 0b0000_0000_0000_0000_0100_0000_0000_0000;
+        const RAPID_BLINKING            = 0b0000_0000_0000_0000_1000_0000_0000_0000;
+        const ALL_BLINKING              = 0b0000_0000_0000_0000_1100_0000_0000_0000;
+        const PROMPT                    = 0b0000_0000_0000_0001_0000_0000_0000_0000;
+        const COMMAND_INPUT             = 0b0000_0000_0000_0010_0000_0000_0000_0000;
+        const COMMAND_OUTPUT            = 0b0000_0000_0000_0100_0000_0000_0000_0000;
+        const SEMANTIC_ZONE             = 0b0000_0000_0000_0111_0000_0000_0000_0000;
     }
 }
 
+impl Flags {
+    /// ECMA-48 name of [`Flags::HIDDEN`], set by SGR 8 and cleared by SGR 28.
+    ///
+    /// Both names refer to the same flag, concealed text keeps its content but is rendered
+    /// using the background color.
+    pub const CONCEALED: Flags = Flags::HIDDEN;
+}
+
+/// Maximum number of zerowidth characters stored in a single cell.
+///
+/// Additional characters are discarded, which keeps storage and text extraction bounded for
+/// pathological input.
+pub const MAX_ZEROWIDTH_CHARS: usize = 16;
+
+/// Minimum number of interned hyperlinks before unused ones are removed.
+const MIN_INTERNER_PRUNE_THRESHOLD: usize = 64;
+
 /// Trait for determining if a reset should be performed.
 pub trait ResetDiscriminant<T> {
     /// Value based on which equality for the reset will be determined.
@@ -45,6 +79,84 @@ impl ResetDiscriminant<Color> for Cell {
     }
 }
 
+/// Style of a cell's underline.
+///
+/// Cells with any underline have [`Flags::UNDERLINE`] set, the style itself is only stored in the
+/// dynamic cell storage when it is not [`UnderlineStyle::Single`].
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UnderlineStyle {
+    None,
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+/// OSC 8 hyperlink target.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Hyperlink {
+    /// Identifier used to group cells which are not adjacent into a single hyperlink.
+    pub id: Option<String>,
+
+    /// Target URI.
+    pub uri: String,
+}
+
+impl Hyperlink {
+    /// Check if two cells with these hyperlinks belong to the same link.
+    ///
+    /// Hyperlinks with an ID are grouped by their ID, all others by their URI.
+    pub fn is_same_link(&self, other: &Hyperlink) -> bool {
+        match (&self.id, &other.id) {
+            (Some(id), Some(other_id)) => id == other_id,
+            (None, None) => self.uri == other.uri,
+            _ => false,
+        }
+    }
+}
+
+/// Deduplication of hyperlinks shared by many cells.
+///
+/// Applications commonly emit the same hyperlink multiple times, for example once for every
+/// line it spans. Interning makes all of these cells share a single allocation.
+#[derive(Debug, Default)]
+pub struct HyperlinkInterner {
+    links: HashMap<Hyperlink, Weak<Hyperlink>>,
+
+    /// Number of links after which unused entries are removed.
+    prune_threshold: usize,
+}
+
+impl HyperlinkInterner {
+    /// Get a shared handle for a hyperlink.
+    pub fn intern(&mut self, hyperlink: Hyperlink) -> Arc<Hyperlink> {
+        if let Some(link) = self.links.get(&hyperlink).and_then(Weak::upgrade) {
+            return link;
+        }
+
+        // Drop links which are no longer referenced by any cell.
+        if self.links.len() >= self.prune_threshold {
+            self.links.retain(|_, link| link.strong_count() > 0);
+            self.prune_threshold = max(MIN_INTERNER_PRUNE_THRESHOLD, self.links.len() * 2);
+        }
+
+        let link = Arc::new(hyperlink.clone());
+        self.links.insert(hyperlink, Arc::downgrade(&link));
+        link
+    }
+
+    /// Number of tracked hyperlinks, including ones which are no longer used.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Check if no hyperlinks are tracked.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
 /// Dynamically allocated cell content.
 ///
 /// This storage is reserved for cell attributes which are rarely set. This allows reducing the
@@ -52,20 +164,73 @@ impl ResetDiscriminant<Color> for Cell {
 /// storage is actually required.
 #[derive(Serialize, Deserialize, Default, Debug, Clone, Eq, PartialEq)]
 struct CellExtra {
-    zerowidth: Vec<char>,
+    zerowidth: SmallVec<[char; 2]>,
+    #[serde(default)]
+    underline_color: Option<Color>,
+    #[serde(default)]
+    hyperlink: Option<Arc<Hyperlink>>,
+    /// Underline style, unless it is single or no underline.
+    #[serde(default)]
+    underline_style: Option<UnderlineStyle>,
+    /// Part of an image displayed in this cell.
+    #[serde(skip)]
+    graphic: Option<GraphicCell>,
+}
+
+impl CellExtra {
+    /// Check if the storage holds no data and can be freed.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.zerowidth.is_empty()
+            && self.underline_color.is_none()
+            && self.hyperlink.is_none()
+            && self.underline_style.is_none()
+            && self.graphic.is_none()
+    }
 }
 
 /// Content and attributes of a single cell in the terminal grid.
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq)]
 pub struct Cell {
     pub c: char,
     pub fg: Color,
     pub bg: Color,
     pub flags: Flags,
+    /// Terminal generation in which this cell was last written.
+    ///
+    /// This is not part of the cell's content, so it is neither compared nor serialized.
+    #[serde(skip)]
+    pub generation: u32,
     #[serde(default)]
     extra: Option<Box<CellExtra>>,
 }
 
+/// Style of a cell packed into the instance attributes of the text shader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VertexStyle {
+    /// Foreground color.
+    pub fg: [u8; 4],
+
+    /// Background color, transparent for the default background.
+    pub bg: [u8; 4],
+
+    /// Shader flags.
+    pub flags: u32,
+}
+
+impl VertexStyle {
+    /// Glyph occupies two cells, `WIDE_CHAR` in `text.v.glsl`.
+    pub const WIDE_CHAR: u32 = 0b01;
+
+    /// Glyph has its own colors, `COLORED` in `text.f.glsl`.
+    ///
+    /// This depends on the rasterized glyph, so it is never set for a cell.
+    pub const COLORED: u32 = 0b10;
+
+    /// Glyph must be drawn without subpixel antialiasing, `GRAYSCALE` in `text.f.glsl`.
+    pub const GRAYSCALE: u32 = 0b100;
+}
+
 impl Default for Cell {
     #[inline]
     fn default() -> Cell {
@@ -74,11 +239,22 @@ impl Default for Cell {
             bg: Color::Named(NamedColor::Background),
             fg: Color::Named(NamedColor::Foreground),
             flags: Flags::empty(),
+            generation: 0,
             extra: None,
         }
     }
 }
 
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.c == other.c
+            && self.fg == other.fg
+            && self.bg == other.bg
+            && self.flags == other.flags
+            && self.extra == other.extra
+    }
+}
+
 impl Cell {
     /// Zerowidth characters stored in this cell.
     #[inline]
@@ -87,9 +263,331 @@ impl Cell {
     }
 
     /// Write a new zerowidth character to this cell.
+    ///
+    /// Characters beyond [`MAX_ZEROWIDTH_CHARS`] are ignored.
     #[inline]
     pub fn push_zerowidth(&mut self, c: char) {
-        self.extra.get_or_insert_with(Default::default).zerowidth.push(c);
+        let zerowidth = &mut self.extra.get_or_insert_with(Default::default).zerowidth;
+        if zerowidth.len() < MAX_ZEROWIDTH_CHARS {
+            zerowidth.push(c);
+        }
+    }
+
+    /// Semantic zone this cell was written in.
+    #[inline]
+    pub fn semantic_zone(&self) -> SemanticZone {
+        if self.flags.contains(Flags::PROMPT) {
+            SemanticZone::Prompt
+        } else if self.flags.contains(Flags::COMMAND_INPUT) {
+            SemanticZone::Input
+        } else {
+            SemanticZone::Output
+        }
+    }
+
+    /// Swap the default foreground and background colors of this cell.
+    ///
+    /// This turns light text on a dark background into dark text on a light background and back,
+    /// without changing the theme. Explicit colors are left alone, so toggling twice always
+    /// restores the original cell.
+    #[inline]
+    pub fn toggle_dark(&mut self) {
+        let swap = |color| match color {
+            Color::Named(NamedColor::Foreground) => Color::Named(NamedColor::Background),
+            Color::Named(NamedColor::Background) => Color::Named(NamedColor::Foreground),
+            color => color,
+        };
+
+        self.fg = swap(self.fg);
+        self.bg = swap(self.bg);
+    }
+
+    /// Check if two cells would be shaped into the same glyph.
+    ///
+    /// Only the character and its zerowidth characters are compared, colors and flags are ignored.
+    #[inline]
+    pub fn same_glyph(&self, other: &Cell) -> bool {
+        self.c == other.c && self.zerowidth().unwrap_or(&[]) == other.zerowidth().unwrap_or(&[])
+    }
+
+    /// Check if two cells share all attributes affecting their appearance.
+    ///
+    /// This compares everything written by [`Cell::as_escape`], while the character is ignored.
+    /// Cells displaying part of an image never share their appearance with another cell.
+    #[inline]
+    pub fn appearance_eq(&self, other: &Cell) -> bool {
+        let same_link = match (self.hyperlink(), other.hyperlink()) {
+            (Some(hyperlink), Some(other_hyperlink)) => hyperlink.is_same_link(other_hyperlink),
+            (hyperlink, other_hyperlink) => hyperlink.is_none() && other_hyperlink.is_none(),
+        };
+
+        self.fg == other.fg
+            && self.bg == other.bg
+            && self.flags == other.flags
+            && self.underline_color() == other.underline_color()
+            && self.underline_style() == other.underline_style()
+            && self.graphic().is_none()
+            && other.graphic().is_none()
+            && same_link
+    }
+
+    /// Check if the glyph of this cell can be drawn with subpixel antialiasing.
+    ///
+    /// Subpixel antialiasing blends each color channel with an opaque background separately.
+    /// This isn't possible on top of an image, which might be transparent, or for inverse cells
+    /// with the default background, since their glyph has to match a possibly translucent window.
+    #[inline]
+    pub fn allows_subpixel(&self) -> bool {
+        let inverse_default_bg =
+            self.flags.contains(Flags::INVERSE) && self.bg == Color::Named(NamedColor::Background);
+        self.graphic().is_none() && !inverse_default_bg
+    }
+
+    /// Resolve the colors and flags of this cell for the text shader.
+    ///
+    /// The default foreground and background colors are taken from the arguments, all other
+    /// colors are looked up in the palette. Inverse cells have their colors swapped, while hidden
+    /// cells use their background color for the foreground.
+    pub fn to_vertex_style<R: ColorResolver>(
+        &self,
+        palette: &R,
+        default_fg: Rgb,
+        default_bg: Rgb,
+    ) -> VertexStyle {
+        let resolve = |color| match color {
+            Color::Named(NamedColor::Foreground) => default_fg,
+            Color::Named(NamedColor::Background) => default_bg,
+            color => palette.resolve(color),
+        };
+
+        let mut fg = resolve(self.fg);
+        let mut bg = resolve(self.bg);
+        let mut bg_alpha = if self.bg == Color::Named(NamedColor::Background) { 0 } else { 255 };
+
+        if self.flags.contains(Flags::INVERSE) {
+            mem::swap(&mut fg, &mut bg);
+            bg_alpha = 255;
+        }
+
+        if self.flags.contains(Flags::HIDDEN) {
+            fg = bg;
+        }
+
+        let mut flags = 0;
+        if self.flags.contains(Flags::WIDE_CHAR) {
+            flags |= VertexStyle::WIDE_CHAR;
+        }
+        if !self.allows_subpixel() {
+            flags |= VertexStyle::GRAYSCALE;
+        }
+
+        VertexStyle { fg: [fg.r, fg.g, fg.b, 255], bg: [bg.r, bg.g, bg.b, bg_alpha], flags }
+    }
+
+    /// Number of columns occupied by the grapheme cluster of this cell.
+    ///
+    /// Wide char spacers are part of the preceding wide char and do not occupy any columns on
+    /// their own. The width of other cells is derived from their character and all zerowidth
+    /// characters attached to it.
+    pub fn grapheme_width(&self) -> usize {
+        if self.flags.contains(Flags::WIDE_CHAR_SPACER) {
+            return 0;
+        } else if self.flags.contains(Flags::WIDE_CHAR) {
+            return 2;
+        }
+
+        let zerowidth = self.zerowidth().unwrap_or(&[]);
+        let width: usize =
+            iter::once(&self.c).chain(zerowidth).map(|c| c.width().unwrap_or(0)).sum();
+
+        min(width, 2)
+    }
+
+    /// Underline color of this cell, `None` if it uses the foreground color.
+    #[inline]
+    pub fn underline_color(&self) -> Option<Color> {
+        self.extra.as_ref().and_then(|extra| extra.underline_color)
+    }
+
+    /// Set the underline color of this cell.
+    ///
+    /// The dynamic cell storage is only allocated when a color is set, and freed again once it
+    /// no longer holds any data.
+    #[inline]
+    pub fn set_underline_color(&mut self, color: Option<Color>) {
+        match (&mut self.extra, color) {
+            (None, None) => (),
+            (Some(extra), None) => {
+                extra.underline_color = None;
+                if extra.is_empty() {
+                    self.extra = None;
+                }
+            },
+            (extra, Some(_)) => {
+                extra.get_or_insert_with(Default::default).underline_color = color;
+            },
+        }
+    }
+
+    /// Underline style of this cell.
+    #[inline]
+    pub fn underline_style(&self) -> UnderlineStyle {
+        if !self.flags.contains(Flags::UNDERLINE) {
+            return UnderlineStyle::None;
+        }
+
+        self.extra
+            .as_ref()
+            .and_then(|extra| extra.underline_style)
+            .unwrap_or(UnderlineStyle::Single)
+    }
+
+    /// Set the underline style of this cell.
+    ///
+    /// This updates [`Flags::UNDERLINE`], the dynamic cell storage is only required for styles
+    /// other than a single underline.
+    #[inline]
+    pub fn set_underline_style(&mut self, style: UnderlineStyle) {
+        self.flags.set(Flags::UNDERLINE, style != UnderlineStyle::None);
+
+        let style = match style {
+            UnderlineStyle::None | UnderlineStyle::Single => None,
+            style => Some(style),
+        };
+
+        match (&mut self.extra, style) {
+            (None, None) => (),
+            (Some(extra), None) => {
+                extra.underline_style = None;
+                if extra.is_empty() {
+                    self.extra = None;
+                }
+            },
+            (extra, Some(_)) => {
+                extra.get_or_insert_with(Default::default).underline_style = style;
+            },
+        }
+    }
+
+    /// OSC 8 hyperlink this cell is part of.
+    #[inline]
+    pub fn hyperlink(&self) -> Option<&Arc<Hyperlink>> {
+        self.extra.as_ref().and_then(|extra| extra.hyperlink.as_ref())
+    }
+
+    /// Set the hyperlink of this cell.
+    ///
+    /// Like the underline color, this only keeps the dynamic cell storage around while required.
+    #[inline]
+    pub fn set_hyperlink(&mut self, hyperlink: Option<Arc<Hyperlink>>) {
+        match (&mut self.extra, hyperlink) {
+            (None, None) => (),
+            (Some(extra), None) => {
+                extra.hyperlink = None;
+                if extra.is_empty() {
+                    self.extra = None;
+                }
+            },
+            (extra, hyperlink) => {
+                extra.get_or_insert_with(Default::default).hyperlink = hyperlink;
+            },
+        }
+    }
+
+    /// Part of an image displayed in this cell.
+    #[inline]
+    pub fn graphic(&self) -> Option<&GraphicCell> {
+        self.extra.as_ref().and_then(|extra| extra.graphic.as_ref())
+    }
+
+    /// Set the part of an image displayed in this cell.
+    #[inline]
+    pub fn set_graphic(&mut self, graphic: Option<GraphicCell>) {
+        match (&mut self.extra, graphic) {
+            (None, None) => (),
+            (Some(extra), None) => {
+                extra.graphic = None;
+                if extra.is_empty() {
+                    self.extra = None;
+                }
+            },
+            (extra, graphic) => {
+                extra.get_or_insert_with(Default::default).graphic = graphic;
+            },
+        }
+    }
+
+    /// Number of generations since this cell was last written.
+    ///
+    /// Cells written in a later generation than `current_gen` have an age of zero.
+    #[inline]
+    pub fn age(&self, current_gen: u32) -> u32 {
+        current_gen.saturating_sub(self.generation)
+    }
+
+    /// Create a cell from `template` with the attributes of an SGR escape applied.
+    ///
+    /// This is the inverse of [`Cell::as_escape`] for all attributes which do not require colon
+    /// separated subparameters, so every value in `csi_params` is treated as a separate parameter.
+    /// Unknown or incomplete parameters are ignored.
+    pub fn from_escape(csi_params: &[u16], template: &Cell) -> Cell {
+        let mut cell = template.clone();
+
+        // Without any parameters, SGR resets all attributes.
+        if csi_params.is_empty() {
+            cell.apply_attr(Attr::Reset);
+        }
+
+        let mut params = csi_params.iter().map(std::slice::from_ref);
+        for attr in ansi::attrs_from_sgr_parameters(&mut params).into_iter().flatten() {
+            cell.apply_attr(attr);
+        }
+
+        cell
+    }
+
+    /// Apply a single SGR attribute to this cell.
+    pub fn apply_attr(&mut self, attr: Attr) {
+        match attr {
+            Attr::Foreground(color) => self.fg = color,
+            Attr::Background(color) => self.bg = color,
+            Attr::Reset => {
+                self.fg = Color::Named(NamedColor::Foreground);
+                self.bg = Color::Named(NamedColor::Background);
+                // The semantic zone is not part of the text attributes.
+                self.flags &= Flags::SEMANTIC_ZONE;
+                self.set_underline_style(UnderlineStyle::None);
+                self.set_underline_color(None);
+            },
+            Attr::Reverse => self.flags.insert(Flags::INVERSE),
+            Attr::CancelReverse => self.flags.remove(Flags::INVERSE),
+            Attr::Bold => self.flags.insert(Flags::BOLD),
+            Attr::CancelBold => self.flags.remove(Flags::BOLD),
+            Attr::Dim => self.flags.insert(Flags::DIM),
+            Attr::CancelBoldDim => self.flags.remove(Flags::BOLD | Flags::DIM),
+            Attr::Italic => self.flags.insert(Flags::ITALIC),
+            Attr::BlinkSlow => {
+                self.flags.remove(Flags::RAPID_BLINKING);
+                self.flags.insert(Flags::BLINKING);
+            },
+            Attr::BlinkFast => {
+                self.flags.remove(Flags::BLINKING);
+                self.flags.insert(Flags::RAPID_BLINKING);
+            },
+            Attr::CancelBlink => self.flags.remove(Flags::ALL_BLINKING),
+            Attr::CancelItalic => self.flags.remove(Flags::ITALIC),
+            Attr::Underline => self.set_underline_style(UnderlineStyle::Single),
+            Attr::DoubleUnderline => self.set_underline_style(UnderlineStyle::Double),
+            Attr::Undercurl => self.set_underline_style(UnderlineStyle::Curly),
+            Attr::CancelUnderline => self.set_underline_style(UnderlineStyle::None),
+            Attr::Hidden => self.flags.insert(Flags::HIDDEN),
+            Attr::CancelHidden => self.flags.remove(Flags::HIDDEN),
+            Attr::Strike => self.flags.insert(Flags::STRIKEOUT),
+            Attr::CancelStrike => self.flags.remove(Flags::STRIKEOUT),
+            Attr::Overline => self.flags.insert(Flags::OVERLINE),
+            Attr::CancelOverline => self.flags.remove(Flags::OVERLINE),
+            Attr::UnderlineColor(color) => self.set_underline_color(color),
+        }
     }
 
     /// Free all dynamically allocated cell storage.
@@ -99,6 +597,253 @@ impl Cell {
             self.extra = None;
         }
     }
+
+    /// Check if this cell's content requires text shaping to be rendered correctly.
+    ///
+    /// Only Latin cells without any combining characters can be rendered glyph by glyph, this
+    /// excludes complex scripts like Arabic or Indic, which are all outside of the Latin blocks.
+    #[inline]
+    pub fn needs_shaping(&self) -> bool {
+        self.c > '\u{24f}'
+            || self.flags.contains(Flags::WIDE_CHAR)
+            || self.zerowidth().map(<[char]>::is_empty) == Some(false)
+    }
+
+    /// Write the escapes required to go from `last`'s attributes to this cell's attributes.
+    ///
+    /// This includes the SGR attributes and the OSC 8 hyperlink. Nothing is written if both
+    /// cells share the same attributes. Hyperlinks are only reopened once a different link is
+    /// entered, following the grouping of [`Hyperlink::is_same_link`].
+    pub fn as_escape(&self, buf: &mut String, last: &Self) {
+        self.push_escape(buf, last, |color, _| color);
+    }
+
+    /// Write the escapes required to go from `last`'s attributes to this cell's attributes, for a
+    /// terminal with different default colors.
+    ///
+    /// Colors which resolve to the receiving terminal's default foreground or background color
+    /// using `palette` are written as default colors, so no color codes are emitted for them.
+    pub fn as_escape_for_defaults<R: ColorResolver>(
+        &self,
+        buf: &mut String,
+        last: &Self,
+        palette: &R,
+        term_default_fg: Rgb,
+        term_default_bg: Rgb,
+    ) {
+        self.push_escape(buf, last, |color, default| {
+            let term_default =
+                if default == NamedColor::Foreground { term_default_fg } else { term_default_bg };
+
+            if palette.resolve(color) == term_default {
+                Color::Named(default)
+            } else {
+                color
+            }
+        });
+    }
+
+    /// Write the escapes for [`Cell::as_escape`], mapping colors with `default_color`.
+    ///
+    /// The mapping receives each foreground and background color, along with its default.
+    fn push_escape<F>(&self, buf: &mut String, last: &Self, default_color: F)
+    where
+        F: Fn(Color, NamedColor) -> Color,
+    {
+        let start = buf.len();
+        buf.push_str("\x1b[");
+        self.push_sgr_params(buf, last, default_color);
+        finish_escape(buf, start, start + 2);
+
+        let hyperlink = self.hyperlink();
+        let same_link = match (hyperlink, last.hyperlink()) {
+            (Some(hyperlink), Some(last_hyperlink)) => hyperlink.is_same_link(last_hyperlink),
+            (hyperlink, last_hyperlink) => hyperlink.is_none() && last_hyperlink.is_none(),
+        };
+        if !same_link {
+            push_hyperlink_escape(buf, hyperlink.map(|hyperlink| &**hyperlink));
+        }
+    }
+
+    /// Write this cell's text, preceded by the escapes required to go from `last`'s attributes.
+    ///
+    /// Zerowidth characters are written after the base character. Wide char spacers are skipped
+    /// entirely, since their column is already covered by the preceding wide char.
+    pub fn write_cell(&self, buf: &mut String, last: &Self) {
+        if self.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+            return;
+        }
+
+        self.as_escape(buf, last);
+        buf.push(self.c);
+        buf.extend(self.zerowidth().into_iter().flatten());
+    }
+
+    /// Write the SGR escape for this cell, assuming the terminal is in its default state.
+    ///
+    /// Unlike calling [`Cell::as_escape`] with a default cell, this will always start with a
+    /// reset whenever any attribute differs from the default, so stale attributes of the
+    /// receiving terminal cannot leak into the output.
+    pub fn as_escape_from_default(&self, buf: &mut String) {
+        let start = buf.len();
+        buf.push_str("\x1b[0;");
+        self.push_sgr_params(buf, &Cell::default(), |color, _| color);
+        finish_escape(buf, start, start + 4);
+
+        if let Some(hyperlink) = self.hyperlink() {
+            push_hyperlink_escape(buf, Some(hyperlink));
+        }
+    }
+
+    /// Append all SGR parameters which differ between `last` and this cell.
+    ///
+    /// Every parameter is terminated by a `;`. Foreground and background colors are compared
+    /// after mapping them with `default_color`.
+    fn push_sgr_params<F>(&self, buf: &mut String, last: &Self, default_color: F)
+    where
+        F: Fn(Color, NamedColor) -> Color,
+    {
+        macro_rules! append_if_flags_differ {
+            ($flag:expr, $set:expr, $clear:expr) => {
+                if self.flags.contains($flag) != last.flags.contains($flag) {
+                    if self.flags.contains($flag) {
+                        buf.push_str(concat!($set, ";"));
+                    } else {
+                        buf.push_str(concat!($clear, ";"));
+                    }
+                }
+            };
+            ($flag:expr, $num:literal) => {
+                append_if_flags_differ!($flag, $num, concat!("2", $num))
+            };
+        }
+
+        // Bold and dim are both cleared by SGR 22, so one might have to be restored.
+        let bold_dim = Flags::BOLD | Flags::DIM;
+        let removed = last.flags & bold_dim & !self.flags;
+        let added = if removed.is_empty() {
+            self.flags & bold_dim & !last.flags
+        } else {
+            buf.push_str("22;");
+            self.flags & bold_dim
+        };
+        if added.contains(Flags::BOLD) {
+            buf.push_str("1;");
+        }
+        if added.contains(Flags::DIM) {
+            buf.push_str("2;");
+        }
+
+        append_if_flags_differ!(Flags::ITALIC, "3");
+
+        // Both blink rates are cleared by SGR 25.
+        let blink = blink_param(self.flags);
+        if blink != blink_param(last.flags) {
+            buf.push_str(blink.unwrap_or("25;"));
+        }
+
+        // Underline styles are mutually exclusive and all of them are cleared by SGR 24.
+        let underline = self.underline_style();
+        if underline != last.underline_style() {
+            buf.push_str(underline_param(underline));
+        }
+
+        append_if_flags_differ!(Flags::INVERSE, "7");
+        append_if_flags_differ!(Flags::HIDDEN, "8");
+        append_if_flags_differ!(Flags::STRIKEOUT, "9");
+        append_if_flags_differ!(Flags::OVERLINE, "53", "55");
+
+        let fg = default_color(self.fg, NamedColor::Foreground);
+        if fg != default_color(last.fg, NamedColor::Foreground) {
+            push_color_param(buf, fg, 30);
+        }
+
+        let bg = default_color(self.bg, NamedColor::Background);
+        if bg != default_color(last.bg, NamedColor::Background) {
+            push_color_param(buf, bg, 40);
+        }
+
+        let underline_color = self.underline_color();
+        if underline_color != last.underline_color() {
+            match underline_color {
+                Some(color) => push_underline_color_param(buf, color),
+                None => buf.push_str("59;"),
+            }
+        }
+    }
+}
+
+/// Terminate an SGR escape started at `start`, or remove it if no parameters were written.
+fn finish_escape(buf: &mut String, start: usize, params_start: usize) {
+    if buf.len() == params_start {
+        buf.truncate(start);
+    } else {
+        // Replace the trailing parameter separator.
+        buf.pop();
+        buf.push('m');
+    }
+}
+
+/// Write an OSC 8 escape opening `hyperlink`, or closing the active hyperlink if it is `None`.
+fn push_hyperlink_escape(buf: &mut String, hyperlink: Option<&Hyperlink>) {
+    buf.push_str("\x1b]8;");
+    if let Some(hyperlink) = hyperlink {
+        if let Some(id) = &hyperlink.id {
+            buf.push_str("id=");
+            buf.push_str(id);
+        }
+        buf.push(';');
+        buf.push_str(&hyperlink.uri);
+    } else {
+        buf.push(';');
+    }
+    buf.push_str("\x1b\\");
+}
+
+/// SGR parameter for the blink rate of a cell, `None` without any blinking.
+fn blink_param(flags: Flags) -> Option<&'static str> {
+    if flags.contains(Flags::RAPID_BLINKING) {
+        Some("6;")
+    } else if flags.contains(Flags::BLINKING) {
+        Some("5;")
+    } else {
+        None
+    }
+}
+
+/// SGR parameter for an underline style.
+///
+/// Double underlines use `4:2` instead of `21`, since SGR 21 is parsed as cancel bold.
+fn underline_param(style: UnderlineStyle) -> &'static str {
+    match style {
+        UnderlineStyle::None => "24;",
+        UnderlineStyle::Single => "4;",
+        UnderlineStyle::Double => "4:2;",
+        UnderlineStyle::Curly => "4:3;",
+        UnderlineStyle::Dotted => "4:4;",
+        UnderlineStyle::Dashed => "4:5;",
+    }
+}
+
+/// Append the SGR parameter for a color, with `base` being 30 for foreground and 40 for background.
+fn push_color_param(buf: &mut String, color: Color, base: u8) {
+    let _ = match color {
+        Color::Named(color) => {
+            let index = color as usize;
+            let dim_black = NamedColor::DimBlack as usize;
+            match index {
+                0..=7 => write!(buf, "{};", base as usize + index),
+                8..=15 => write!(buf, "{};", base as usize + 52 + index),
+                // Dim colors have no escape of their own, so the normal color is used instead.
+                _ if index >= dim_black && index <= NamedColor::DimWhite as usize => {
+                    write!(buf, "{};", base as usize + index - dim_black)
+                },
+                _ => write!(buf, "{};", base + 9),
+            }
+        },
+        Color::Indexed(index) => write!(buf, "{};5;{};", base + 8, index),
+        Color::Spec(rgb) => write!(buf, "{};2;{};{};{};", base + 8, rgb.r, rgb.g, rgb.b),
+    };
 }
 
 impl GridCell for Cell {
@@ -110,13 +855,14 @@ impl GridCell for Cell {
             && !self.flags.intersects(
                 Flags::INVERSE
                     | Flags::UNDERLINE
-                    | Flags::DOUBLE_UNDERLINE
                     | Flags::STRIKEOUT
+                    | Flags::OVERLINE
+                    | Flags::ALL_BLINKING
                     | Flags::WRAPLINE
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self.extra.as_ref().map(|extra| extra.is_empty()) != Some(false)
     }
 
     #[inline]
@@ -146,6 +892,10 @@ impl From<Color> for Cell {
 pub trait LineLength {
     /// Calculate the occupied line length.
     fn line_length(&self) -> Column;
+
+    /// Calculate the occupied line length, including trailing blanks with a non-default
+    /// background.
+    fn line_length_with_bg(&self) -> Column;
 }
 
 impl LineLength for grid::Row<Cell> {
@@ -167,14 +917,207 @@ impl LineLength for grid::Row<Cell> {
 
         length
     }
+
+    fn line_length_with_bg(&self) -> Column {
+        let background = Color::Named(NamedColor::Background);
+        let colored = self[..].iter().rposition(|cell| cell.bg != background);
+
+        match colored {
+            Some(index) => max(self.line_length(), Column(index + 1)),
+            None => self.line_length(),
+        }
+    }
+}
+
+/// Union of the flags of all cells in a row.
+///
+/// This allows checking if any cell in the row uses an attribute, like italic text, without
+/// inspecting every cell individually.
+pub fn row_flag_union(row: &grid::Row<Cell>) -> Flags {
+    let mut union = Flags::empty();
+
+    for cell in &row[..] {
+        union |= cell.flags;
+
+        // No further cell can add any flags.
+        if union.is_all() {
+            break;
+        }
+    }
+
+    union
+}
+
+/// Length of the longest run of identical cells in a row.
+///
+/// Cells are identical when they render the same glyph with the same appearance, which allows
+/// serializers to decide whether repeating characters with `REP` is worth it.
+pub fn longest_cell_run(row: &grid::Row<Cell>) -> usize {
+    let cells = &row[..];
+    let mut longest = 0;
+    let mut run = 0;
+
+    for (i, cell) in cells.iter().enumerate() {
+        let previous = &cells[i.saturating_sub(1)];
+        if i > 0 && cell.same_glyph(previous) && cell.appearance_eq(previous) {
+            run += 1;
+        } else {
+            run = 1;
+        }
+        longest = max(longest, run);
+    }
+
+    longest
+}
+
+/// Write a narrow char to a cell which might be part of a wide char.
+///
+/// The other half of an overwritten wide char is reset to a default cell, so no spacer without
+/// its wide char or wide char without its spacer is left behind. A leading wide char spacer
+/// becomes a regular cell, since it no longer pads the end of the line.
+pub fn write_narrow_over(row: &mut grid::Row<Cell>, col: Column, c: char) {
+    let flags = row[col].flags;
+    if flags.contains(Flags::WIDE_CHAR) && col.0 + 1 < row.len() {
+        row[col + 1] = Cell::default();
+    } else if flags.contains(Flags::WIDE_CHAR_SPACER) && col.0 > 0 {
+        row[col - 1] = Cell::default();
+    }
+
+    let cell = &mut row[col];
+    cell.flags.remove(Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER);
+    cell.c = c;
+}
+
+/// Convert a column in a row to the byte offset of its text in the extracted row.
+///
+/// Wide char spacers map to the start of their wide char. Tab stops are not taken into account,
+/// so every cell is assumed to be part of the extracted text.
+pub fn column_to_byte_offset(row: &grid::Row<Cell>, col: Column) -> usize {
+    let mut col = min(col, Column(row.len()));
+    if col.0 > 0 && col.0 < row.len() && row[col].flags.contains(Flags::WIDE_CHAR_SPACER) {
+        col -= 1;
+    }
+
+    row[..col].iter().map(text_len).sum()
+}
+
+/// Convert a byte offset in the extracted row text to the column containing it.
+///
+/// Offsets past the end of the text map to the column after the last cell.
+pub fn byte_offset_to_column(row: &grid::Row<Cell>, offset: usize) -> Column {
+    let mut start = 0;
+    for (col, cell) in row[..].iter().enumerate() {
+        start += text_len(cell);
+        if offset < start {
+            return Column(col);
+        }
+    }
+
+    Column(row.len())
+}
+
+/// Length in bytes of a cell's text, as it is extracted from the grid.
+fn text_len(cell: &Cell) -> usize {
+    if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+        return 0;
+    }
+
+    let zerowidth = cell.zerowidth().into_iter().flatten().take(MAX_ZEROWIDTH_CHARS);
+    cell.c.len_utf8() + zerowidth.map(|c| c.len_utf8()).sum::<usize>()
+}
+
+/// Append the SGR parameter for an underline color.
+///
+/// Since SGR 58 has no short form for the named colors, they use their palette index instead.
+fn push_underline_color_param(buf: &mut String, color: Color) {
+    let _ = match color {
+        Color::Named(color) if (color as usize) < 16 => write!(buf, "58;5;{};", color as usize),
+        Color::Named(color) => {
+            let index = color as usize;
+            let dim_black = NamedColor::DimBlack as usize;
+            if index >= dim_black && index <= NamedColor::DimWhite as usize {
+                write!(buf, "58;5;{};", index - dim_black)
+            } else {
+                // Remaining named colors can only be represented by the foreground default.
+                write!(buf, "59;")
+            }
+        },
+        Color::Indexed(index) => write!(buf, "58;5;{};", index),
+        Color::Spec(rgb) => write!(buf, "58;2;{};{};{};", rgb.r, rgb.g, rgb.b),
+    };
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Cell, LineLength};
+    use std::io;
+    use std::sync::Arc;
+
+    use super::{
+        byte_offset_to_column, column_to_byte_offset, longest_cell_run, row_flag_union,
+        write_narrow_over, Cell, Flags, Hyperlink, HyperlinkInterner, LineLength, UnderlineStyle,
+        VertexStyle, MAX_ZEROWIDTH_CHARS, MIN_INTERNER_PRUNE_THRESHOLD,
+    };
+
+    use crate::ansi::{Attr, Color, NamedColor, Processor};
+    use crate::config::{Colors, MockConfig};
+    use crate::event::VoidListener;
+    use crate::grid::{GridCell, Row};
+    use crate::index::{Column, Line};
+    use crate::term::color::{List, Rgb};
+    use crate::term::image::{GraphicCell, Image, ImageData};
+    use crate::term::{SizeInfo, Term};
+
+    /// Terminal with a parser for checking how escapes are applied to the grid.
+    fn terminal() -> (Term<VoidListener>, Processor) {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        (Term::new(&MockConfig::default(), size, VoidListener), Processor::new())
+    }
 
-    use crate::grid::Row;
-    use crate::index::Column;
+    fn feed(term: &mut Term<VoidListener>, parser: &mut Processor, bytes: &str) {
+        for byte in bytes.bytes() {
+            parser.advance(term, byte, &mut io::sink());
+        }
+    }
+
+    #[test]
+    fn row_flag_union_works() {
+        let mut row = Row::<Cell>::new(Column(10));
+        assert_eq!(row_flag_union(&row), Flags::empty());
+
+        row[Column(3)].flags = Flags::ITALIC;
+        row[Column(7)].flags = Flags::BOLD | Flags::UNDERLINE;
+        row[Column(9)].flags = Flags::WRAPLINE;
+
+        let union = row_flag_union(&row);
+        assert!(union.contains(Flags::ITALIC));
+        assert_eq!(union, Flags::ITALIC | Flags::BOLD | Flags::UNDERLINE | Flags::WRAPLINE);
+
+        row[Column(0)].flags = Flags::all();
+        assert_eq!(row_flag_union(&row), Flags::all());
+    }
+
+    #[test]
+    fn longest_cell_run_of_spaces() {
+        let row = Row::<Cell>::new(Column(10));
+        assert_eq!(longest_cell_run(&row), 10);
+    }
+
+    #[test]
+    fn longest_cell_run_alternating() {
+        let mut row = Row::<Cell>::new(Column(10));
+        for i in 0..row.len() {
+            row[Column(i)].c = if i % 2 == 0 { 'a' } else { 'b' };
+        }
+        assert_eq!(longest_cell_run(&row), 1);
+
+        // Runs are broken up by differing attributes.
+        for i in 0..row.len() {
+            row[Column(i)].c = 'a';
+        }
+        row[Column(3)].flags = Flags::BOLD;
+        row[Column(4)].fg = Color::Named(NamedColor::Red);
+        assert_eq!(longest_cell_run(&row), 5);
+    }
 
     #[test]
     fn line_length_works() {
@@ -191,6 +1134,864 @@ mod tests {
 
         assert_eq!(row.line_length(), Column(10));
     }
+
+    #[test]
+    fn line_length_with_bg_keeps_colored_blanks() {
+        let mut row = Row::<Cell>::new(Column(10));
+        row[Column(1)].c = 'a';
+        for col in 2..7 {
+            row[Column(col)].bg = Color::Named(NamedColor::Red);
+        }
+
+        assert_eq!(row.line_length(), Column(2));
+        assert_eq!(row.line_length_with_bg(), Column(7));
+    }
+
+    #[test]
+    fn line_length_with_bg_drops_default_blanks() {
+        let mut row = Row::<Cell>::new(Column(10));
+        row[Column(0)].bg = Color::Named(NamedColor::Red);
+        row[Column(3)].c = 'a';
+
+        assert_eq!(row.line_length(), Column(4));
+        assert_eq!(row.line_length_with_bg(), Column(4));
+    }
+
+    fn wide_char_row() -> Row<Cell> {
+        let mut row = Row::<Cell>::new(Column(4));
+        row[Column(1)].c = '字';
+        row[Column(1)].flags.insert(Flags::WIDE_CHAR);
+        row[Column(2)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        row
+    }
+
+    #[test]
+    fn write_narrow_over_wide_char() {
+        let mut row = wide_char_row();
+        write_narrow_over(&mut row, Column(1), 'a');
+
+        assert_eq!(row[Column(1)].c, 'a');
+        assert!(!row[Column(1)].flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(row[Column(2)], Cell::default());
+    }
+
+    #[test]
+    fn write_narrow_over_wide_char_spacer() {
+        let mut row = wide_char_row();
+        write_narrow_over(&mut row, Column(2), 'a');
+
+        assert_eq!(row[Column(1)], Cell::default());
+        assert_eq!(row[Column(2)].c, 'a');
+        assert!(!row[Column(2)].flags.contains(Flags::WIDE_CHAR_SPACER));
+    }
+
+    #[test]
+    fn write_narrow_over_leading_wide_char_spacer() {
+        let mut row = Row::<Cell>::new(Column(4));
+        row[Column(3)].flags.insert(Flags::LEADING_WIDE_CHAR_SPACER | Flags::WRAPLINE);
+        write_narrow_over(&mut row, Column(3), 'a');
+
+        assert_eq!(row[Column(3)].c, 'a');
+        assert_eq!(row[Column(3)].flags, Flags::WRAPLINE);
+    }
+
+    #[test]
+    fn byte_offset_conversion() {
+        // Extracted text is "a漢é b", with the `é` using a combining mark.
+        let mut row = Row::<Cell>::new(Column(6));
+        row[Column(0)].c = 'a';
+        row[Column(1)].c = '漢';
+        row[Column(1)].flags = Flags::WIDE_CHAR;
+        row[Column(2)].flags = Flags::WIDE_CHAR_SPACER;
+        row[Column(3)].c = 'e';
+        row[Column(3)].push_zerowidth('\u{0301}');
+        row[Column(5)].c = 'b';
+
+        let offsets = [0, 1, 1, 4, 7, 8];
+        for (col, offset) in offsets.iter().enumerate() {
+            assert_eq!(column_to_byte_offset(&row, Column(col)), *offset);
+        }
+        assert_eq!(column_to_byte_offset(&row, Column(6)), 9);
+
+        let columns = [0, 1, 1, 1, 3, 3, 3, 4, 5];
+        for (offset, col) in columns.iter().enumerate() {
+            assert_eq!(byte_offset_to_column(&row, offset), Column(*col));
+        }
+        assert_eq!(byte_offset_to_column(&row, 9), Column(6));
+    }
+
+    #[test]
+    fn needs_shaping_works() {
+        let mut cell = Cell::default();
+        cell.c = 'a';
+        assert!(!cell.needs_shaping());
+
+        cell.c = 'ß';
+        assert!(!cell.needs_shaping());
+
+        cell.c = 'ع';
+        assert!(cell.needs_shaping());
+
+        cell.c = 'क';
+        assert!(cell.needs_shaping());
+
+        cell.c = 'e';
+        cell.push_zerowidth('\u{0301}');
+        assert!(cell.needs_shaping());
+
+        let mut wide = Cell::default();
+        wide.c = '漢';
+        wide.flags = Flags::WIDE_CHAR;
+        assert!(wide.needs_shaping());
+    }
+
+    #[test]
+    fn zerowidth_storage_is_capped() {
+        let mut cell = Cell::default();
+        for _ in 0..MAX_ZEROWIDTH_CHARS * 2 {
+            cell.push_zerowidth('\u{0301}');
+        }
+
+        assert_eq!(cell.zerowidth().map(<[char]>::len), Some(MAX_ZEROWIDTH_CHARS));
+    }
+
+    #[test]
+    fn as_escape_for_defaults_skips_default_colors() {
+        let palette = List::from(&Colors::default());
+        let fg = palette[NamedColor::Foreground];
+        let bg = palette[NamedColor::Background];
+
+        let mut buf = String::new();
+        let cell = Cell::default();
+        cell.as_escape_for_defaults(&mut buf, &Cell::default(), &palette, fg, bg);
+        assert_eq!(buf, "");
+
+        // Explicit colors matching the defaults are not written either.
+        let mut explicit = Cell::default();
+        explicit.fg = Color::Spec(fg);
+        explicit.bg = Color::Spec(bg);
+        explicit.as_escape_for_defaults(&mut buf, &cell, &palette, fg, bg);
+        assert_eq!(buf, "");
+        explicit.as_escape(&mut buf, &cell);
+        assert!(!buf.is_empty());
+    }
+
+    #[test]
+    fn as_escape_for_defaults_with_other_defaults() {
+        let palette = List::from(&Colors::default());
+        let red = palette[NamedColor::Red];
+        let bg = palette[NamedColor::Background];
+
+        // Red text is the default of the receiving terminal.
+        let mut buf = String::new();
+        let mut cell = Cell::default();
+        cell.fg = Color::Named(NamedColor::Red);
+        cell.as_escape_for_defaults(&mut buf, &Cell::default(), &palette, red, bg);
+        assert_eq!(buf, "");
+
+        let mut blue = Cell::default();
+        blue.fg = Color::Named(NamedColor::Blue);
+        blue.as_escape_for_defaults(&mut buf, &cell, &palette, red, bg);
+        cell.as_escape_for_defaults(&mut buf, &blue, &palette, red, bg);
+        assert_eq!(buf, "\x1b[34m\x1b[39m");
+    }
+
+    #[test]
+    fn as_escape_works() {
+        let mut buf = String::new();
+        let cell = Cell::default();
+        cell.as_escape(&mut buf, &Cell::default());
+        assert_eq!(buf, "");
+
+        let mut styled = Cell::default();
+        styled.flags = Flags::BOLD | Flags::BLINKING | Flags::UNDERLINE;
+        styled.fg = Color::Named(NamedColor::BrightRed);
+        styled.bg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        styled.as_escape(&mut buf, &cell);
+        assert_eq!(buf, "\x1b[1;5;4;91;48;2;1;2;3m");
+
+        buf.clear();
+        let mut dim = Cell::default();
+        dim.flags = Flags::DIM;
+        dim.fg = Color::Indexed(100);
+        dim.as_escape(&mut buf, &styled);
+        assert_eq!(buf, "\x1b[22;2;25;24;38;5;100;49m");
+
+        // Blinking text followed by default text.
+        buf.clear();
+        let mut blink = Cell::default();
+        blink.flags = Flags::BLINKING;
+        blink.as_escape(&mut buf, &cell);
+        buf.push('a');
+        cell.as_escape(&mut buf, &blink);
+        buf.push('b');
+        assert_eq!(buf, "\x1b[5ma\x1b[25mb");
+
+        // Setting and clearing double underlines.
+        buf.clear();
+        let mut double = Cell::default();
+        double.set_underline_style(UnderlineStyle::Double);
+        double.as_escape(&mut buf, &cell);
+        cell.as_escape(&mut buf, &double);
+        assert_eq!(buf, "\x1b[4:2m\x1b[24m");
+    }
+
+    #[test]
+    fn as_escape_overline() {
+        let mut overline = Cell::default();
+        overline.flags = Flags::OVERLINE;
+        assert!(!overline.is_empty());
+
+        let mut buf = String::new();
+        overline.as_escape(&mut buf, &Cell::default());
+        buf.push(overline.c);
+        Cell::default().as_escape(&mut buf, &overline);
+        assert_eq!(buf, "\x1b[53m \x1b[55m");
+    }
+
+    #[test]
+    fn overline_escape_round_trip() {
+        let (mut term, mut parser) = terminal();
+
+        let mut overline = Cell::default();
+        overline.flags = Flags::OVERLINE;
+
+        let mut buf = String::new();
+        overline.as_escape(&mut buf, &Cell::default());
+        feed(&mut term, &mut parser, &buf);
+        assert_eq!(term.grid.cursor.template.flags, Flags::OVERLINE);
+
+        buf.clear();
+        Cell::default().as_escape(&mut buf, &overline);
+        feed(&mut term, &mut parser, &buf);
+        assert_eq!(term.grid.cursor.template.flags, Flags::empty());
+    }
+
+    #[test]
+    fn write_cell_zerowidth_round_trip() {
+        let mut accented = Cell::default();
+        accented.c = 'e';
+        accented.flags = Flags::BOLD;
+        accented.push_zerowidth('\u{0301}');
+
+        let mut wide = Cell::default();
+        wide.c = '字';
+        wide.flags = Flags::WIDE_CHAR;
+        let mut spacer = Cell::default();
+        spacer.flags = Flags::WIDE_CHAR_SPACER;
+
+        let mut buf = String::new();
+        accented.write_cell(&mut buf, &Cell::default());
+        wide.write_cell(&mut buf, &accented);
+        spacer.write_cell(&mut buf, &wide);
+        assert_eq!(buf, "\x1b[1me\u{0301}\x1b[22m字");
+
+        let (mut term, mut parser) = terminal();
+        feed(&mut term, &mut parser, &buf);
+
+        assert_eq!(term.grid[Line(0)][Column(0)], accented);
+        assert_eq!(term.grid[Line(0)][Column(1)].c, '字');
+        assert_eq!(term.grid.cursor.point.col, Column(3));
+    }
+
+    #[test]
+    fn age() {
+        let (mut term, mut parser) = terminal();
+
+        for _ in 0..1000 {
+            term.next_generation();
+        }
+        feed(&mut term, &mut parser, "a");
+
+        let generation = term.generation();
+        assert_eq!(term.grid()[Line(0)][Column(0)].age(generation), 0);
+        assert_eq!(term.grid()[Line(0)][Column(1)].age(generation), 1000);
+
+        term.next_generation();
+        assert_eq!(term.grid()[Line(0)][Column(0)].age(term.generation()), 1);
+
+        // Writing the same content again is not a change of the cell.
+        let mut cell = Cell::default();
+        cell.generation = term.generation();
+        assert_eq!(cell, Cell::default());
+    }
+
+    #[test]
+    fn grapheme_width() {
+        let (mut term, mut parser) = terminal();
+        feed(&mut term, &mut parser, "e\u{301}字a\u{200d}\u{fe0f}");
+
+        let widths = (0..5).map(|col| term.grid()[Line(0)][Column(col)].grapheme_width());
+        assert_eq!(widths.collect::<Vec<_>>(), vec![1, 2, 0, 1, 1]);
+
+        // Narrow cells never exceed two columns and zerowidth content does not occupy any.
+        let mut cell = Cell { c: '\u{200b}', ..Cell::default() };
+        assert_eq!(cell.grapheme_width(), 0);
+        cell.c = '字';
+        assert_eq!(cell.grapheme_width(), 2);
+    }
+
+    #[test]
+    fn toggle_dark_swaps_default_roles() {
+        let mut cell = Cell { c: 'a', ..Cell::default() };
+        cell.toggle_dark();
+        assert_eq!(cell.fg, Color::Named(NamedColor::Background));
+        assert_eq!(cell.bg, Color::Named(NamedColor::Foreground));
+
+        cell.toggle_dark();
+        assert_eq!(cell, Cell { c: 'a', ..Cell::default() });
+
+        // Only the default role is swapped when the other color is explicit.
+        let mut cell = Cell { bg: Color::Indexed(4), ..Cell::default() };
+        cell.toggle_dark();
+        assert_eq!(cell.fg, Color::Named(NamedColor::Background));
+        assert_eq!(cell.bg, Color::Indexed(4));
+    }
+
+    #[test]
+    fn toggle_dark_keeps_explicit_colors() {
+        let rgb = Rgb { r: 0x12, g: 0x34, b: 0x56 };
+        let mut cell =
+            Cell { fg: Color::Spec(rgb), bg: Color::Named(NamedColor::Black), ..Cell::default() };
+        let original = cell.clone();
+
+        cell.toggle_dark();
+        assert_eq!(cell, original);
+    }
+
+    #[test]
+    fn same_glyph() {
+        let red = Cell { c: 'a', fg: Color::Named(NamedColor::Red), ..Cell::default() };
+        let mut blue = Cell { c: 'a', fg: Color::Named(NamedColor::Blue), ..Cell::default() };
+        blue.flags.insert(Flags::UNDERLINE);
+        blue.set_underline_color(Some(Color::Named(NamedColor::Green)));
+        assert!(red.same_glyph(&blue));
+
+        let b = Cell { c: 'b', ..red.clone() };
+        assert!(!red.same_glyph(&b));
+
+        blue.push_zerowidth('\u{301}');
+        assert!(!red.same_glyph(&blue));
+    }
+
+    #[test]
+    fn from_escape_round_trip() {
+        let flags = [
+            Flags::BOLD,
+            Flags::DIM,
+            Flags::ITALIC,
+            Flags::UNDERLINE,
+            Flags::INVERSE,
+            Flags::HIDDEN,
+            Flags::STRIKEOUT,
+            Flags::OVERLINE,
+        ];
+        let blinking = [Flags::empty(), Flags::BLINKING, Flags::RAPID_BLINKING];
+        let colors = [
+            Color::Named(NamedColor::Red),
+            Color::Named(NamedColor::BrightCyan),
+            Color::Indexed(200),
+            Color::Spec(Rgb { r: 1, g: 128, b: 255 }),
+        ];
+        let fgs = [&[Color::Named(NamedColor::Foreground)], &colors[..]].concat();
+        let bgs = [&[Color::Named(NamedColor::Background)], &colors[..]].concat();
+
+        for mask in 0..(1 << flags.len()) {
+            let flags = (0..flags.len())
+                .filter(|bit| mask & (1 << bit) != 0)
+                .fold(Flags::empty(), |acc, bit| acc | flags[bit]);
+
+            for (blinking, fg, bg) in blinking
+                .iter()
+                .flat_map(|blinking| fgs.iter().map(move |fg| (blinking, fg)))
+                .flat_map(|(blinking, fg)| bgs.iter().map(move |bg| (blinking, fg, bg)))
+            {
+                let mut cell = Cell::default();
+                cell.flags = flags | *blinking;
+                cell.fg = *fg;
+                cell.bg = *bg;
+
+                // Named underline colors are written as indexed colors.
+                if let Color::Indexed(_) | Color::Spec(_) = bg {
+                    cell.set_underline_color(Some(*bg));
+                }
+
+                let mut buf = String::new();
+                cell.as_escape(&mut buf, &Cell::default());
+                let params = buf
+                    .trim_start_matches("\x1b[")
+                    .trim_end_matches('m')
+                    .split(';')
+                    .filter_map(|param| param.parse().ok())
+                    .collect::<Vec<u16>>();
+
+                assert_eq!(Cell::from_escape(&params, &Cell::default()), cell, "{:?}", buf);
+            }
+        }
+    }
+
+    #[test]
+    fn from_escape_ignores_invalid_params() {
+        let mut template = Cell::default();
+        template.c = 'x';
+        template.flags = Flags::WIDE_CHAR | Flags::BOLD;
+
+        // Incomplete and out of range colors are skipped.
+        let cell = Cell::from_escape(&[999, 38, 5, 300, 48, 7, 48, 2, 1, 2], &template);
+        assert_eq!(cell, template);
+
+        let cell = Cell::from_escape(&[22, 3, 38, 5, 12, 48, 2, 1, 2, 3], &template);
+        assert_eq!(cell.c, 'x');
+        assert_eq!(cell.flags, Flags::WIDE_CHAR | Flags::ITALIC);
+        assert_eq!(cell.fg, Color::Indexed(12));
+        assert_eq!(cell.bg, Color::Spec(Rgb { r: 1, g: 2, b: 3 }));
+
+        // An empty escape resets all attributes.
+        assert_eq!(Cell::from_escape(&[], &cell).flags, Flags::empty());
+    }
+
+    #[test]
+    fn as_escape_hidden() {
+        let mut hidden = Cell::default();
+        hidden.flags = Flags::HIDDEN;
+        assert_eq!(Flags::HIDDEN, Flags::CONCEALED);
+        assert_eq!(format!("{:?}", Flags::CONCEALED), "HIDDEN");
+
+        let mut buf = String::new();
+        hidden.as_escape(&mut buf, &Cell::default());
+        assert_eq!(buf, "\x1b[8m");
+
+        buf.clear();
+        Cell::default().as_escape(&mut buf, &hidden);
+        assert_eq!(buf, "\x1b[28m");
+    }
+
+    #[test]
+    fn as_escape_blink() {
+        let mut blink = Cell::default();
+        blink.flags = Flags::BLINKING;
+        assert!(!blink.is_empty());
+
+        let mut buf = String::new();
+        blink.as_escape(&mut buf, &Cell::default());
+        buf.push(blink.c);
+        Cell::default().as_escape(&mut buf, &blink);
+        assert_eq!(buf, "\x1b[5m \x1b[25m");
+
+        let mut rapid = Cell::default();
+        rapid.flags = Flags::RAPID_BLINKING;
+        assert!(!rapid.is_empty());
+
+        // Switching rates must not emit SGR 25 after the new rate.
+        buf.clear();
+        rapid.as_escape(&mut buf, &blink);
+        buf.push(rapid.c);
+        blink.as_escape(&mut buf, &rapid);
+        buf.push(blink.c);
+        Cell::default().as_escape(&mut buf, &blink);
+        assert_eq!(buf, "\x1b[6m \x1b[5m \x1b[25m");
+    }
+
+    #[test]
+    fn as_escape_undercurl() {
+        let mut buf = String::new();
+        let mut undercurl = Cell::default();
+        undercurl.set_underline_style(UnderlineStyle::Curly);
+        undercurl.as_escape(&mut buf, &Cell::default());
+        assert_eq!(buf, "\x1b[4:3m");
+
+        buf.clear();
+        let mut underline = Cell::default();
+        underline.flags = Flags::UNDERLINE;
+        underline.as_escape(&mut buf, &undercurl);
+        assert_eq!(buf, "\x1b[4m");
+
+        buf.clear();
+        Cell::default().as_escape(&mut buf, &undercurl);
+        assert_eq!(buf, "\x1b[24m");
+    }
+
+    #[test]
+    fn as_escape_double_underline() {
+        let mut underline = Cell::default();
+        underline.flags = Flags::UNDERLINE;
+        let mut double = Cell::default();
+        double.set_underline_style(UnderlineStyle::Double);
+
+        let mut buf = String::new();
+        double.as_escape(&mut buf, &underline);
+        assert_eq!(buf, "\x1b[4:2m");
+
+        buf.clear();
+        Cell::default().as_escape(&mut buf, &double);
+        assert_eq!(buf, "\x1b[24m");
+
+        buf.clear();
+        underline.as_escape(&mut buf, &double);
+        assert_eq!(buf, "\x1b[4m");
+
+        // Setting a double underline replaces the straight underline.
+        buf.clear();
+        let mut replaced = underline.clone();
+        replaced.set_underline_style(UnderlineStyle::Double);
+        replaced.c = 'a';
+        replaced.as_escape(&mut buf, &Cell::default());
+        buf.push(replaced.c);
+        Cell::default().as_escape(&mut buf, &replaced);
+        buf.push('b');
+        assert_eq!(buf, "\x1b[4:2ma\x1b[24mb");
+
+        buf.clear();
+        double.as_escape(&mut buf, &replaced);
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn as_escape_dotted_and_dashed_underline() {
+        let mut dotted = Cell::default();
+        dotted.set_underline_style(UnderlineStyle::Dotted);
+        let mut dashed = Cell::default();
+        dashed.set_underline_style(UnderlineStyle::Dashed);
+
+        let mut buf = String::new();
+        dotted.as_escape(&mut buf, &Cell::default());
+        dashed.as_escape(&mut buf, &dotted);
+        Cell::default().as_escape(&mut buf, &dashed);
+        assert_eq!(buf, "\x1b[4:4m\x1b[4:5m\x1b[24m");
+    }
+
+    #[test]
+    fn underline_style_storage() {
+        let mut cell = Cell::default();
+        assert_eq!(cell.underline_style(), UnderlineStyle::None);
+
+        // Single underlines only require the presence flag.
+        cell.apply_attr(Attr::Underline);
+        assert_eq!(cell.underline_style(), UnderlineStyle::Single);
+        assert_eq!(cell.flags, Flags::UNDERLINE);
+        assert!(cell.extra.is_none());
+
+        cell.apply_attr(Attr::Undercurl);
+        assert_eq!(cell.underline_style(), UnderlineStyle::Curly);
+        assert_eq!(cell.flags, Flags::UNDERLINE);
+        assert!(cell.extra.is_some());
+
+        cell.apply_attr(Attr::Underline);
+        assert_eq!(cell.underline_style(), UnderlineStyle::Single);
+        assert!(cell.extra.is_none());
+
+        cell.apply_attr(Attr::DoubleUnderline);
+        cell.apply_attr(Attr::CancelUnderline);
+        assert_eq!(cell.underline_style(), UnderlineStyle::None);
+        assert_eq!(cell, Cell::default());
+    }
+
+    #[test]
+    fn as_escape_underline_color() {
+        let mut buf = String::new();
+        let mut cell = Cell::default();
+        cell.set_underline_color(Some(Color::Spec(Rgb { r: 1, g: 2, b: 3 })));
+        cell.as_escape(&mut buf, &Cell::default());
+        assert_eq!(buf, "\x1b[58;2;1;2;3m");
+
+        buf.clear();
+        Cell::default().as_escape(&mut buf, &cell);
+        assert_eq!(buf, "\x1b[59m");
+    }
+
+    #[test]
+    fn as_escape_indexed_underline_color() {
+        let mut buf = String::new();
+        let mut cell = Cell::default();
+        cell.set_underline_color(Some(Color::Indexed(200)));
+        cell.as_escape(&mut buf, &Cell::default());
+        assert_eq!(buf, "\x1b[58;5;200m");
+
+        buf.clear();
+        let mut named = Cell::default();
+        named.set_underline_color(Some(Color::Named(NamedColor::BrightRed)));
+        named.as_escape(&mut buf, &cell);
+        assert_eq!(buf, "\x1b[58;5;9m");
+    }
+
+    #[test]
+    fn underline_color_storage() {
+        let mut cell = Cell::default();
+        cell.set_underline_color(None);
+        assert!(cell.extra.is_none());
+        assert!(cell.is_empty());
+
+        cell.set_underline_color(Some(Color::Indexed(1)));
+        assert_eq!(cell.underline_color(), Some(Color::Indexed(1)));
+
+        cell.set_underline_color(None);
+        assert!(cell.extra.is_none());
+
+        cell.push_zerowidth('\u{0301}');
+        cell.set_underline_color(Some(Color::Indexed(1)));
+        cell.set_underline_color(None);
+        assert_eq!(cell.zerowidth(), Some(&['\u{0301}'][..]));
+
+        cell.set_underline_color(Some(Color::Indexed(1)));
+        cell.drop_extra();
+        assert_eq!(cell.underline_color(), None);
+    }
+
+    #[test]
+    fn zerowidth_serde_round_trip() {
+        let mut cell = Cell::default();
+        for c in &['\u{0301}', '\u{0302}', '\u{0303}'] {
+            cell.push_zerowidth(*c);
+        }
+
+        let serialized = serde_json::to_string(&cell).unwrap();
+        let deserialized: Cell = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, cell);
+        assert_eq!(deserialized.zerowidth(), Some(&['\u{0301}', '\u{0302}', '\u{0303}'][..]));
+    }
+
+    #[test]
+    fn reset_clears_underline_color() {
+        let mut cell = Cell::default();
+        cell.set_underline_color(Some(Color::Indexed(1)));
+        cell.reset(&Cell::default());
+        assert_eq!(cell.underline_color(), None);
+        assert!(cell.is_empty());
+    }
+
+    #[test]
+    fn underline_color_is_not_empty() {
+        let mut cell = Cell::default();
+        cell.set_underline_color(Some(Color::Indexed(1)));
+        assert!(!cell.is_empty());
+    }
+
+    #[test]
+    fn as_escape_from_default_works() {
+        let mut buf = String::new();
+        Cell::default().as_escape_from_default(&mut buf);
+        assert_eq!(buf, "");
+
+        let mut cell = Cell::default();
+        cell.flags = Flags::ITALIC | Flags::STRIKEOUT;
+        cell.fg = Color::Named(NamedColor::Green);
+        cell.bg = Color::Named(NamedColor::DimBlue);
+        cell.as_escape_from_default(&mut buf);
+        assert_eq!(buf, "\x1b[0;3;9;32;44m");
+    }
+
+    #[test]
+    fn escape_round_trip_through_parser() {
+        let truecolor = Color::Spec(Rgb { r: 12, g: 34, b: 56 });
+        let hyperlink = Hyperlink { id: Some(String::from("x")), uri: String::from("file:///") };
+        let hyperlink = Some(Arc::new(hyperlink));
+
+        // Flags, underline style, foreground, background and underline color of every cell.
+        let (none, single) = (UnderlineStyle::None, UnderlineStyle::Single);
+        let (curly, double) = (UnderlineStyle::Curly, UnderlineStyle::Double);
+        let cells = [
+            (Flags::empty(), none, truecolor, Color::Indexed(17), None),
+            (Flags::BOLD | Flags::ITALIC, none, truecolor, Color::Indexed(0), None),
+            (Flags::DIM, single, Color::Indexed(250), truecolor, Some(truecolor)),
+            (
+                Flags::BOLD | Flags::DIM,
+                none,
+                Color::Named(NamedColor::BrightGreen),
+                truecolor,
+                None,
+            ),
+            (Flags::INVERSE | Flags::HIDDEN, none, truecolor, Color::Named(NamedColor::Blue), None),
+            (Flags::STRIKEOUT | Flags::OVERLINE, none, truecolor, Color::Indexed(231), None),
+            (Flags::empty(), curly, truecolor, Color::Indexed(52), Some(Color::Indexed(196))),
+            (Flags::BLINKING, double, truecolor, Color::Indexed(8), None),
+            (Flags::RAPID_BLINKING | Flags::ITALIC, none, Color::Indexed(4), truecolor, None),
+            (Flags::all() & !Flags::RAPID_BLINKING, single, truecolor, Color::Indexed(99), None),
+            (Flags::INVERSE, single, truecolor, Color::Indexed(255), Some(truecolor)),
+        ];
+
+        for &(flags, style, fg, bg, underline_color) in &cells {
+            let mut cell = Cell { c: 'x', flags, fg, bg, ..Cell::default() };
+            cell.set_underline_style(style);
+
+            // Flags describing the grid structure or semantic zones cannot be set through SGR.
+            cell.flags.remove(
+                Flags::WRAPLINE
+                    | Flags::WIDE_CHAR
+                    | Flags::WIDE_CHAR_SPACER
+                    | Flags::LEADING_WIDE_CHAR_SPACER
+                    | Flags::SEMANTIC_ZONE,
+            );
+            cell.set_underline_color(underline_color);
+            cell.set_hyperlink(hyperlink.clone());
+
+            let mut buf = String::new();
+            cell.as_escape_from_default(&mut buf);
+            buf.push(cell.c);
+
+            let (mut term, mut parser) = terminal();
+            feed(&mut term, &mut parser, &buf);
+
+            assert_eq!(term.grid()[Line(0)][Column(0)], cell, "{:?}", buf);
+        }
+    }
+
+    #[test]
+    fn as_escape_shared_hyperlink() {
+        let hyperlink = Hyperlink { id: None, uri: String::from("https://example.org") };
+        let hyperlink = Arc::new(hyperlink);
+        let mut cells = vec![Cell::default(); 5];
+        for cell in &mut cells[1..4] {
+            cell.set_hyperlink(Some(hyperlink.clone()));
+        }
+
+        let mut buf = String::new();
+        let mut last = Cell::default();
+        for cell in &cells {
+            cell.as_escape(&mut buf, &last);
+            buf.push('x');
+            last = cell.clone();
+        }
+
+        assert_eq!(buf, "x\x1b]8;;https://example.org\x1b\\xxx\x1b]8;;\x1b\\x");
+    }
+
+    #[test]
+    fn as_escape_hyperlink_run() {
+        let link = |id: &str, uri: &str| {
+            Some(Arc::new(Hyperlink { id: Some(String::from(id)), uri: String::from(uri) }))
+        };
+
+        // Cells of a link with an ID are grouped, even when they don't share an allocation.
+        let mut cells = vec![Cell::default(); 3];
+        cells[0].set_hyperlink(link("a", "file:///a"));
+        cells[1].set_hyperlink(link("a", "file:///a"));
+
+        let mut buf = String::new();
+        let mut last = Cell::default();
+        for cell in &cells {
+            cell.as_escape(&mut buf, &last);
+            buf.push('x');
+            last = cell.clone();
+        }
+
+        assert_eq!(buf.matches("\x1b]8;id=a;file:///a\x1b\\").count(), 1);
+        assert_eq!(buf.matches("\x1b]8;;\x1b\\").count(), 1);
+        assert_eq!(buf, "\x1b]8;id=a;file:///a\x1b\\xx\x1b]8;;\x1b\\x");
+
+        // Adjacent links with different IDs are reopened.
+        let mut second = cells[0].clone();
+        second.set_hyperlink(link("b", "file:///a"));
+        buf.clear();
+        second.as_escape(&mut buf, &cells[0]);
+        assert_eq!(buf, "\x1b]8;id=b;file:///a\x1b\\");
+    }
+
+    #[test]
+    fn hyperlink_storage() {
+        let hyperlink = Hyperlink { id: Some(String::from("a")), uri: String::from("file:///") };
+        let hyperlink = Arc::new(hyperlink);
+
+        let mut cell = Cell::default();
+        cell.set_hyperlink(Some(hyperlink.clone()));
+        assert!(!cell.is_empty());
+        assert_eq!(cell.hyperlink(), Some(&hyperlink));
+
+        let mut buf = String::new();
+        cell.as_escape_from_default(&mut buf);
+        assert_eq!(buf, "\x1b]8;id=a;file:///\x1b\\");
+
+        cell.set_hyperlink(None);
+        assert!(cell.is_empty());
+        assert!(cell.extra.is_none());
+
+        cell.set_hyperlink(Some(hyperlink.clone()));
+        cell.drop_extra();
+        assert_eq!(cell.hyperlink(), None);
+
+        cell.set_hyperlink(Some(hyperlink));
+        cell.reset(&Cell::default());
+        assert_eq!(cell.hyperlink(), None);
+    }
+
+    #[test]
+    fn hyperlink_interner() {
+        let link = |uri: &str| Hyperlink { id: None, uri: String::from(uri) };
+        let mut interner = HyperlinkInterner::default();
+
+        let first = interner.intern(link("file:///a"));
+        assert!(Arc::ptr_eq(&first, &interner.intern(link("file:///a"))));
+        assert!(!Arc::ptr_eq(&first, &interner.intern(link("file:///b"))));
+
+        // Links without any users are removed once the interner grows.
+        for i in 0..MIN_INTERNER_PRUNE_THRESHOLD * 2 {
+            interner.intern(link(&format!("file:///{}", i)));
+        }
+        assert!(interner.len() < MIN_INTERNER_PRUNE_THRESHOLD * 2);
+        assert!(Arc::ptr_eq(&first, &interner.intern(link("file:///a"))));
+    }
+
+    #[test]
+    fn hyperlink_grouping() {
+        let link = |id: Option<&str>, uri: &str| Hyperlink {
+            id: id.map(String::from),
+            uri: String::from(uri),
+        };
+
+        assert!(link(Some("a"), "file:///a").is_same_link(&link(Some("a"), "file:///b")));
+        assert!(!link(Some("a"), "file:///a").is_same_link(&link(Some("b"), "file:///a")));
+        assert!(link(None, "file:///a").is_same_link(&link(None, "file:///a")));
+        assert!(!link(None, "file:///a").is_same_link(&link(None, "file:///b")));
+        assert!(!link(Some("a"), "file:///a").is_same_link(&link(None, "file:///a")));
+    }
+
+    #[test]
+    fn allows_subpixel() {
+        let mut cell = Cell::default();
+        assert!(cell.allows_subpixel());
+
+        cell.bg = Color::Named(NamedColor::Blue);
+        cell.flags = Flags::INVERSE;
+        assert!(cell.allows_subpixel());
+
+        // Inverse cells with the default background draw their glyph in the window color.
+        cell.bg = Color::Named(NamedColor::Background);
+        assert!(!cell.allows_subpixel());
+
+        // Images might be transparent.
+        let image = Image { id: 0, serial: 0, width: 1, height: 1, data: ImageData::Rgba(vec![]) };
+        cell.flags = Flags::empty();
+        cell.set_graphic(Some(GraphicCell { image: Arc::new(image), col: 0, line: 0 }));
+        assert!(!cell.allows_subpixel());
+    }
+
+    #[test]
+    fn vertex_style() {
+        let palette = List::from(&Colors::default());
+        let default_fg = Rgb { r: 0xee, g: 0xee, b: 0xee };
+        let default_bg = Rgb { r: 0x11, g: 0x11, b: 0x11 };
+        let red = palette[NamedColor::Red];
+
+        let mut cell = Cell::default();
+        cell.fg = Color::Named(NamedColor::Red);
+        cell.flags = Flags::WIDE_CHAR;
+        let style = cell.to_vertex_style(&palette, default_fg, default_bg);
+        assert_eq!(style.fg, [red.r, red.g, red.b, 255]);
+        assert_eq!(style.bg, [0x11, 0x11, 0x11, 0]);
+        assert_eq!(style.flags, VertexStyle::WIDE_CHAR);
+
+        // Inverse cells swap their colors and always draw the background.
+        cell.flags = Flags::INVERSE;
+        let style = cell.to_vertex_style(&palette, default_fg, default_bg);
+        assert_eq!(style.fg, [0x11, 0x11, 0x11, 255]);
+        assert_eq!(style.bg, [red.r, red.g, red.b, 255]);
+        assert_eq!(style.flags, VertexStyle::GRAYSCALE);
+
+        cell.flags = Flags::INVERSE | Flags::HIDDEN;
+        cell.bg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        let style = cell.to_vertex_style(&palette, default_fg, default_bg);
+        assert_eq!(style.fg, [red.r, red.g, red.b, 255]);
+        assert_eq!(style.bg, style.fg);
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -199,6 +2000,17 @@ mod benches {
 
     use super::*;
 
+    #[bench]
+    fn push_zerowidth(b: &mut test::Bencher) {
+        b.iter(|| {
+            for _ in 0..100 {
+                let mut cell = Cell::default();
+                cell.push_zerowidth('\u{0301}');
+                test::black_box(cell);
+            }
+        });
+    }
+
     #[bench]
     fn cell_reset(b: &mut test::Bencher) {
         b.iter(|| {
@@ -231,6 +231,15 @@ impl<'de> Deserialize<'de> for CellRgb {
     }
 }
 
+/// Source for the RGB values of terminal colors.
+///
+/// This allows embedders to resolve colors from their own palette or theme, instead of the
+/// configured [`List`].
+pub trait ColorResolver {
+    /// RGB value of a color.
+    fn resolve(&self, color: ansi::Color) -> Rgb;
+}
+
 /// List of indexed colors.
 ///
 /// The first 16 entries are the standard ansi named colors. Items 16..232 are
@@ -282,6 +291,13 @@ impl List {
         self[ansi::NamedColor::Foreground] = colors.primary.foreground;
         self[ansi::NamedColor::Background] = colors.primary.background;
 
+        // Cursor, falling back to the foreground when it depends on the cell below it.
+        self[ansi::NamedColor::Cursor] = match colors.cursor.cursor() {
+            CellRgb::Rgb(rgb) => rgb,
+            CellRgb::CellForeground => colors.primary.foreground,
+            CellRgb::CellBackground => colors.primary.background,
+        };
+
         // Dims.
         self[ansi::NamedColor::DimForeground] =
             colors.primary.dim_foreground.unwrap_or(colors.primary.foreground * DIM_FACTOR);
@@ -362,6 +378,17 @@ impl List {
     }
 }
 
+impl ColorResolver for List {
+    #[inline]
+    fn resolve(&self, color: ansi::Color) -> Rgb {
+        match color {
+            ansi::Color::Spec(rgb) => rgb,
+            ansi::Color::Named(name) => self[name],
+            ansi::Color::Indexed(index) => self[index],
+        }
+    }
+}
+
 impl fmt::Debug for List {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("List[..]")
@@ -422,6 +449,45 @@ mod tests {
 
     use std::f64::EPSILON;
 
+    use crate::ansi::{Color, NamedColor};
+
+    /// Resolver which uses a different red than the configured palette.
+    struct CustomRed(List);
+
+    impl ColorResolver for CustomRed {
+        fn resolve(&self, color: Color) -> Rgb {
+            match color {
+                Color::Named(NamedColor::Red) => Rgb { r: 0xfa, g: 0x11, b: 0x11 },
+                color => self.0.resolve(color),
+            }
+        }
+    }
+
+    #[test]
+    fn resolve_list_colors() {
+        let list = List::from(&Colors::default());
+        let spec = Rgb { r: 1, g: 2, b: 3 };
+
+        assert_eq!(list.resolve(Color::Spec(spec)), spec);
+        assert_eq!(list.resolve(Color::Named(NamedColor::Foreground)), list[256usize]);
+        assert_eq!(list.resolve(Color::Named(NamedColor::Cursor)), list[258usize]);
+        assert_eq!(list.resolve(Color::Indexed(42)), list[42u8]);
+    }
+
+    #[test]
+    fn custom_resolver() {
+        let list = List::from(&Colors::default());
+        let resolver = CustomRed(list);
+
+        let red = resolver.resolve(Color::Named(NamedColor::Red));
+        assert_eq!(red, Rgb { r: 0xfa, g: 0x11, b: 0x11 });
+        assert_ne!(red, list[NamedColor::Red]);
+
+        // Other colors, including the indexed alias of red, still use the palette.
+        assert_eq!(resolver.resolve(Color::Indexed(1)), list[1u8]);
+        assert_eq!(resolver.resolve(Color::Named(NamedColor::Green)), list[NamedColor::Green]);
+    }
+
     #[test]
     fn contrast() {
         let rgb1 = Rgb { r: 0xff, g: 0xff, b: 0xff };
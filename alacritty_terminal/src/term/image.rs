@@ -0,0 +1,869 @@
+//! Images transmitted with the kitty graphics protocol.
+//!
+//! Graphics commands are sent as `ESC _ G <control data> ; <payload> ESC \`, with the control data
+//! being a comma separated list of `key=value` pairs and the payload containing base64 encoded
+//! image data. See <https://sw.kovidgoyal.net/kitty/graphics-protocol.html> for details.
+//!
+//! Uncompressed RGB, RGBA and PNG data can be transmitted directly, through a temporary file, or
+//! through a POSIX shared memory object on Unix. Images are placed at the cursor by attaching a
+//! [`GraphicCell`] to every cell they cover, z-index ordering, source rectangles and virtual
+//! placements are ignored.
+//!
+//! Sixel images are decoded by the [`sixel`](crate::term::sixel) module and placed the same way.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{self, Debug, Formatter};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::str;
+use std::sync::Arc;
+
+#[cfg(unix)]
+use nix::fcntl::OFlag;
+#[cfg(unix)]
+use nix::sys::mman;
+#[cfg(unix)]
+use nix::sys::stat::Mode;
+#[cfg(unix)]
+use std::os::unix::io::FromRawFd;
+
+/// Maximum number of images kept in the store.
+///
+/// Transmitting another image removes the least recently transmitted one.
+const MAX_IMAGES: usize = 256;
+
+/// Maximum size of a single image in bytes, once decoded to RGBA.
+pub(crate) const MAX_IMAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Maximum size of all stored images in bytes.
+///
+/// Like in kitty, storing an image beyond this limit removes older images.
+const MAX_STORE_SIZE: usize = 320 * 1024 * 1024;
+
+/// Maximum size of the base64 payload of a single image, across all of its chunks.
+const MAX_PAYLOAD_SIZE: usize = MAX_IMAGE_SIZE / 3 * 4;
+
+/// Text which must be part of the name of files used to transmit images.
+const FILE_MARKER: &str = "tty-graphics-protocol";
+
+/// Signature at the start of every PNG file.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Action requested by a graphics command (`a`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Store an image without displaying it.
+    Transmit,
+
+    /// Store an image and display it at the cursor.
+    TransmitAndDisplay,
+
+    /// Display a previously transmitted image at the cursor.
+    Put,
+
+    /// Remove image placements and data.
+    Delete,
+
+    /// Check if an image could be transmitted, without storing it.
+    Query,
+}
+
+/// Pixel format of the transmitted data (`f`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Rgb,
+    Rgba,
+    Png,
+}
+
+/// Parsed kitty graphics command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicsCommand {
+    pub action: Action,
+    pub format: Format,
+
+    /// Transmission medium (`t`), either direct (`d`), a file (`f`) or shared memory (`s`).
+    pub medium: u8,
+
+    /// Number of bytes read from a file or shared memory (`S`), zero to read everything.
+    pub size: u32,
+
+    /// Offset of the data in a file or shared memory (`O`).
+    pub offset: u32,
+
+    /// Compression of the payload (`o`).
+    pub compression: Option<u8>,
+
+    /// Image width in pixels (`s`), required for RGB and RGBA data.
+    pub width: u32,
+
+    /// Image height in pixels (`v`), required for RGB and RGBA data.
+    pub height: u32,
+
+    /// Client image ID (`i`), responses are only sent for images with an ID.
+    pub id: u32,
+
+    /// Whether more chunks of the payload follow (`m`).
+    pub more: bool,
+
+    /// Suppression of responses (`q`), `1` suppresses `OK` and `2` all responses.
+    pub quiet: u8,
+
+    /// Whether the cursor is moved after the image is displayed (`C`).
+    pub move_cursor: bool,
+
+    /// Placements which should be deleted (`d`).
+    pub delete: u8,
+
+    /// Base64 encoded image data.
+    pub payload: Vec<u8>,
+}
+
+impl Default for GraphicsCommand {
+    fn default() -> Self {
+        Self {
+            action: Action::Transmit,
+            format: Format::Rgba,
+            medium: b'd',
+            size: 0,
+            offset: 0,
+            compression: None,
+            width: 0,
+            height: 0,
+            id: 0,
+            more: false,
+            quiet: 0,
+            move_cursor: true,
+            delete: b'a',
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl GraphicsCommand {
+    /// Parse the content of an APC string, without the leading `G`.
+    ///
+    /// Unknown keys are ignored, `None` is returned if a known key has an invalid value.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut parts = bytes.splitn(2, |&b| b == b';');
+        let control = parts.next().unwrap_or_default();
+        let payload = parts.next().unwrap_or_default();
+
+        let mut command = Self { payload: payload.to_vec(), ..Self::default() };
+
+        for pair in control.split(|&b| b == b',').filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair {
+                [key, b'=', value @ ..] if !value.is_empty() => (*key, value),
+                _ => return None,
+            };
+
+            match key {
+                b'a' => {
+                    command.action = match value {
+                        b"t" => Action::Transmit,
+                        b"T" => Action::TransmitAndDisplay,
+                        b"p" => Action::Put,
+                        b"d" => Action::Delete,
+                        b"q" => Action::Query,
+                        _ => return None,
+                    }
+                },
+                b'f' => {
+                    command.format = match value {
+                        b"24" => Format::Rgb,
+                        b"32" => Format::Rgba,
+                        b"100" => Format::Png,
+                        _ => return None,
+                    }
+                },
+                b't' => command.medium = single_byte(value)?,
+                b'S' => command.size = number(value)?,
+                b'O' => command.offset = number(value)?,
+                b'o' => command.compression = Some(single_byte(value)?),
+                b'd' => command.delete = single_byte(value)?,
+                b's' => command.width = number(value)?,
+                b'v' => command.height = number(value)?,
+                b'i' => command.id = number(value)?,
+                b'm' => command.more = number(value)? == 1,
+                b'q' => command.quiet = number(value)? as u8,
+                b'C' => command.move_cursor = number(value)? == 0,
+                _ => (),
+            }
+        }
+
+        Some(command)
+    }
+}
+
+/// Error response to a graphics command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicsError {
+    /// POSIX error name, as used by kitty.
+    pub code: &'static str,
+
+    /// Human readable description.
+    pub message: &'static str,
+}
+
+impl GraphicsError {
+    pub fn new(code: &'static str, message: &'static str) -> Self {
+        Self { code, message }
+    }
+}
+
+impl fmt::Display for GraphicsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.message)
+    }
+}
+
+/// Pixel data of an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageData {
+    /// Uncompressed 8-bit RGBA pixels, row by row.
+    Rgba(Vec<u8>),
+
+    /// PNG file, decoding is left to the renderer.
+    Png(Vec<u8>),
+}
+
+/// Image stored in the terminal.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Image {
+    /// Client image ID, zero for images which can not be referenced by the client.
+    pub id: u32,
+
+    /// Number uniquely identifying this transmission during the terminal's lifetime.
+    pub serial: u64,
+
+    /// Width in pixels.
+    pub width: u32,
+
+    /// Height in pixels.
+    pub height: u32,
+
+    pub data: ImageData,
+}
+
+impl ImageData {
+    /// Size of the pixel data in bytes.
+    fn size(&self) -> usize {
+        match self {
+            ImageData::Rgba(data) | ImageData::Png(data) => data.len(),
+        }
+    }
+}
+
+impl Debug for Image {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let format = match self.data {
+            ImageData::Rgba(_) => "RGBA",
+            ImageData::Png(_) => "PNG",
+        };
+
+        f.debug_struct("Image")
+            .field("id", &self.id)
+            .field("serial", &self.serial)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("format", &format)
+            .finish()
+    }
+}
+
+/// Image decoded from a sixel DCS sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SixelImage {
+    /// Uncompressed 8-bit RGBA pixels, row by row.
+    pub data: Vec<u8>,
+
+    /// Width in sixel pixels.
+    pub width: u32,
+
+    /// Height in sixel pixels.
+    pub height: u32,
+
+    /// Horizontal size of a sixel pixel, relative to `pixel_height`.
+    pub pixel_width: u32,
+
+    /// Vertical size of a sixel pixel, relative to `pixel_width`.
+    pub pixel_height: u32,
+}
+
+/// Part of an image displayed in a single cell.
+#[derive(Debug, Clone)]
+pub struct GraphicCell {
+    pub image: Arc<Image>,
+
+    /// Column of this cell relative to the image's first column.
+    pub col: u32,
+
+    /// Line of this cell relative to the image's first line.
+    pub line: u32,
+}
+
+impl PartialEq for GraphicCell {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.image, &other.image) && self.col == other.col && self.line == other.line
+    }
+}
+
+impl Eq for GraphicCell {}
+
+/// Transmission which is still waiting for more chunks.
+#[derive(Debug)]
+struct PendingTransmission {
+    command: GraphicsCommand,
+
+    /// Whether the payload exceeded [`MAX_PAYLOAD_SIZE`] and has been discarded.
+    oversized: bool,
+}
+
+/// Images transmitted by the client, keyed by their ID.
+#[derive(Debug, Default)]
+pub struct ImageStore {
+    images: HashMap<u32, Arc<Image>>,
+    pending: Option<PendingTransmission>,
+    next_serial: u64,
+}
+
+impl ImageStore {
+    /// Add a chunk of a transmission.
+    ///
+    /// Once the last chunk has been received, the command of the first chunk is returned with
+    /// the payload of all chunks. Oversized transmissions are returned without their payload.
+    pub fn push_chunk(
+        &mut self,
+        command: GraphicsCommand,
+    ) -> Option<Result<GraphicsCommand, (GraphicsCommand, GraphicsError)>> {
+        let mut pending = match self.pending.take() {
+            Some(mut pending) => {
+                if !pending.oversized {
+                    pending.command.payload.extend_from_slice(&command.payload);
+                }
+                pending.command.more = command.more;
+                pending
+            },
+            None => PendingTransmission { command, oversized: false },
+        };
+
+        if pending.command.payload.len() > MAX_PAYLOAD_SIZE {
+            pending.command.payload = Vec::new();
+            pending.oversized = true;
+        }
+
+        if pending.command.more {
+            self.pending = Some(pending);
+            None
+        } else if pending.oversized {
+            Some(Err((pending.command, GraphicsError::new("EFBIG", "image too large"))))
+        } else {
+            Some(Ok(pending.command))
+        }
+    }
+
+    /// Decode the image of a transmission, without storing it.
+    pub fn decode(&mut self, command: &GraphicsCommand) -> Result<Arc<Image>, GraphicsError> {
+        if command.compression.is_some() {
+            return Err(GraphicsError::new("EINVAL", "unsupported compression"));
+        }
+
+        let payload = base64::decode(&command.payload)
+            .map_err(|_| GraphicsError::new("EINVAL", "invalid base64 payload"))?;
+
+        let bytes = match command.medium {
+            b'd' => payload,
+            b'f' => read_file(payload_path(&payload)?, command)?,
+            b's' => read_shared_memory(payload_path(&payload)?, command)?,
+            _ => return Err(GraphicsError::new("EINVAL", "unsupported transmission medium")),
+        };
+
+        let (width, height, data) = match command.format {
+            Format::Rgb | Format::Rgba => {
+                let (width, height) = (command.width, command.height);
+                let bytes_per_pixel = if command.format == Format::Rgb { 3 } else { 4 };
+                check_size(width, height)?;
+                if bytes.len() != width as usize * height as usize * bytes_per_pixel {
+                    return Err(GraphicsError::new("ENODATA", "payload does not match size"));
+                }
+
+                let rgba = if command.format == Format::Rgb {
+                    let mut rgba = Vec::with_capacity(bytes.len() / 3 * 4);
+                    for rgb in bytes.chunks_exact(3) {
+                        rgba.extend_from_slice(rgb);
+                        rgba.push(u8::MAX);
+                    }
+                    rgba
+                } else {
+                    bytes
+                };
+
+                (width, height, ImageData::Rgba(rgba))
+            },
+            Format::Png => {
+                let (width, height) = png_size(&bytes)
+                    .ok_or_else(|| GraphicsError::new("EBADPNG", "invalid PNG header"))?;
+                check_size(width, height)?;
+                (width, height, ImageData::Png(bytes))
+            },
+        };
+
+        self.next_serial += 1;
+
+        Ok(Arc::new(Image { id: command.id, serial: self.next_serial, width, height, data }))
+    }
+
+    /// Convert a sixel image to an image which can be displayed.
+    ///
+    /// Sixel images can not be referenced by the client, so they are never stored.
+    pub fn decode_sixel(&mut self, sixel: &SixelImage) -> Result<Arc<Image>, GraphicsError> {
+        let (scale_x, scale_y) = (sixel.pixel_width as usize, sixel.pixel_height as usize);
+        let width = sixel.width.saturating_mul(sixel.pixel_width);
+        let height = sixel.height.saturating_mul(sixel.pixel_height);
+        check_size(width, height)?;
+
+        let data = if scale_x == 1 && scale_y == 1 {
+            sixel.data.clone()
+        } else {
+            let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+            for row in sixel.data.chunks_exact(sixel.width as usize * 4) {
+                let start = data.len();
+                for pixel in row.chunks_exact(4) {
+                    for _ in 0..scale_x {
+                        data.extend_from_slice(pixel);
+                    }
+                }
+
+                let scaled_row = data[start..].to_vec();
+                for _ in 1..scale_y {
+                    data.extend_from_slice(&scaled_row);
+                }
+            }
+            data
+        };
+
+        self.next_serial += 1;
+
+        let data = ImageData::Rgba(data);
+        Ok(Arc::new(Image { id: 0, serial: self.next_serial, width, height, data }))
+    }
+
+    /// Decode and store the image of a transmission.
+    ///
+    /// Images without an ID are not stored, since they can not be referenced again.
+    pub fn transmit(&mut self, command: &GraphicsCommand) -> Result<Arc<Image>, GraphicsError> {
+        let image = self.decode(command)?;
+
+        if image.id != 0 {
+            self.images.remove(&image.id);
+            self.evict(image.data.size());
+            self.images.insert(image.id, image.clone());
+        }
+
+        Ok(image)
+    }
+
+    /// Remove stored images until an image of `size` bytes fits into the store.
+    ///
+    /// Images which are not displayed are removed first, starting with the least recently
+    /// transmitted one.
+    fn evict(&mut self, size: usize) {
+        let mut total: usize = self.images.values().map(|image| image.data.size()).sum();
+        while self.images.len() >= MAX_IMAGES || (total + size > MAX_STORE_SIZE && total > 0) {
+            // Every cell displaying an image holds another reference to it.
+            let oldest = self
+                .images
+                .values()
+                .min_by_key(|image| (Arc::strong_count(image) > 1, image.serial))
+                .map(|image| image.id);
+
+            match oldest.and_then(|id| self.images.remove(&id)) {
+                Some(image) => total -= image.data.size(),
+                None => break,
+            }
+        }
+    }
+
+    /// Get a stored image.
+    pub fn get(&self, id: u32) -> Option<&Arc<Image>> {
+        self.images.get(&id)
+    }
+
+    /// Remove a stored image.
+    pub fn remove(&mut self, id: u32) {
+        self.images.remove(&id);
+    }
+
+    /// Remove all stored images.
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.pending = None;
+    }
+}
+
+/// Path of a file or shared memory object transmitted in the payload.
+fn payload_path(payload: &[u8]) -> Result<&str, GraphicsError> {
+    str::from_utf8(payload).map_err(|_| GraphicsError::new("EINVAL", "invalid path"))
+}
+
+/// Read image data from a file.
+///
+/// Like in kitty, only regular files in the temporary directory with [`FILE_MARKER`] in their name
+/// are allowed, so applications can't read arbitrary files through the terminal. All failures are
+/// reported with the same error, which doesn't reveal whether a file exists.
+fn read_file(path: &str, command: &GraphicsCommand) -> Result<Vec<u8>, GraphicsError> {
+    let read_error = || GraphicsError::new("EBADF", "could not read file");
+
+    let path = fs::canonicalize(path).map_err(|_| read_error())?;
+    if !is_transmission_file(&path) {
+        return Err(read_error());
+    }
+
+    let mut file = File::open(&path).map_err(|_| read_error())?;
+    file.seek(SeekFrom::Start(u64::from(command.offset))).map_err(|_| read_error())?;
+
+    let limit = if command.size == 0 { MAX_IMAGE_SIZE + 1 } else { command.size as usize };
+    let mut bytes = Vec::new();
+    file.take(limit as u64).read_to_end(&mut bytes).map_err(|_| read_error())?;
+
+    if bytes.len() > MAX_IMAGE_SIZE {
+        return Err(GraphicsError::new("EFBIG", "image too large"));
+    }
+
+    Ok(bytes)
+}
+
+/// Check if a canonical path may be used to transmit an image.
+fn is_transmission_file(path: &Path) -> bool {
+    let in_temp_dir = match (path.parent(), fs::canonicalize(env::temp_dir())) {
+        (Some(parent), Ok(temp_dir)) => {
+            parent == temp_dir || (cfg!(unix) && parent == Path::new("/tmp"))
+        },
+        _ => false,
+    };
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+
+    in_temp_dir && name.contains(FILE_MARKER) && path.is_file()
+}
+
+/// Read image data from a POSIX shared memory object.
+///
+/// The object is removed once it has been opened, like the client expects. Like with files, only
+/// objects with [`FILE_MARKER`] in their name are allowed, so applications can't read or remove
+/// unrelated shared memory through the terminal.
+#[cfg(unix)]
+fn read_shared_memory(name: &str, command: &GraphicsCommand) -> Result<Vec<u8>, GraphicsError> {
+    let open_error = || GraphicsError::new("ENOENT", "could not open shared memory");
+    if !is_transmission_shared_memory(name) {
+        return Err(open_error());
+    }
+
+    let fd = mman::shm_open(name, OFlag::O_RDONLY, Mode::empty()).map_err(|_| open_error())?;
+    let _ = mman::shm_unlink(name);
+
+    // The object is read instead of mapped, so a client truncating it can't crash the terminal.
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    let read_error = |_| GraphicsError::new("EBADF", "could not read shared memory");
+    file.seek(SeekFrom::Start(u64::from(command.offset))).map_err(read_error)?;
+
+    let limit = if command.size == 0 { MAX_IMAGE_SIZE + 1 } else { command.size as usize };
+    let mut bytes = Vec::new();
+    file.take(limit as u64).read_to_end(&mut bytes).map_err(read_error)?;
+
+    if bytes.len() > MAX_IMAGE_SIZE {
+        return Err(GraphicsError::new("EFBIG", "image too large"));
+    }
+
+    Ok(bytes)
+}
+
+/// Check if the name of a shared memory object may be used to transmit an image.
+#[cfg(unix)]
+fn is_transmission_shared_memory(name: &str) -> bool {
+    let name = name.trim_start_matches('/');
+    name.contains(FILE_MARKER) && !name.contains('/')
+}
+
+#[cfg(not(unix))]
+fn read_shared_memory(_name: &str, _command: &GraphicsCommand) -> Result<Vec<u8>, GraphicsError> {
+    Err(GraphicsError::new("EINVAL", "unsupported transmission medium"))
+}
+
+/// Check that an image's dimensions are valid.
+fn check_size(width: u32, height: u32) -> Result<(), GraphicsError> {
+    if width == 0 || height == 0 {
+        Err(GraphicsError::new("EINVAL", "missing image dimensions"))
+    } else if width as usize * height as usize * 4 > MAX_IMAGE_SIZE {
+        Err(GraphicsError::new("EFBIG", "image too large"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Read the dimensions from the IHDR chunk of a PNG file.
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    if png.len() < 24 || !png.starts_with(PNG_SIGNATURE) || &png[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+
+    Some((width, height))
+}
+
+/// Value of a key which is a single character.
+fn single_byte(value: &[u8]) -> Option<u8> {
+    match value {
+        [byte] => Some(*byte),
+        _ => None,
+    }
+}
+
+/// Value of a key which is an unsigned number.
+fn number(value: &[u8]) -> Option<u32> {
+    str::from_utf8(value).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    use std::io::Write;
+    use std::{env, process};
+
+    /// Header of a 3x2 PNG file, the image data itself is never decoded by the terminal.
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02\x08\x06\0\0\0";
+
+    fn command(control: &str, payload: &[u8]) -> GraphicsCommand {
+        let escape = format!("{};{}", control, base64::encode(payload));
+        GraphicsCommand::parse(escape.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse_command() {
+        let command = GraphicsCommand::parse(b"a=T,f=24,s=10,v=20,i=7,q=1,C=1,X=3;AAAA").unwrap();
+
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.format, Format::Rgb);
+        assert_eq!((command.width, command.height), (10, 20));
+        assert_eq!(command.id, 7);
+        assert_eq!(command.quiet, 1);
+        assert!(!command.move_cursor);
+        assert_eq!(command.payload, b"AAAA");
+
+        assert_eq!(GraphicsCommand::parse(b"a=x"), None);
+        assert_eq!(GraphicsCommand::parse(b"s=a"), None);
+        assert_eq!(GraphicsCommand::parse(b"a"), None);
+    }
+
+    #[test]
+    fn decode_rgb() {
+        let mut store = ImageStore::default();
+        let image = store.decode(&command("f=24,s=2,v=1", &[1, 2, 3, 4, 5, 6])).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.data, ImageData::Rgba(vec![1, 2, 3, 255, 4, 5, 6, 255]));
+
+        let error = store.decode(&command("f=24,s=2,v=2", &[1, 2, 3, 4, 5, 6])).unwrap_err();
+        assert_eq!(error.code, "ENODATA");
+    }
+
+    #[test]
+    fn decode_png() {
+        let mut store = ImageStore::default();
+        let image = store.decode(&command("f=100", PNG)).unwrap();
+
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.data, ImageData::Png(PNG.to_vec()));
+
+        let error = store.decode(&command("f=100", b"GIF89a")).unwrap_err();
+        assert_eq!(error.code, "EBADPNG");
+    }
+
+    #[test]
+    fn chunked_transmission() {
+        let mut store = ImageStore::default();
+
+        let first = GraphicsCommand::parse(b"a=t,f=32,s=1,v=1,i=3,m=1;AQID").unwrap();
+        assert_eq!(store.push_chunk(first), None);
+
+        let last = GraphicsCommand::parse(b"m=0;BA==").unwrap();
+        let command = store.push_chunk(last).unwrap().unwrap();
+        assert_eq!(command.id, 3);
+        assert!(!command.more);
+
+        let image = store.transmit(&command).unwrap();
+        assert_eq!(image.data, ImageData::Rgba(vec![1, 2, 3, 4]));
+        assert_eq!(store.get(3), Some(&image));
+    }
+
+    #[test]
+    fn store_size_limit() {
+        // Store an image of the maximum size, without any references from the grid.
+        fn store_large(store: &mut ImageStore, id: u32) {
+            let data = ImageData::Rgba(vec![0; MAX_IMAGE_SIZE]);
+            let image = Image { id, serial: u64::from(id), width: 4096, height: 4096, data };
+            store.images.insert(id, Arc::new(image));
+            store.next_serial = u64::from(id);
+        }
+
+        let mut store = ImageStore::default();
+        for id in 1..=5 {
+            store_large(&mut store, id);
+        }
+
+        // The oldest image is displayed, so the oldest image which is not displayed is removed.
+        let displayed = store.get(1).cloned();
+        store.transmit(&command("f=32,s=1,v=1,i=6", &[0; 4])).unwrap();
+        assert!(store.get(1).is_some());
+        assert!(store.get(2).is_none());
+        assert!(store.get(6).is_some());
+
+        // Once it is no longer displayed, the oldest image is removed first.
+        drop(displayed);
+        store_large(&mut store, 7);
+        store.transmit(&command("f=32,s=1,v=1,i=8", &[0; 4])).unwrap();
+        assert!(store.get(1).is_none());
+        assert!(store.get(3).is_some());
+        assert!(store.get(8).is_some());
+    }
+
+    #[test]
+    fn store_without_id() {
+        let mut store = ImageStore::default();
+        let image = store.transmit(&command("f=32,s=1,v=1", &[0; 4])).unwrap();
+
+        assert_eq!(image.id, 0);
+        assert_eq!(store.get(0), None);
+    }
+
+    #[test]
+    fn unsupported_transmission() {
+        let mut store = ImageStore::default();
+
+        let error = store.decode(&command("t=t,f=100", b"/tmp/image.png")).unwrap_err();
+        assert_eq!(error.code, "EINVAL");
+
+        let error = store.decode(&command("o=z,f=100", PNG)).unwrap_err();
+        assert_eq!(error.code, "EINVAL");
+    }
+
+    #[test]
+    fn decode_sixel() {
+        let mut store = ImageStore::default();
+        let sixel = SixelImage {
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+            width: 2,
+            height: 1,
+            pixel_width: 1,
+            pixel_height: 2,
+        };
+
+        let image = store.decode_sixel(&sixel).unwrap();
+        assert_eq!((image.id, image.width, image.height), (0, 2, 2));
+        assert_eq!(image.data, ImageData::Rgba([1, 2, 3, 4, 5, 6, 7, 8].repeat(2)));
+
+        let sixel = SixelImage { pixel_width: 2, pixel_height: 1, ..sixel };
+        let image = store.decode_sixel(&sixel).unwrap();
+        assert_eq!((image.width, image.height), (4, 1));
+        assert_eq!(
+            image.data,
+            ImageData::Rgba(vec![1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8, 5, 6, 7, 8])
+        );
+    }
+
+    #[test]
+    fn store_png() {
+        let mut store = ImageStore::default();
+        let image = store.transmit(&command("f=100,i=5", PNG)).unwrap();
+
+        assert_eq!(store.get(5), Some(&image));
+        assert_eq!((image.width, image.height), (3, 2));
+    }
+
+    #[test]
+    fn transmit_file() {
+        let name = format!("alacritty-{}-{}.png", FILE_MARKER, process::id());
+        let path = env::temp_dir().join(name);
+        fs::write(&path, [b"padding", PNG].concat()).unwrap();
+        let path = path.to_str().unwrap().as_bytes();
+
+        let mut store = ImageStore::default();
+        let image = store.decode(&command("t=f,f=100,O=7", path)).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(image.data, ImageData::Png(PNG.to_vec()));
+
+        // The size limits the data which is read.
+        let error = store.decode(&command("t=f,f=100,O=7,S=20", path)).unwrap_err();
+        assert_eq!(error.code, "EBADPNG");
+
+        fs::remove_file(str::from_utf8(path).unwrap()).unwrap();
+        let error = store.decode(&command("t=f,f=100", path)).unwrap_err();
+        assert_eq!(error.code, "EBADF");
+    }
+
+    #[test]
+    fn transmit_file_rejects_other_files() {
+        let path = env::temp_dir().join(format!("alacritty-image-test-{}.png", process::id()));
+        fs::write(&path, PNG).unwrap();
+        let directory = env::temp_dir().join(format!("{}-{}", FILE_MARKER, process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let mut store = ImageStore::default();
+        let mut decode = |path: &Path| {
+            let path = path.to_str().unwrap().as_bytes();
+            store.decode(&command("t=f,f=100", path)).unwrap_err().code
+        };
+
+        // Files without the marker, directories and files outside the temporary directory can't
+        // be told apart from missing files.
+        assert_eq!(decode(&path), "EBADF");
+        assert_eq!(decode(&directory), "EBADF");
+        assert_eq!(decode(&env::current_dir().unwrap().join("Cargo.toml")), "EBADF");
+
+        fs::remove_file(path).unwrap();
+        fs::remove_dir(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn transmit_shared_memory() {
+        let name = format!("/alacritty-{}-{}", FILE_MARKER, process::id());
+        let flags = OFlag::O_CREAT | OFlag::O_EXCL | OFlag::O_RDWR;
+        let fd = mman::shm_open(name.as_str(), flags, Mode::S_IRUSR | Mode::S_IWUSR).unwrap();
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.write_all(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+
+        let mut store = ImageStore::default();
+        let image = store.decode(&command("t=s,f=32,s=1,v=1,O=4", name.as_bytes())).unwrap();
+        assert_eq!(image.data, ImageData::Rgba(vec![5, 6, 7, 8]));
+
+        // The shared memory object is removed after reading it.
+        let error = store.decode(&command("t=s,f=32,s=1,v=1", name.as_bytes())).unwrap_err();
+        assert_eq!(error.code, "ENOENT");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn transmit_shared_memory_rejects_other_objects() {
+        let name = format!("/alacritty-image-test-{}", process::id());
+        let flags = OFlag::O_CREAT | OFlag::O_EXCL | OFlag::O_RDWR;
+        let fd = mman::shm_open(name.as_str(), flags, Mode::S_IRUSR | Mode::S_IWUSR).unwrap();
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.write_all(&[1, 2, 3, 4]).unwrap();
+
+        let mut store = ImageStore::default();
+        let mut decode = |name: &str| {
+            store.decode(&command("t=s,f=32,s=1,v=1", name.as_bytes())).unwrap_err().code
+        };
+
+        // Objects without the marker are neither read nor removed.
+        assert_eq!(decode(&name), "ENOENT");
+        assert_eq!(decode(&format!("/{}/../{}", FILE_MARKER, &name[1..])), "ENOENT");
+        mman::shm_unlink(name.as_str()).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! Descriptions of the contents and attributes stored in a cell.
+//!
+//! This allows inspecting exactly what an application wrote to the terminal, which is useful for
+//! debugging color schemes, since colors are reported both as written and as rendered.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::ansi::Color;
+use crate::index::Point;
+use crate::term::cell::{Flags, UnderlineStyle};
+use crate::term::color::{ColorResolver, Rgb};
+use crate::term::Term;
+
+/// Color of a cell, both as it is stored and as it is resolved using the current palette.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColorDescription {
+    /// Color as written by the application.
+    pub color: Color,
+
+    /// RGB value of the color.
+    pub rgb: Rgb,
+}
+
+impl Display for ColorDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.color {
+            Color::Named(name) => write!(f, "{:?}", name)?,
+            Color::Indexed(index) => write!(f, "Indexed({})", index)?,
+            Color::Spec(_) => write!(f, "Spec")?,
+        }
+
+        write!(f, " #{:02x}{:02x}{:02x}", self.rgb.r, self.rgb.g, self.rgb.b)
+    }
+}
+
+/// Contents and attributes of a single cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellDescription {
+    /// Character of the cell, followed by its zerowidth characters.
+    pub chars: Vec<char>,
+
+    /// Foreground color.
+    pub fg: ColorDescription,
+
+    /// Background color.
+    pub bg: ColorDescription,
+
+    /// Underline color, `None` if the underline uses the foreground color.
+    pub underline_color: Option<ColorDescription>,
+
+    /// Style of the underline.
+    pub underline_style: UnderlineStyle,
+
+    /// All flags stored in the cell.
+    pub flags: Flags,
+
+    /// URI of the `OSC 8` hyperlink the cell is part of.
+    pub hyperlink: Option<String>,
+}
+
+impl Display for CellDescription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let text: String = self.chars.iter().collect();
+        write!(f, "\"{}\"", text)?;
+        for c in &self.chars {
+            write!(f, " U+{:04X}", *c as u32)?;
+        }
+
+        write!(f, ", fg {}, bg {}, flags {:?}", self.fg, self.bg, self.flags)?;
+
+        if self.underline_style != UnderlineStyle::None {
+            write!(f, ", underline {:?}", self.underline_style)?;
+            if let Some(color) = self.underline_color {
+                write!(f, " {}", color)?;
+            }
+        }
+
+        if let Some(hyperlink) = &self.hyperlink {
+            write!(f, ", link {}", hyperlink)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Term<T> {
+    /// Describe the cell at a point in the buffer.
+    pub fn describe_cell(&self, point: Point<usize>) -> CellDescription {
+        let cell = &self.grid[point.line][point.col];
+        let describe_color = |color| ColorDescription { color, rgb: self.colors.resolve(color) };
+
+        let mut chars = vec![cell.c];
+        chars.extend(cell.zerowidth().into_iter().flatten());
+
+        CellDescription {
+            chars,
+            fg: describe_color(cell.fg),
+            bg: describe_color(cell.bg),
+            underline_color: cell.underline_color().map(describe_color),
+            underline_style: cell.underline_style(),
+            flags: cell.flags,
+            hyperlink: cell.hyperlink().map(|hyperlink| hyperlink.uri.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use crate::ansi::{NamedColor, Processor};
+    use crate::config::MockConfig;
+    use crate::event::VoidListener;
+    use crate::index::{Column, Line};
+    use crate::term::SizeInfo;
+
+    fn term_with_output(output: &str) -> Term<VoidListener> {
+        let size = SizeInfo::new(30.0, 9.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, VoidListener);
+        let mut parser = Processor::new();
+        for byte in output.bytes() {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+        term
+    }
+
+    fn describe(term: &Term<VoidListener>, col: usize) -> CellDescription {
+        term.describe_cell(term.visible_to_buffer(Point::new(Line(0), Column(col))))
+    }
+
+    #[test]
+    fn describe_colors() {
+        let term = term_with_output("a\x1b[31;48;5;42mb\x1b[38;2;1;2;3mc");
+
+        let default = describe(&term, 0);
+        assert_eq!(default.chars, vec!['a']);
+        assert_eq!(default.fg.color, Color::Named(NamedColor::Foreground));
+        assert_eq!(default.fg.rgb, term.colors[NamedColor::Foreground]);
+        assert_eq!(default.bg.color, Color::Named(NamedColor::Background));
+        assert_eq!(default.flags, Flags::empty());
+
+        let indexed = describe(&term, 1);
+        assert_eq!(indexed.fg.color, Color::Named(NamedColor::Red));
+        assert_eq!(indexed.fg.rgb, term.colors[NamedColor::Red]);
+        assert_eq!(indexed.bg.color, Color::Indexed(42));
+        assert_eq!(indexed.bg.rgb, term.colors[42usize]);
+
+        let rgb = Rgb { r: 1, g: 2, b: 3 };
+        let spec = describe(&term, 2);
+        assert_eq!(spec.fg, ColorDescription { color: Color::Spec(rgb), rgb });
+        assert_eq!(
+            spec.to_string(),
+            format!("\"c\" U+0063, fg Spec #010203, bg {}, flags (empty)", spec.bg)
+        );
+    }
+
+    #[test]
+    fn describe_flags_and_underline() {
+        let term = term_with_output("\x1b[1;3;7;9ma\x1b[0;4:3;58:5:9mb");
+
+        let styled = describe(&term, 0);
+        assert_eq!(styled.flags, Flags::BOLD | Flags::ITALIC | Flags::INVERSE | Flags::STRIKEOUT);
+        assert_eq!(styled.underline_style, UnderlineStyle::None);
+        assert_eq!(styled.underline_color, None);
+
+        let underlined = describe(&term, 1);
+        assert_eq!(underlined.flags, Flags::UNDERLINE);
+        assert_eq!(underlined.underline_style, UnderlineStyle::Curly);
+        let color = underlined.underline_color.unwrap();
+        assert_eq!(color.color, Color::Indexed(9));
+        assert!(underlined.to_string().ends_with(&format!("underline Curly {}", color)));
+    }
+
+    #[test]
+    fn describe_text_and_hyperlink() {
+        let term = term_with_output("\x1b]8;;https://example.com\x1b\\e\u{301}\x1b]8;;\x1b\\");
+
+        let description = describe(&term, 0);
+        assert_eq!(description.chars, vec!['e', '\u{301}']);
+        assert_eq!(description.hyperlink.as_deref(), Some("https://example.com"));
+        assert!(description.to_string().starts_with("\"e\u{301}\" U+0065 U+0301, fg Foreground"));
+        assert!(description.to_string().ends_with(", link https://example.com"));
+
+        assert_eq!(describe(&term, 1).hyperlink, None);
+    }
+}
@@ -1,42 +1,77 @@
 //! Exports the `Term` type which is a high-level API for the Grid.
 
 use std::cmp::{max, min};
+use std::fmt::Write as _;
 use std::iter::Peekable;
 use std::ops::{Index, IndexMut, Range, RangeInclusive};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{io, iter, mem, ptr, str};
 
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use serde::{Deserialize, Serialize};
 use unicode_width::UnicodeWidthChar;
 
 use crate::ansi::{
-    self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, NamedColor, StandardCharset,
+    self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, KeyboardModeBehavior,
+    KittyKeyboardFlags, ModifyOtherKeys, NamedColor, StandardCharset, WorkingDirectory,
 };
-use crate::config::{BellAnimation, BellConfig, Config};
+use crate::config::{BellAnimation, BellConfig, Config, LineSelection, Osc52};
 use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, DisplayIter, Grid, IndexRegion, Indexed, Scroll};
+use crate::grid::{Dimensions, DisplayIter, Grid, GridCell, IndexRegion, Indexed, Row, Scroll};
 use crate::index::{self, Boundary, Column, Direction, IndexRange, Line, Point, Side};
 use crate::selection::{Selection, SelectionRange};
-use crate::term::cell::{Cell, Flags, LineLength};
-use crate::term::color::{CellRgb, Rgb, DIM_FACTOR};
+use crate::term::cell::{
+    Cell, Flags, Hyperlink, HyperlinkInterner, LineLength, UnderlineStyle, MAX_ZEROWIDTH_CHARS,
+};
+use crate::term::color::{CellRgb, ColorResolver, Rgb, DIM_FACTOR};
+use crate::term::image::{
+    Action, GraphicCell, GraphicsCommand, GraphicsError, Image, ImageStore, SixelImage,
+};
 use crate::term::search::{RegexIter, RegexSearch};
+use crate::term::semantic::SemanticZone;
 use crate::vi_mode::{ViModeCursor, ViMotion};
 
+#[cfg(feature = "bidi")]
+pub mod bidi;
 pub mod cell;
 pub mod color;
-mod search;
+pub mod image;
+pub mod inspect;
+pub mod persist;
+pub mod search;
+pub mod semantic;
+pub mod sixel;
+mod terminfo;
 
 /// Max size of the window title stack.
 const TITLE_STACK_MAX_DEPTH: usize = 4096;
 
+/// Max size of the kitty keyboard protocol flag stack.
+const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = 5;
+
+/// Terminal modes corresponding to the kitty keyboard protocol flags.
+const KEYBOARD_MODES: [(KittyKeyboardFlags, TermMode); 5] = [
+    (KittyKeyboardFlags::DISAMBIGUATE_ESC_CODES, TermMode::DISAMBIGUATE_ESC_CODES),
+    (KittyKeyboardFlags::REPORT_EVENT_TYPES, TermMode::REPORT_EVENT_TYPES),
+    (KittyKeyboardFlags::REPORT_ALTERNATE_KEYS, TermMode::REPORT_ALTERNATE_KEYS),
+    (KittyKeyboardFlags::REPORT_ALL_KEYS_AS_ESC, TermMode::REPORT_ALL_KEYS_AS_ESC),
+    (KittyKeyboardFlags::REPORT_ASSOCIATED_TEXT, TermMode::REPORT_ASSOCIATED_TEXT),
+];
+
 /// Minimum contrast between a fixed cursor color and the cell's background.
 const MIN_CURSOR_CONTRAST: f64 = 1.5;
 
 /// Maximum number of linewraps followed outside of the viewport during search highlighting.
 const MAX_SEARCH_LINES: usize = 100;
 
+/// Maximum duration of a synchronized update, before rendering is resumed automatically.
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Interval at which the terminal is drawn while ConPTY repaints it after resizes.
+#[cfg(windows)]
+const RESIZE_SYNC_TIMEOUT: Duration = Duration::from_millis(50);
+
 /// Default tab interval, corresponding to terminfo `it` value.
 const INITIAL_TABSTOPS: usize = 8;
 
@@ -75,6 +110,14 @@ pub struct RenderableSearch<'a> {
 impl<'a> RenderableSearch<'a> {
     /// Create a new renderable search iterator.
     fn new<T>(term: &'a Term<T>) -> Self {
+        let regex = match &term.regex_search {
+            Some(regex) => regex,
+            None => {
+                let iter: MatchIter<'a> = Box::new(iter::empty());
+                return Self { iter: iter.peekable() };
+            },
+        };
+
         let viewport_end = term.grid().display_offset();
         let viewport_start = viewport_end + term.screen_lines().0 - 1;
 
@@ -99,7 +142,7 @@ impl<'a> RenderableSearch<'a> {
 
         // Create an iterater for the current regex search for all visible matches.
         let iter: MatchIter<'a> = Box::new(
-            RegexIter::new(start, end, Direction::Right, &term)
+            RegexIter::new(start, end, Direction::Right, term, regex)
                 .skip_while(move |rm| rm.end().line > viewport_start)
                 .take_while(move |rm| rm.start().line >= viewport_end),
         );
@@ -140,6 +183,7 @@ pub struct RenderableCellsIter<'a, C> {
     colors: &'a color::List,
     selection: Option<SelectionRange<Line>>,
     search: RenderableSearch<'a>,
+    reverse_video: bool,
 }
 
 impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
@@ -191,6 +235,7 @@ impl<'a, C> RenderableCellsIter<'a, C> {
             config,
             colors: &term.colors,
             search: RenderableSearch::new(term),
+            reverse_video: term.mode.contains(TermMode::REVERSE_VIDEO),
         }
     }
 
@@ -290,6 +335,7 @@ impl<'a, C> RenderableCellsIter<'a, C> {
 pub enum RenderableCellContent {
     Chars((char, Option<Vec<char>>)),
     Cursor(CursorKey),
+    Graphic(GraphicCell),
 }
 
 #[derive(Clone, Debug)]
@@ -301,8 +347,12 @@ pub struct RenderableCell {
     pub fg: Rgb,
     pub bg: Rgb,
     pub bg_alpha: f32,
+    pub underline: Rgb,
+    pub underline_style: UnderlineStyle,
     pub flags: Flags,
     pub is_match: bool,
+    pub hyperlink: Option<Arc<Hyperlink>>,
+    pub allows_subpixel: bool,
 }
 
 impl RenderableCell {
@@ -313,16 +363,21 @@ impl RenderableCell {
         let mut fg_rgb = Self::compute_fg_rgb(iter.config, iter.colors, cell.fg, cell.flags);
         let mut bg_rgb = Self::compute_bg_rgb(iter.colors, cell.bg);
 
-        let mut bg_alpha = if cell.flags.contains(Flags::INVERSE) {
+        // Reverse video inverts all cells, so it cancels out with the cell's own inversion.
+        //
+        // Since the window is cleared with the foreground color in reverse video, every cell
+        // needs to draw its background.
+        let inverse = cell.flags.contains(Flags::INVERSE) != iter.reverse_video;
+        if inverse {
             mem::swap(&mut fg_rgb, &mut bg_rgb);
-            1.0
-        } else {
-            Self::compute_bg_alpha(cell.bg)
-        };
+        }
+        let mut bg_alpha =
+            if inverse || iter.reverse_video { 1.0 } else { Self::compute_bg_alpha(cell.bg) };
 
         let mut is_match = false;
 
-        if iter.is_selected(point) {
+        let is_selected = iter.is_selected(point);
+        if is_selected {
             let config_bg = iter.config.colors.selection.background();
             let selected_fg = iter.config.colors.selection.foreground().color(fg_rgb, bg_rgb);
             bg_rgb = config_bg.color(fg_rgb, bg_rgb);
@@ -350,23 +405,41 @@ impl RenderableCell {
             is_match = true;
         }
 
-        let zerowidth = cell.zerowidth().map(|zerowidth| zerowidth.to_vec());
+        // Highlighted cells always draw their underline using the highlight's foreground.
+        let underline = match cell.underline_color() {
+            Some(color) if !is_selected && !is_match => {
+                Self::compute_fg_rgb(iter.config, iter.colors, color, cell.flags)
+            },
+            _ => fg_rgb,
+        };
+
+        let inner = match cell.graphic() {
+            Some(graphic) => RenderableCellContent::Graphic(graphic.clone()),
+            None => {
+                let zerowidth = cell.zerowidth().map(|zerowidth| zerowidth.to_vec());
+                RenderableCellContent::Chars((cell.c, zerowidth))
+            },
+        };
 
         RenderableCell {
             line: cell.line,
             column: cell.column,
-            inner: RenderableCellContent::Chars((cell.c, zerowidth)),
+            inner,
             fg: fg_rgb,
             bg: bg_rgb,
             bg_alpha,
+            underline,
+            underline_style: cell.underline_style(),
             flags: cell.flags,
             is_match,
+            hyperlink: cell.hyperlink().cloned(),
+            allows_subpixel: cell.allows_subpixel(),
         }
     }
 
     fn is_empty(&self) -> bool {
         self.bg_alpha == 0.
-            && !self.flags.intersects(Flags::UNDERLINE | Flags::STRIKEOUT | Flags::DOUBLE_UNDERLINE)
+            && !self.flags.intersects(Flags::UNDERLINE | Flags::STRIKEOUT | Flags::OVERLINE)
             && self.inner == RenderableCellContent::Chars((' ', None))
     }
 
@@ -425,12 +498,8 @@ impl RenderableCell {
     }
 
     #[inline]
-    fn compute_bg_rgb(colors: &color::List, bg: Color) -> Rgb {
-        match bg {
-            Color::Spec(rgb) => rgb,
-            Color::Named(ansi) => colors[ansi],
-            Color::Indexed(idx) => colors[idx],
-        }
+    fn compute_bg_rgb<R: ColorResolver>(colors: &R, bg: Color) -> Rgb {
+        colors.resolve(bg)
     }
 }
 
@@ -439,27 +508,37 @@ pub mod mode {
 
     bitflags! {
         pub struct TermMode: u32 {
-            const NONE                = 0;
-            const SHOW_CURSOR         = 0b0000_0000_0000_0000_0001;
-            const APP_CURSOR          = 0b0000_0000_0000_0000_0010;
-            const APP_KEYPAD          = 0b0000_0000_0000_0000_0100;
-            const MOUSE_REPORT_CLICK  = 0b0000_0000_0000_0000_1000;
-            const BRACKETED_PASTE     = 0b0000_0000_0000_0001_0000;
-            const SGR_MOUSE           = 0b0000_0000_0000_0010_0000;
-            const MOUSE_MOTION        = 0b0000_0000_0000_0100_0000;
-            const LINE_WRAP           = 0b0000_0000_0000_1000_0000;
-            const LINE_FEED_NEW_LINE  = 0b0000_0000_0001_0000_0000;
-            const ORIGIN              = 0b0000_0000_0010_0000_0000;
-            const INSERT              = 0b0000_0000_0100_0000_0000;
-            const FOCUS_IN_OUT        = 0b0000_0000_1000_0000_0000;
-            const ALT_SCREEN          = 0b0000_0001_0000_0000_0000;
-            const MOUSE_DRAG          = 0b0000_0010_0000_0000_0000;
-            const MOUSE_MODE          = 0b0000_0010_0000_0100_1000;
-            const UTF8_MOUSE          = 0b0000_0100_0000_0000_0000;
-            const ALTERNATE_SCROLL    = 0b0000_1000_0000_0000_0000;
-            const VI                  = 0b0001_0000_0000_0000_0000;
-            const URGENCY_HINTS       = 0b0010_0000_0000_0000_0000;
-            const ANY                 = std::u32::MAX;
+            const NONE                    = 0;
+            const SHOW_CURSOR             = 0b0000_0000_0000_0000_0000_0000_0001;
+            const APP_CURSOR              = 0b0000_0000_0000_0000_0000_0000_0010;
+            const APP_KEYPAD              = 0b0000_0000_0000_0000_0000_0000_0100;
+            const MOUSE_REPORT_CLICK      = 0b0000_0000_0000_0000_0000_0000_1000;
+            const BRACKETED_PASTE         = 0b0000_0000_0000_0000_0000_0001_0000;
+            const SGR_MOUSE               = 0b0000_0000_0000_0000_0000_0010_0000;
+            const MOUSE_MOTION            = 0b0000_0000_0000_0000_0000_0100_0000;
+            const LINE_WRAP               = 0b0000_0000_0000_0000_0000_1000_0000;
+            const LINE_FEED_NEW_LINE      = 0b0000_0000_0000_0000_0001_0000_0000;
+            const ORIGIN                  = 0b0000_0000_0000_0000_0010_0000_0000;
+            const INSERT                  = 0b0000_0000_0000_0000_0100_0000_0000;
+            const FOCUS_IN_OUT            = 0b0000_0000_0000_0000_1000_0000_0000;
+            const ALT_SCREEN              = 0b0000_0000_0000_0001_0000_0000_0000;
+            const MOUSE_DRAG              = 0b0000_0000_0000_0010_0000_0000_0000;
+            const MOUSE_MODE              = 0b0000_0000_0000_0010_0000_0100_1000;
+            const UTF8_MOUSE              = 0b0000_0000_0000_0100_0000_0000_0000;
+            const ALTERNATE_SCROLL        = 0b0000_0000_0000_1000_0000_0000_0000;
+            const VI                      = 0b0000_0000_0001_0000_0000_0000_0000;
+            const URGENCY_HINTS           = 0b0000_0000_0010_0000_0000_0000_0000;
+            const REVERSE_VIDEO           = 0b0000_0000_0100_0000_0000_0000_0000;
+            const DISAMBIGUATE_ESC_CODES  = 0b0000_0000_1000_0000_0000_0000_0000;
+            const REPORT_EVENT_TYPES      = 0b0000_0001_0000_0000_0000_0000_0000;
+            const REPORT_ALTERNATE_KEYS   = 0b0000_0010_0000_0000_0000_0000_0000;
+            const REPORT_ALL_KEYS_AS_ESC  = 0b0000_0100_0000_0000_0000_0000_0000;
+            const REPORT_ASSOCIATED_TEXT  = 0b0000_1000_0000_0000_0000_0000_0000;
+            const KITTY_KEYBOARD_PROTOCOL = 0b0000_1111_1000_0000_0000_0000_0000;
+            const MODIFY_OTHER_KEYS       = 0b0001_0000_0000_0000_0000_0000_0000;
+            const MODIFY_ALL_KEYS         = 0b0010_0000_0000_0000_0000_0000_0000;
+            const WIN32_INPUT             = 0b0100_0000_0000_0000_0000_0000_0000;
+            const ANY                     = std::u32::MAX;
         }
     }
 
@@ -657,9 +736,19 @@ impl SizeInfo {
         }
     }
 
+    /// Grow the grid to at least the specified dimensions.
+    ///
+    /// The grid can exceed the window size as a result, in which case the excess is clipped
+    /// while rendering.
+    #[inline]
+    pub fn set_minimum_dimensions(&mut self, cols: Column, screen_lines: Line) {
+        self.cols = max(self.cols, cols);
+        self.screen_lines = max(self.screen_lines, screen_lines);
+    }
+
     #[inline]
     pub fn reserve_lines(&mut self, count: usize) {
-        self.screen_lines = Line(max(self.screen_lines.saturating_sub(count), MIN_SCREEN_LINES));
+        self.screen_lines = max(self.screen_lines.saturating_sub(count), Line(MIN_SCREEN_LINES));
     }
 
     /// Check if coordinates are inside the terminal grid.
@@ -682,8 +771,8 @@ impl SizeInfo {
         let line = Line(y.saturating_sub(self.padding_y as usize) / (self.cell_height as usize));
 
         Point {
-            line: min(line, Line(self.screen_lines.saturating_sub(1))),
-            col: min(col, Column(self.cols.saturating_sub(1))),
+            line: min(line, self.screen_lines.saturating_sub(1)),
+            col: min(col, self.cols.saturating_sub(1)),
         }
     }
 
@@ -744,6 +833,15 @@ pub struct Term<T> {
     /// Terminal focus controlling the cursor shape.
     pub is_focused: bool,
 
+    /// Whether a blinking cursor is currently in its visible phase.
+    cursor_blink: bool,
+
+    /// Start of the current cursor blinking cycle.
+    cursor_blink_start: Instant,
+
+    /// Duration of each phase of the cursor blinking cycle.
+    cursor_blink_interval: Duration,
+
     /// Cursor for keyboard selection.
     pub vi_mode_cursor: ViModeCursor,
 
@@ -777,6 +875,15 @@ pub struct Term<T> {
 
     semantic_escape_chars: String,
 
+    /// Extent of line selections.
+    line_selection: LineSelection,
+
+    /// Clipboard access through `OSC 52`.
+    osc52: Osc52,
+
+    /// Maximum size of the data written to the clipboard through `OSC 52`.
+    osc52_max_size: usize,
+
     /// Colors used for rendering.
     colors: color::List,
 
@@ -805,12 +912,45 @@ pub struct Term<T> {
     /// term is set.
     title_stack: Vec<Option<String>>,
 
+    /// Stack of saved kitty keyboard protocol flags, the current flags are part of `mode`.
+    keyboard_mode_stack: Vec<KittyKeyboardFlags>,
+
+    /// Kitty keyboard protocol flags and stack of the inactive screen.
+    inactive_keyboard_mode: KittyKeyboardFlags,
+    inactive_keyboard_mode_stack: Vec<KittyKeyboardFlags>,
+
+    /// Working directory reported by the shell.
+    working_directory: Option<WorkingDirectory>,
+
     /// Current forward and backward buffer search regexes.
     regex_search: Option<RegexSearch>,
 
     /// Information about cell dimensions.
     cell_width: usize,
     cell_height: usize,
+
+    /// Shared storage for the hyperlinks of all cells.
+    hyperlinks: HyperlinkInterner,
+
+    /// Deadline of the active synchronized update, rendering is paused until it ends.
+    sync_update_timeout: Option<Instant>,
+
+    /// Deadline of the synchronized update started internally by a resize.
+    ///
+    /// This is only used on Windows and is never reported to applications.
+    resize_sync_timeout: Option<Instant>,
+
+    /// Whether images can be displayed using the kitty graphics protocol.
+    kitty_graphics: bool,
+
+    /// Whether images can be displayed using sixel graphics.
+    sixel_graphics: bool,
+
+    /// Images transmitted using the kitty graphics protocol.
+    images: ImageStore,
+
+    /// Generation stamped on every written cell.
+    generation: u32,
 }
 
 impl<T> Term<T> {
@@ -829,7 +969,19 @@ impl<T> Term<T> {
         let num_lines = size.screen_lines;
 
         let history_size = config.scrolling.history() as usize;
-        let grid = Grid::new(num_lines, num_cols, history_size);
+        let mut grid = Grid::new(num_lines, num_cols, history_size);
+
+        if let Some(dir) = &config.scrollback_persistence {
+            if let Err(err) = persist::claim_orphan(dir) {
+                warn!("Unable to claim persisted scrollback in {:?}: {}", dir, err);
+            }
+
+            let path = persist::file(dir);
+            match persist::restore(&path, num_cols) {
+                Ok(rows) => grid.prepend_history(rows),
+                Err(err) => warn!("Unable to restore scrollback from {:?}: {}", path, err),
+            }
+        }
         let alt = Grid::new(num_lines, num_cols, 0);
 
         let tabs = TabStops::new(grid.cols());
@@ -852,17 +1004,34 @@ impl<T> Term<T> {
             color_modified: [false; color::COUNT],
             original_colors: colors,
             semantic_escape_chars: config.selection.semantic_escape_chars().to_owned(),
+            line_selection: config.selection.line_selection,
+            osc52: config.selection.osc52,
+            osc52_max_size: config.selection.osc52_max_size(),
             cursor_style: None,
             default_cursor_style: config.cursor.style(),
             vi_mode_cursor_style: config.cursor.vi_mode_style(),
             event_proxy,
             is_focused: true,
+            cursor_blink: true,
+            cursor_blink_start: Instant::now(),
+            cursor_blink_interval: Duration::from_millis(config.cursor.blink_interval()),
             title: None,
             title_stack: Vec::new(),
+            keyboard_mode_stack: Vec::new(),
+            inactive_keyboard_mode: KittyKeyboardFlags::empty(),
+            inactive_keyboard_mode_stack: Vec::new(),
+            working_directory: None,
             selection: None,
             regex_search: None,
             cell_width: size.cell_width as usize,
             cell_height: size.cell_height as usize,
+            hyperlinks: HyperlinkInterner::default(),
+            sync_update_timeout: None,
+            resize_sync_timeout: None,
+            kitty_graphics: config.kitty_graphics,
+            sixel_graphics: config.sixel_graphics,
+            images: ImageStore::default(),
+            generation: 0,
         }
     }
 
@@ -871,6 +1040,9 @@ impl<T> Term<T> {
         T: EventListener,
     {
         self.semantic_escape_chars = config.selection.semantic_escape_chars().to_owned();
+        self.line_selection = config.selection.line_selection;
+        self.osc52 = config.selection.osc52;
+        self.osc52_max_size = config.selection.osc52_max_size();
         self.original_colors.fill_named(&config.colors);
         self.original_colors.fill_cube(&config.colors);
         self.original_colors.fill_gray_ramp(&config.colors);
@@ -886,6 +1058,14 @@ impl<T> Term<T> {
         self.default_cursor_style = config.cursor.style();
         self.vi_mode_cursor_style = config.cursor.vi_mode_style();
 
+        self.cursor_blink_interval = Duration::from_millis(config.cursor.blink_interval());
+
+        self.kitty_graphics = config.kitty_graphics;
+        if !self.kitty_graphics {
+            self.images.clear();
+        }
+        self.sixel_graphics = config.sixel_graphics;
+
         let title_event = match &self.title {
             Some(title) => Event::Title(title.clone()),
             None => Event::ResetTitle,
@@ -977,7 +1157,7 @@ impl<T> Term<T> {
                 text.push(cell.c);
 
                 // Push zero-width characters.
-                for c in cell.zerowidth().into_iter().flatten() {
+                for c in cell.zerowidth().into_iter().flatten().take(MAX_ZEROWIDTH_CHARS) {
                     text.push(*c);
                 }
             }
@@ -1002,6 +1182,55 @@ impl<T> Term<T> {
         text
     }
 
+    /// Serialize lines of the grid with the escapes required to reproduce their attributes.
+    ///
+    /// Lines are counted from the top of the scrollback history, so `Line(0)` is the oldest line
+    /// in the history and the bottom of the screen is at `total_lines - 1`. Wrapped lines are not
+    /// separated, which lets the receiving terminal wrap them on its own. The output assumes the
+    /// receiving terminal starts out with default attributes and resets them at the end.
+    pub fn dump_grid(&self, range: RangeInclusive<Line>) -> String {
+        let total_lines = self.grid.total_lines();
+        let start = range.start().0;
+        let end = min(range.end().0, total_lines - 1);
+
+        let default = Cell::default();
+        let mut stream = EscapeStream::new();
+        let mut wrapped = false;
+
+        for line in start..=end {
+            let row = &self.grid[total_lines - 1 - line];
+
+            if line != start && !wrapped {
+                // Lines created by scrolling are cleared with the active background.
+                stream.reset_background();
+                stream.out.push_str("\r\n");
+            }
+
+            // Skip trailing blanks, unless their attributes are visible.
+            let blanks = row[..].iter().rev().take_while(|cell| **cell == default).count();
+            let mut length = max(row.line_length(), Column(row.len() - blanks));
+
+            // The previous line is only wrapped once a cell is written to this one.
+            if wrapped {
+                length = max(length, Column(1));
+            }
+
+            for cell in &row[..length] {
+                stream.push_cell(cell);
+            }
+
+            // Wrapping into a new line clears it with the background of its first cell.
+            if wrapped && length.0 < row.len() && row[Column(0)].bg != default.bg {
+                stream.reset_background();
+                stream.out.push_str("\x1b[K");
+            }
+
+            wrapped = row[Column(row.len() - 1)].flags.contains(Flags::WRAPLINE);
+        }
+
+        stream.finish()
+    }
+
     pub fn visible_to_buffer(&self, point: Point) -> Point<usize> {
         self.grid.visible_to_buffer(point)
     }
@@ -1014,6 +1243,18 @@ impl<T> Term<T> {
         &self.grid
     }
 
+    /// Copy the active screen, without any scrollback history.
+    pub fn snapshot(&self) -> TermSnapshot {
+        let lines =
+            (0..self.screen_lines().0).map(|line| self.grid[Line(line)][..].to_vec()).collect();
+
+        TermSnapshot {
+            lines,
+            cursor: self.grid.cursor.point,
+            title: self.title.clone().unwrap_or_default(),
+        }
+    }
+
     /// Mutable access for swapping out the grid during tests.
     #[cfg(test)]
     pub fn grid_mut(&mut self) -> &mut Grid<Cell> {
@@ -1082,7 +1323,7 @@ impl<T> Term<T> {
         } else if let Some(selection) = self.selection.take() {
             // Move the selection if only number of lines changed.
             let delta = if num_lines > old_lines {
-                (num_lines - old_lines.0).saturating_sub(self.history_size()) as isize
+                (num_lines - old_lines.0).saturating_sub(self.history_size()).0 as isize
             } else {
                 let cursor_line = self.grid.cursor.point.line;
                 -(min(old_lines - cursor_line - 1, old_lines - num_lines).0 as isize)
@@ -1109,8 +1350,54 @@ impl<T> Term<T> {
         &self.mode
     }
 
+    /// Current kitty keyboard protocol flags.
+    pub fn keyboard_mode(&self) -> KittyKeyboardFlags {
+        KEYBOARD_MODES
+            .iter()
+            .filter(|(_, mode)| self.mode.contains(*mode))
+            .fold(KittyKeyboardFlags::empty(), |flags, (flag, _)| flags | *flag)
+    }
+
+    /// Replace the current kitty keyboard protocol flags.
+    fn set_keyboard_flags(&mut self, flags: KittyKeyboardFlags) {
+        for (flag, mode) in KEYBOARD_MODES.iter() {
+            self.mode.set(*mode, flags.contains(*flag));
+        }
+    }
+
+    /// Deadline after which the active synchronized update is ended automatically.
+    ///
+    /// While a synchronized update (DEC private mode 2026) is active, the terminal should not be
+    /// rendered, so applications can present their changes atomically.
+    #[inline]
+    pub fn sync_update_timeout(&self) -> Option<Instant> {
+        self.sync_update_timeout.max(self.resize_sync_timeout)
+    }
+
+    /// End the active synchronized update.
+    #[inline]
+    pub fn stop_sync_update(&mut self) {
+        self.sync_update_timeout = None;
+        self.resize_sync_timeout = None;
+    }
+
+    /// Start a synchronized update for the repaint ConPTY sends after a resize.
+    ///
+    /// ConPTY redraws the entire screen after every resize, so rendering is paused like during a
+    /// synchronized update. The deadline is not extended by further resizes, which keeps drawing
+    /// the terminal periodically while the window is resized continuously.
+    #[cfg(windows)]
+    pub fn start_resize_sync(&mut self) {
+        let now = Instant::now();
+        if !matches!(self.resize_sync_timeout, Some(timeout) if timeout > now) {
+            self.resize_sync_timeout = Some(now + RESIZE_SYNC_TIMEOUT);
+        }
+    }
+
     /// Swap primary and alternate screen buffer.
     pub fn swap_alt(&mut self) {
+        self.sync_update_timeout = None;
+
         if !self.mode.contains(TermMode::ALT_SCREEN) {
             // Set alt screen cursor to the current primary screen cursor.
             self.inactive_grid.cursor = self.grid.cursor.clone();
@@ -1123,6 +1410,16 @@ impl<T> Term<T> {
             self.inactive_grid.region_mut(..).each(|cell| *cell = bg.into());
         }
 
+        // Both screens have their own keyboard modes, the alternate screen's are reset on exit.
+        let keyboard_mode = self.keyboard_mode();
+        self.set_keyboard_flags(self.inactive_keyboard_mode);
+        self.inactive_keyboard_mode = keyboard_mode;
+        mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
+        if self.mode.contains(TermMode::ALT_SCREEN) {
+            self.inactive_keyboard_mode = KittyKeyboardFlags::empty();
+            self.inactive_keyboard_mode_stack.clear();
+        }
+
         mem::swap(&mut self.grid, &mut self.inactive_grid);
         self.mode ^= TermMode::ALT_SCREEN;
         self.selection = None;
@@ -1190,9 +1487,41 @@ impl<T> Term<T> {
         self.grid.region_mut(..).each(|cell| *cell = bg.into());
     }
 
+    /// Color used to clear the window, which is the foreground color in reverse video.
     #[inline]
     pub fn background_color(&self) -> Rgb {
-        self.colors[NamedColor::Background]
+        if self.mode.contains(TermMode::REVERSE_VIDEO) {
+            self.colors[NamedColor::Foreground]
+        } else {
+            self.colors[NamedColor::Background]
+        }
+    }
+
+    /// Generation which is stamped on cells as they are written.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Start a new generation, aging all previously written cells by one.
+    #[inline]
+    pub fn next_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Working directory reported by the shell through OSC 7.
+    #[inline]
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.working_directory.as_ref()
+    }
+
+    /// Capture the primary grid, so it can be persisted and restored after a crash.
+    pub fn scrollback_snapshot(&self) -> persist::Snapshot {
+        if self.mode.contains(TermMode::ALT_SCREEN) {
+            persist::Snapshot::new(&self.inactive_grid)
+        } else {
+            persist::Snapshot::new(&self.grid)
+        }
     }
 
     #[inline]
@@ -1203,6 +1532,45 @@ impl<T> Term<T> {
         self.event_proxy.send_event(Event::Exit);
     }
 
+    /// Check the consistency of the terminal state, panicking if it is violated.
+    ///
+    /// This walks the entire grid including scrollback, so it is only available in debug and test
+    /// builds.
+    #[cfg(any(test, debug_assertions))]
+    pub fn verify_invariants(&self) {
+        let cursor = self.grid.cursor.point;
+        assert!(cursor.line < self.screen_lines(), "cursor line out of bounds: {:?}", cursor);
+        assert!(cursor.col < self.cols(), "cursor column out of bounds: {:?}", cursor);
+
+        for index in 0..self.grid.total_lines() {
+            let row = &self.grid[index];
+            for (col, cell) in row[..].iter().enumerate() {
+                if cell.flags.contains(Flags::WIDE_CHAR) {
+                    let spacer = row[..].get(col + 1).map(|cell| cell.flags);
+                    assert!(
+                        spacer.map(|flags| flags.contains(Flags::WIDE_CHAR_SPACER)) == Some(true),
+                        "wide char without spacer in line {} column {}",
+                        index,
+                        col
+                    );
+                }
+
+                if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    assert!(
+                        col > 0 && row[Column(col - 1)].flags.contains(Flags::WIDE_CHAR),
+                        "spacer without wide char in line {} column {}",
+                        index,
+                        col
+                    );
+                }
+
+                if cell.flags.contains(Flags::WRAPLINE) {
+                    assert_eq!(col + 1, row.len(), "wrapline before the end of line {}", index);
+                }
+            }
+        }
+    }
+
     /// Toggle the vi mode.
     #[inline]
     pub fn toggle_vi_mode(&mut self)
@@ -1336,6 +1704,11 @@ impl<T> Term<T> {
         &self.semantic_escape_chars
     }
 
+    #[inline]
+    pub fn line_selection(&self) -> LineSelection {
+        self.line_selection
+    }
+
     /// Active terminal cursor style.
     ///
     /// While vi mode is active, this will automatically return the vi mode cursor style.
@@ -1350,6 +1723,67 @@ impl<T> Term<T> {
         }
     }
 
+    /// Whether a blinking cursor is currently visible.
+    #[inline]
+    pub fn cursor_blink(&self) -> bool {
+        self.cursor_blink
+    }
+
+    /// Whether the cursor is visible, ignoring its blinking state.
+    ///
+    /// The vi mode cursor is always visible, even if the application hid the terminal cursor.
+    #[inline]
+    pub fn cursor_visible(&self) -> bool {
+        self.mode.intersects(TermMode::SHOW_CURSOR | TermMode::VI)
+    }
+
+    /// Change the visibility of the terminal cursor.
+    ///
+    /// Showing the cursor restarts its blinking cycle, so it does not reappear in the hidden
+    /// phase. Listeners are notified, to stop blinking while the cursor is hidden.
+    fn set_cursor_visible(&mut self, visible: bool)
+    where
+        T: EventListener,
+    {
+        if self.mode.contains(TermMode::SHOW_CURSOR) == visible {
+            return;
+        }
+
+        self.mode.set(TermMode::SHOW_CURSOR, visible);
+        if visible {
+            self.reset_cursor_blink(Instant::now());
+        }
+
+        self.event_proxy.send_event(Event::CursorBlinkingChange(self.cursor_style().blinking));
+    }
+
+    /// Update the cursor blinking state.
+    ///
+    /// The cursor toggles its visibility every blink interval since the last reset. The elapsed
+    /// time is rounded to the closest interval, so timers firing slightly early or late do not
+    /// skip a phase.
+    ///
+    /// Returns `true` if the cursor visibility has changed.
+    pub fn advance_cursor_blink(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.cursor_blink_start);
+        let interval = self.cursor_blink_interval.as_millis().max(1);
+        let phase = (elapsed.as_millis() + interval / 2) / interval;
+
+        let cursor_blink = phase & 1 == 0;
+        let changed = cursor_blink != self.cursor_blink;
+        self.cursor_blink = cursor_blink;
+
+        changed
+    }
+
+    /// Show the cursor and restart its blinking cycle.
+    ///
+    /// This should be called whenever the user types, to keep the cursor visible while typing.
+    pub fn reset_cursor_blink(&mut self, now: Instant) {
+        self.cursor_blink_start = now;
+        self.cursor_blink = true;
+    }
+
     /// Insert a linebreak at the current cursor position.
     #[inline]
     fn wrapline(&mut self)
@@ -1384,6 +1818,12 @@ impl<T> Term<T> {
         let fg = self.grid.cursor.template.fg;
         let bg = self.grid.cursor.template.bg;
         let flags = self.grid.cursor.template.flags;
+        let underline_color = self.grid.cursor.template.underline_color();
+        let underline_style = self.grid.cursor.template.underline_style();
+        let hyperlink = self.grid.cursor.template.hyperlink().cloned();
+
+        let point = self.grid.cursor.point;
+        self.clear_wide_char_at(point);
 
         let cursor_cell = self.grid.cursor_cell();
 
@@ -1393,10 +1833,142 @@ impl<T> Term<T> {
         cursor_cell.fg = fg;
         cursor_cell.bg = bg;
         cursor_cell.flags = flags;
+        cursor_cell.generation = self.generation;
+
+        if underline_color.is_some() {
+            cursor_cell.set_underline_color(underline_color);
+        }
+
+        if !matches!(underline_style, UnderlineStyle::None | UnderlineStyle::Single) {
+            cursor_cell.set_underline_style(underline_style);
+        }
+
+        if hyperlink.is_some() {
+            cursor_cell.set_hyperlink(hyperlink);
+        }
 
         cursor_cell
     }
 
+    /// Clear the other half of a wide char before the cell at `point` is overwritten.
+    #[inline]
+    fn clear_wide_char_at(&mut self, point: Point) {
+        let row = &mut self.grid[point.line];
+        let flags = row[point.col].flags;
+        if flags.contains(Flags::WIDE_CHAR) && point.col.0 + 1 < row.len() {
+            row[point.col + 1].flags.remove(Flags::WIDE_CHAR_SPACER);
+        } else if flags.contains(Flags::WIDE_CHAR_SPACER) && point.col.0 > 0 {
+            let wide_char = &mut row[point.col - 1];
+            wide_char.flags.remove(Flags::WIDE_CHAR);
+            wide_char.c = ' ';
+        }
+    }
+
+    /// Display an image at the cursor position.
+    ///
+    /// Every cell covered by the image references the part of the image it displays. The image
+    /// scrolls the screen if it extends below the bottom of the scrolling region.
+    fn place_image(&mut self, image: Arc<Image>, move_cursor: bool)
+    where
+        T: EventListener,
+    {
+        let cols = (image.width as usize + self.cell_width - 1) / self.cell_width.max(1);
+        let lines = (image.height as usize + self.cell_height - 1) / self.cell_height.max(1);
+
+        let saved_cursor = self.grid.cursor.clone();
+        let start_col = self.grid.cursor.point.col;
+        let visible_cols = cols.min(self.cols().0 - start_col.0);
+
+        for line in 0..lines {
+            if line > 0 {
+                self.linefeed();
+            }
+
+            let cursor_line = self.grid.cursor.point.line;
+            for col in 0..visible_cols {
+                let point = Point::new(cursor_line, start_col + col);
+                self.clear_wide_char_at(point);
+
+                let cell = &mut self.grid[point.line][point.col];
+                *cell = Cell::default();
+                cell.generation = self.generation;
+                cell.set_graphic(Some(GraphicCell {
+                    image: image.clone(),
+                    col: col as u32,
+                    line: line as u32,
+                }));
+            }
+        }
+
+        if move_cursor {
+            let col = start_col.0 + cols;
+            self.grid.cursor.point.col = Column(col.min(self.cols().0 - 1));
+            self.grid.cursor.input_needs_wrap = col >= self.cols().0;
+        } else {
+            self.grid.cursor = saved_cursor;
+        }
+    }
+
+    /// Remove displayed images.
+    ///
+    /// Images are either removed from the visible screen or, when an ID is specified, from the
+    /// entire buffer.
+    fn delete_images(&mut self, command: &GraphicsCommand) {
+        let by_id = command.delete.eq_ignore_ascii_case(&b'i');
+        let lines = if by_id { self.grid.total_lines() } else { self.screen_lines().0 };
+
+        for line in 0..lines {
+            let row = if by_id { &mut self.grid[line] } else { &mut self.grid[Line(line)] };
+            for cell in row[..].iter_mut() {
+                let id = match cell.graphic() {
+                    Some(graphic) => graphic.image.id,
+                    None => continue,
+                };
+
+                if !by_id || (id == command.id && command.id != 0) {
+                    *cell = Cell::default();
+                }
+            }
+        }
+
+        if by_id && command.delete.is_ascii_uppercase() {
+            self.images.remove(command.id);
+        }
+    }
+
+    /// Report the result of a graphics command back to the client.
+    fn graphics_response(&mut self, command: &GraphicsCommand, result: Result<(), GraphicsError>)
+    where
+        T: EventListener,
+    {
+        // Images without an ID can't be referenced by a response.
+        if command.id == 0 {
+            return;
+        }
+
+        let message = match result {
+            Ok(()) if command.quiet == 0 => String::from("OK"),
+            Err(err) if command.quiet < 2 => err.to_string(),
+            _ => return,
+        };
+
+        let response = format!("\x1b_Gi={};{}\x1b\\", command.id, message);
+        self.event_proxy.send_event(Event::PtyWrite(response));
+    }
+
+    /// Check if a printable ASCII character can be written without any special handling.
+    ///
+    /// This is the case when the character maps to itself in the active charset and it can be
+    /// written at the cursor without wrapping or shifting existing cells.
+    #[inline]
+    fn can_input_ascii(&self) -> bool {
+        let cursor = &self.grid.cursor;
+        !cursor.input_needs_wrap
+            && cursor.point.col + 1 < self.cols()
+            && cursor.charsets[self.active_charset] == StandardCharset::Ascii
+            && !self.mode.contains(TermMode::INSERT)
+    }
+
     /// Get rendering information about the active cursor.
     fn renderable_cursor<C>(&self, config: &Config<C>, show_cursor: bool) -> RenderableCursor {
         let vi_mode = self.mode.contains(TermMode::VI);
@@ -1480,6 +2052,13 @@ impl<T: EventListener> Handler for Term<T> {
     /// A character to be displayed.
     #[inline(never)]
     fn input(&mut self, c: char) {
+        // Fast path for printable ASCII, which makes up the majority of all terminal output.
+        if (' '..='~').contains(&c) && self.can_input_ascii() {
+            self.write_at_cursor(c);
+            self.grid.cursor.point.col += 1;
+            return;
+        }
+
         // Number of cells the char will occupy.
         let width = match c.width() {
             Some(width) => width,
@@ -1513,13 +2092,7 @@ impl<T: EventListener> Handler for Term<T> {
 
         // If in insert mode, first shift cells to the right.
         if self.mode.contains(TermMode::INSERT) && self.grid.cursor.point.col + width < num_cols {
-            let line = self.grid.cursor.point.line;
-            let col = self.grid.cursor.point.col;
-            let row = &mut self.grid[line][..];
-
-            for col in (col.0..(num_cols - width).0).rev() {
-                row.swap(col + width, col);
-            }
+            self.insert_blank(Column(width));
         }
 
         if width == 1 {
@@ -1612,6 +2185,8 @@ impl<T: EventListener> Handler for Term<T> {
         for cell in &mut row[source.0..destination.0] {
             *cell = bg.into();
         }
+
+        repair_row(row);
     }
 
     #[inline]
@@ -1639,7 +2214,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_backward(&mut self, cols: Column) {
         trace!("Moving backward: {}", cols);
-        self.grid.cursor.point.col = Column(self.grid.cursor.point.col.saturating_sub(cols.0));
+        self.grid.cursor.point.col = self.grid.cursor.point.col.saturating_sub(cols.0);
         self.grid.cursor.input_needs_wrap = false;
     }
 
@@ -1659,6 +2234,12 @@ impl<T: EventListener> Handler for Term<T> {
         }
     }
 
+    #[inline]
+    fn report_version<W: io::Write>(&mut self, writer: &mut W) {
+        trace!("Reporting terminal version");
+        let _ = write!(writer, "\x1bP>|alacritty {}\x1b\\", env!("CARGO_PKG_VERSION"));
+    }
+
     #[inline]
     fn device_status<W: io::Write>(&mut self, writer: &mut W, arg: usize) {
         trace!("Reporting device status: {}", arg);
@@ -1852,6 +2433,8 @@ impl<T: EventListener> Handler for Term<T> {
         for cell in &mut row[start..end] {
             *cell = bg.into();
         }
+
+        repair_row(&mut row[..]);
     }
 
     #[inline]
@@ -1880,6 +2463,8 @@ impl<T: EventListener> Handler for Term<T> {
         for cell in &mut row[end..] {
             *cell = bg.into();
         }
+
+        repair_row(row);
     }
 
     #[inline]
@@ -1896,6 +2481,8 @@ impl<T: EventListener> Handler for Term<T> {
             }
             self.grid.cursor.point.col = col;
         }
+
+        self.grid.cursor.input_needs_wrap = false;
     }
 
     #[inline]
@@ -1945,6 +2532,8 @@ impl<T: EventListener> Handler for Term<T> {
             },
         }
 
+        repair_row(&mut row[..]);
+
         let cursor_buffer_line = (self.screen_lines() - self.grid.cursor.point.line - 1).0;
         self.selection = self
             .selection
@@ -1986,25 +2575,113 @@ impl<T: EventListener> Handler for Term<T> {
         self.color_modified[index] = false;
     }
 
+    /// Handle a kitty graphics protocol command.
+    fn graphics_command(&mut self, command: GraphicsCommand) {
+        if !self.kitty_graphics {
+            return;
+        }
+
+        trace!("Graphics command: {:?} {}", command.action, command.id);
+
+        let command = match self.images.push_chunk(command) {
+            Some(Ok(command)) => command,
+            Some(Err((command, err))) => return self.graphics_response(&command, Err(err)),
+            None => return,
+        };
+
+        let result = match command.action {
+            Action::Query => self.images.decode(&command).map(|_| ()),
+            Action::Transmit => self.images.transmit(&command).map(|_| ()),
+            Action::TransmitAndDisplay => self.images.transmit(&command).map(|image| {
+                self.place_image(image, command.move_cursor);
+            }),
+            Action::Put => match self.images.get(command.id).cloned() {
+                Some(image) => {
+                    self.place_image(image, command.move_cursor);
+                    Ok(())
+                },
+                None => Err(GraphicsError::new("ENOENT", "image not found")),
+            },
+            Action::Delete => {
+                self.delete_images(&command);
+                return;
+            },
+        };
+
+        self.graphics_response(&command, result);
+    }
+
+    fn sixel_image(&mut self, sixel: SixelImage) {
+        if !self.sixel_graphics {
+            return;
+        }
+
+        trace!("Sixel image: {}x{}", sixel.width, sixel.height);
+
+        let image = match self.images.decode_sixel(&sixel) {
+            Ok(image) => image,
+            Err(err) => {
+                debug!("Ignoring sixel image: {}", err);
+                return;
+            },
+        };
+
+        // Continue below the image, at the column it started in.
+        let start_col = self.grid.cursor.point.col;
+        self.place_image(image, true);
+        self.linefeed();
+        self.grid.cursor.point.col = start_col;
+        self.grid.cursor.input_needs_wrap = false;
+    }
+
+    /// Set the hyperlink for all following cells.
+    #[inline]
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        trace!("Setting hyperlink: {:?}", hyperlink);
+        let hyperlink = hyperlink.map(|hyperlink| self.hyperlinks.intern(hyperlink));
+        self.grid.cursor.template.set_hyperlink(hyperlink);
+    }
+
+    #[inline]
+    fn set_semantic_zone(&mut self, zone: Option<SemanticZone>) {
+        trace!("Setting semantic zone: {:?}", zone);
+        let template = &mut self.grid.cursor.template;
+        template.flags.remove(Flags::SEMANTIC_ZONE);
+        template.flags.insert(zone.map_or(Flags::empty(), SemanticZone::flags));
+    }
+
     /// Store data into clipboard.
     #[inline]
-    fn clipboard_store(&mut self, clipboard: u8, base64: &[u8]) {
+    fn clipboard_store(&mut self, clipboard: u8, data: &[u8]) {
+        if !self.osc52.allows_copy() {
+            debug!("Ignoring OSC 52 clipboard store");
+            return;
+        }
+
         let clipboard_type = match clipboard {
             b'c' => ClipboardType::Clipboard,
             b'p' | b's' => ClipboardType::Selection,
             _ => return,
         };
 
-        if let Ok(bytes) = base64::decode(base64) {
-            if let Ok(text) = String::from_utf8(bytes) {
-                self.event_proxy.send_event(Event::ClipboardStore(clipboard_type, text));
-            }
+        if let Ok(text) = str::from_utf8(data) {
+            self.event_proxy.send_event(Event::ClipboardStore(clipboard_type, text.to_owned()));
         }
     }
 
+    #[inline]
+    fn clipboard_store_limit(&self) -> usize {
+        self.osc52_max_size
+    }
+
     /// Load data from clipboard.
     #[inline]
     fn clipboard_load(&mut self, clipboard: u8, terminator: &str) {
+        if !self.osc52.allows_paste() {
+            debug!("Ignoring OSC 52 clipboard load");
+            return;
+        }
+
         let clipboard_type = match clipboard {
             b'c' => ClipboardType::Clipboard,
             b'p' | b's' => ClipboardType::Selection,
@@ -2022,6 +2699,16 @@ impl<T: EventListener> Handler for Term<T> {
         ));
     }
 
+    #[inline]
+    fn set_working_directory(&mut self, working_directory: Option<WorkingDirectory>) {
+        trace!("Setting working directory to {:?}", working_directory);
+
+        if working_directory != self.working_directory {
+            self.working_directory = working_directory.clone();
+            self.event_proxy.send_event(Event::WorkingDirectory(working_directory));
+        }
+    }
+
     #[inline]
     fn clear_screen(&mut self, mode: ansi::ClearMode) {
         trace!("Clearing screen: {:?}", mode);
@@ -2045,6 +2732,7 @@ impl<T: EventListener> Handler for Term<T> {
                 for cell in &mut self.grid[cursor.line][..end] {
                     *cell = bg.into();
                 }
+                repair_row(&mut self.grid[cursor.line][..]);
 
                 self.selection = self
                     .selection
@@ -2056,6 +2744,7 @@ impl<T: EventListener> Handler for Term<T> {
                 for cell in &mut self.grid[cursor.line][cursor.col..] {
                     *cell = bg.into();
                 }
+                repair_row(&mut self.grid[cursor.line][..]);
 
                 if cursor.line.0 < num_lines - 1 {
                     self.grid.region_mut((cursor.line + 1)..).each(|cell| *cell = bg.into());
@@ -2102,6 +2791,7 @@ impl<T: EventListener> Handler for Term<T> {
         if self.mode.contains(TermMode::ALT_SCREEN) {
             mem::swap(&mut self.grid, &mut self.inactive_grid);
         }
+        self.sync_update_timeout = None;
         self.active_charset = Default::default();
         self.colors = self.original_colors;
         self.color_modified = [false; color::COUNT];
@@ -2111,9 +2801,13 @@ impl<T: EventListener> Handler for Term<T> {
         self.scroll_region = Line(0)..self.screen_lines();
         self.tabs = TabStops::new(self.cols());
         self.title_stack = Vec::new();
+        self.keyboard_mode_stack = Vec::new();
+        self.inactive_keyboard_mode = KittyKeyboardFlags::empty();
+        self.inactive_keyboard_mode_stack = Vec::new();
         self.title = None;
         self.selection = None;
         self.regex_search = None;
+        self.images.clear();
 
         // Preserve vi mode across resets.
         self.mode &= TermMode::VI;
@@ -2130,7 +2824,7 @@ impl<T: EventListener> Handler for Term<T> {
         if self.grid.cursor.point.line == self.scroll_region.start {
             self.scroll_down(Line(1));
         } else {
-            self.grid.cursor.point.line = Line(self.grid.cursor.point.line.saturating_sub(1));
+            self.grid.cursor.point.line = self.grid.cursor.point.line.saturating_sub(1);
         }
     }
 
@@ -2138,42 +2832,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn terminal_attribute(&mut self, attr: Attr) {
         trace!("Setting attribute: {:?}", attr);
-        let cursor = &mut self.grid.cursor;
-        match attr {
-            Attr::Foreground(color) => cursor.template.fg = color,
-            Attr::Background(color) => cursor.template.bg = color,
-            Attr::Reset => {
-                cursor.template.fg = Color::Named(NamedColor::Foreground);
-                cursor.template.bg = Color::Named(NamedColor::Background);
-                cursor.template.flags = Flags::empty();
-            },
-            Attr::Reverse => cursor.template.flags.insert(Flags::INVERSE),
-            Attr::CancelReverse => cursor.template.flags.remove(Flags::INVERSE),
-            Attr::Bold => cursor.template.flags.insert(Flags::BOLD),
-            Attr::CancelBold => cursor.template.flags.remove(Flags::BOLD),
-            Attr::Dim => cursor.template.flags.insert(Flags::DIM),
-            Attr::CancelBoldDim => cursor.template.flags.remove(Flags::BOLD | Flags::DIM),
-            Attr::Italic => cursor.template.flags.insert(Flags::ITALIC),
-            Attr::CancelItalic => cursor.template.flags.remove(Flags::ITALIC),
-            Attr::Underline => {
-                cursor.template.flags.remove(Flags::DOUBLE_UNDERLINE);
-                cursor.template.flags.insert(Flags::UNDERLINE);
-            },
-            Attr::DoubleUnderline => {
-                cursor.template.flags.remove(Flags::UNDERLINE);
-                cursor.template.flags.insert(Flags::DOUBLE_UNDERLINE);
-            },
-            Attr::CancelUnderline => {
-                cursor.template.flags.remove(Flags::UNDERLINE | Flags::DOUBLE_UNDERLINE);
-            },
-            Attr::Hidden => cursor.template.flags.insert(Flags::HIDDEN),
-            Attr::CancelHidden => cursor.template.flags.remove(Flags::HIDDEN),
-            Attr::Strike => cursor.template.flags.insert(Flags::STRIKEOUT),
-            Attr::CancelStrike => cursor.template.flags.remove(Flags::STRIKEOUT),
-            _ => {
-                debug!("Term got unhandled attr: {:?}", attr);
-            },
-        }
+        self.grid.cursor.template.apply_attr(attr);
     }
 
     #[inline]
@@ -2186,7 +2845,7 @@ impl<T: EventListener> Handler for Term<T> {
                     self.swap_alt();
                 }
             },
-            ansi::Mode::ShowCursor => self.mode.insert(TermMode::SHOW_CURSOR),
+            ansi::Mode::ShowCursor => self.set_cursor_visible(true),
             ansi::Mode::CursorKeys => self.mode.insert(TermMode::APP_CURSOR),
             // Mouse protocols are mutually exclusive.
             ansi::Mode::ReportMouseClicks => {
@@ -2221,11 +2880,16 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::Origin => self.mode.insert(TermMode::ORIGIN),
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.insert(TermMode::INSERT),
+            ansi::Mode::ReverseVideo => self.mode.insert(TermMode::REVERSE_VIDEO),
             ansi::Mode::BlinkingCursor => {
                 let style = self.cursor_style.get_or_insert(self.default_cursor_style);
                 style.blinking = true;
                 self.event_proxy.send_event(Event::CursorBlinkingChange(true));
             },
+            ansi::Mode::SyncUpdate => {
+                self.sync_update_timeout = Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
+            },
+            ansi::Mode::Win32Input => self.mode.insert(TermMode::WIN32_INPUT),
         }
     }
 
@@ -2239,7 +2903,7 @@ impl<T: EventListener> Handler for Term<T> {
                     self.swap_alt();
                 }
             },
-            ansi::Mode::ShowCursor => self.mode.remove(TermMode::SHOW_CURSOR),
+            ansi::Mode::ShowCursor => self.set_cursor_visible(false),
             ansi::Mode::CursorKeys => self.mode.remove(TermMode::APP_CURSOR),
             ansi::Mode::ReportMouseClicks => {
                 self.mode.remove(TermMode::MOUSE_REPORT_CLICK);
@@ -2263,14 +2927,67 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::Origin => self.mode.remove(TermMode::ORIGIN),
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.remove(TermMode::INSERT),
+            ansi::Mode::ReverseVideo => self.mode.remove(TermMode::REVERSE_VIDEO),
             ansi::Mode::BlinkingCursor => {
                 let style = self.cursor_style.get_or_insert(self.default_cursor_style);
                 style.blinking = false;
                 self.event_proxy.send_event(Event::CursorBlinkingChange(false));
             },
+            ansi::Mode::SyncUpdate => self.stop_sync_update(),
+            ansi::Mode::Win32Input => self.mode.remove(TermMode::WIN32_INPUT),
         }
     }
 
+    #[inline]
+    fn report_mode<W: io::Write>(&mut self, writer: &mut W, intermediate: Option<&u8>, mode: u16) {
+        trace!("Reporting mode {}", mode);
+
+        let flag = match ansi::Mode::from_primitive(intermediate, mode) {
+            Some(ansi::Mode::SyncUpdate) => Some(self.sync_update_timeout.is_some()),
+            Some(ansi::Mode::BlinkingCursor) => Some(self.cursor_style().blinking),
+            Some(ansi::Mode::SwapScreenAndSetRestoreCursor) => {
+                Some(self.mode.contains(TermMode::ALT_SCREEN))
+            },
+            Some(ansi::Mode::ShowCursor) => Some(self.mode.contains(TermMode::SHOW_CURSOR)),
+            Some(ansi::Mode::CursorKeys) => Some(self.mode.contains(TermMode::APP_CURSOR)),
+            Some(ansi::Mode::ReportMouseClicks) => {
+                Some(self.mode.contains(TermMode::MOUSE_REPORT_CLICK))
+            },
+            Some(ansi::Mode::ReportCellMouseMotion) => {
+                Some(self.mode.contains(TermMode::MOUSE_DRAG))
+            },
+            Some(ansi::Mode::ReportAllMouseMotion) => {
+                Some(self.mode.contains(TermMode::MOUSE_MOTION))
+            },
+            Some(ansi::Mode::ReportFocusInOut) => Some(self.mode.contains(TermMode::FOCUS_IN_OUT)),
+            Some(ansi::Mode::BracketedPaste) => Some(self.mode.contains(TermMode::BRACKETED_PASTE)),
+            Some(ansi::Mode::SgrMouse) => Some(self.mode.contains(TermMode::SGR_MOUSE)),
+            Some(ansi::Mode::Utf8Mouse) => Some(self.mode.contains(TermMode::UTF8_MOUSE)),
+            Some(ansi::Mode::AlternateScroll) => {
+                Some(self.mode.contains(TermMode::ALTERNATE_SCROLL))
+            },
+            Some(ansi::Mode::UrgencyHints) => Some(self.mode.contains(TermMode::URGENCY_HINTS)),
+            Some(ansi::Mode::LineWrap) => Some(self.mode.contains(TermMode::LINE_WRAP)),
+            Some(ansi::Mode::LineFeedNewLine) => {
+                Some(self.mode.contains(TermMode::LINE_FEED_NEW_LINE))
+            },
+            Some(ansi::Mode::Origin) => Some(self.mode.contains(TermMode::ORIGIN)),
+            Some(ansi::Mode::Insert) => Some(self.mode.contains(TermMode::INSERT)),
+            Some(ansi::Mode::ReverseVideo) => Some(self.mode.contains(TermMode::REVERSE_VIDEO)),
+            Some(ansi::Mode::Win32Input) => Some(self.mode.contains(TermMode::WIN32_INPUT)),
+            Some(ansi::Mode::DECCOLM) | None => None,
+        };
+
+        // Modes are reported as set (1), reset (2) or not recognized (0).
+        let state = match flag {
+            Some(true) => 1,
+            Some(false) => 2,
+            None => 0,
+        };
+        let private = if intermediate.is_some() { "?" } else { "" };
+        let _ = write!(writer, "\x1b[{}{};{}$y", private, mode, state);
+    }
+
     #[inline]
     fn set_scrolling_region(&mut self, top: usize, bottom: Option<usize>) {
         // Fallback to the last line as default.
@@ -2387,6 +3104,110 @@ impl<T: EventListener> Handler for Term<T> {
     fn text_area_size_chars<W: io::Write>(&mut self, writer: &mut W) {
         let _ = write!(writer, "\x1b[8;{};{}t", self.screen_lines(), self.cols());
     }
+
+    #[inline]
+    fn push_keyboard_mode(&mut self, flags: KittyKeyboardFlags) {
+        trace!("Pushing keyboard mode {:?}", flags);
+
+        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
+            self.keyboard_mode_stack.remove(0);
+        }
+
+        self.keyboard_mode_stack.push(self.keyboard_mode());
+        self.set_keyboard_flags(flags);
+    }
+
+    #[inline]
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        trace!("Popping {} keyboard modes", to_pop);
+
+        if to_pop == 0 {
+            return;
+        }
+
+        // Popping more entries than were pushed resets all flags.
+        let len = self.keyboard_mode_stack.len();
+        let flags = match len.checked_sub(to_pop as usize) {
+            Some(index) => {
+                let flags = self.keyboard_mode_stack[index];
+                self.keyboard_mode_stack.truncate(index);
+                flags
+            },
+            None => {
+                self.keyboard_mode_stack.clear();
+                KittyKeyboardFlags::empty()
+            },
+        };
+
+        self.set_keyboard_flags(flags);
+    }
+
+    #[inline]
+    fn set_keyboard_mode(&mut self, flags: KittyKeyboardFlags, behavior: KeyboardModeBehavior) {
+        trace!("Setting keyboard mode {:?} using {:?}", flags, behavior);
+
+        let current = self.keyboard_mode();
+        let flags = match behavior {
+            KeyboardModeBehavior::Replace => flags,
+            KeyboardModeBehavior::Union => current | flags,
+            KeyboardModeBehavior::Difference => current - flags,
+        };
+
+        self.set_keyboard_flags(flags);
+    }
+
+    #[inline]
+    fn report_keyboard_mode<W: io::Write>(&mut self, writer: &mut W) {
+        let _ = write!(writer, "\x1b[?{}u", self.keyboard_mode().bits());
+    }
+
+    #[inline]
+    fn set_modify_other_keys(&mut self, modify: ModifyOtherKeys) {
+        trace!("Setting modifyOtherKeys: {:?}", modify);
+        self.mode.remove(TermMode::MODIFY_OTHER_KEYS | TermMode::MODIFY_ALL_KEYS);
+        match modify {
+            ModifyOtherKeys::Reset => (),
+            ModifyOtherKeys::EnableExceptWellDefined => {
+                self.mode.insert(TermMode::MODIFY_OTHER_KEYS)
+            },
+            ModifyOtherKeys::EnableAll => self.mode.insert(TermMode::MODIFY_ALL_KEYS),
+        }
+    }
+
+    #[inline]
+    fn report_modify_other_keys<W: io::Write>(&mut self, writer: &mut W) {
+        let level = if self.mode.contains(TermMode::MODIFY_ALL_KEYS) {
+            2
+        } else if self.mode.contains(TermMode::MODIFY_OTHER_KEYS) {
+            1
+        } else {
+            0
+        };
+        let _ = write!(writer, "\x1b[>4;{}m", level);
+    }
+
+    #[inline]
+    fn report_capability(&mut self, name: Option<&str>, hex_name: &[u8]) {
+        trace!("Reporting capability: {:?}", name);
+        let response = match name.and_then(|name| Some((name, terminfo::capability(name)?))) {
+            Some((name, Some(value))) => format!(
+                "\x1bP1+r{}={}\x1b\\",
+                terminfo::encode_hex(name),
+                terminfo::encode_hex(value)
+            ),
+            Some((name, None)) => format!("\x1bP1+r{}\x1b\\", terminfo::encode_hex(name)),
+            None => {
+                // Unknown names are echoed back, without anything the shell might interpret.
+                let hex_name: String = hex_name
+                    .iter()
+                    .filter(|b| b.is_ascii_graphic())
+                    .map(|&b| char::from(b))
+                    .collect();
+                format!("\x1bP0+r{}\x1b\\", hex_name)
+            },
+        };
+        self.event_proxy.send_event(Event::PtyWrite(response));
+    }
 }
 
 /// Terminal version for escape sequence reports.
@@ -2410,6 +3231,224 @@ fn version_number(mut version: &str) -> usize {
     version_number
 }
 
+/// Options for serializing rows of cells with [`region_to_escape_with_options`].
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EscapeOptions {
+    /// Replace blank cells with a uniform background at the end of a row by EL.
+    ///
+    /// Since EL fills the line with the current background color, this is shorter than writing
+    /// the cells out explicitly and preserves full-width highlights.
+    pub erase_trailing_bg: bool,
+
+    /// Character written in place of empty cells.
+    ///
+    /// Trailing empty cells are still skipped, so this only makes gaps between the text of a row
+    /// visible.
+    pub empty_glyph: Option<char>,
+}
+
+/// Owned copy of the terminal's screen, for inspecting it in tests and scripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermSnapshot {
+    /// Cells of every screen line, from top to bottom.
+    pub lines: Vec<Vec<Cell>>,
+
+    /// Position of the terminal cursor.
+    pub cursor: Point,
+
+    /// Window title, empty if no title was set.
+    pub title: String,
+}
+
+impl TermSnapshot {
+    /// Text of the screen without any attributes.
+    ///
+    /// Lines are separated by `\n` and trailing spaces are removed from every line.
+    pub fn to_plain_text(&self) -> String {
+        let spacer = Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER;
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let mut text = String::new();
+                for cell in line.iter().filter(|cell| !cell.flags.intersects(spacer)) {
+                    text.push(cell.c);
+                    text.extend(cell.zerowidth().into_iter().flatten());
+                }
+                text.truncate(text.trim_end_matches(' ').len());
+                text
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+
+    /// Text of the screen with the escapes required to reproduce its attributes.
+    ///
+    /// See [`region_to_escape`] for details on the format.
+    pub fn to_ansi(&self) -> String {
+        let rows: Vec<Row<Cell>> =
+            self.lines.iter().map(|line| Row::from_vec(line.clone(), line.len())).collect();
+        region_to_escape(&rows)
+    }
+}
+
+/// Restore the invariants of a row after its cells were moved or erased.
+///
+/// Wide chars and spacers which lost their counterpart are cleared and a displaced wrap flag is
+/// moved back to the last column.
+fn repair_row(row: &mut [Cell]) {
+    let last = row.len() - 1;
+    for col in 0..row.len() {
+        let flags = row[col].flags;
+        if flags.contains(Flags::WIDE_CHAR)
+            && row.get(col + 1).map(|cell| cell.flags.contains(Flags::WIDE_CHAR_SPACER))
+                != Some(true)
+        {
+            row[col].flags.remove(Flags::WIDE_CHAR);
+            row[col].c = ' ';
+        } else if flags.contains(Flags::WIDE_CHAR_SPACER)
+            && (col == 0 || !row[col - 1].flags.contains(Flags::WIDE_CHAR))
+        {
+            row[col].flags.remove(Flags::WIDE_CHAR_SPACER);
+        }
+
+        if col != last && flags.contains(Flags::WRAPLINE) {
+            row[col].flags.remove(Flags::WRAPLINE);
+            row[last].flags.insert(Flags::WRAPLINE);
+        }
+    }
+}
+
+/// Serialize rows of cells to text with the escapes required to reproduce their attributes.
+///
+/// Rows are separated by `\r\n` and trailing empty cells of each row are skipped. The output
+/// assumes the receiving terminal starts out with default attributes and resets them at the end.
+pub fn region_to_escape(rows: &[Row<Cell>]) -> String {
+    region_to_escape_with_options(rows, EscapeOptions::default())
+}
+
+/// Serialize rows of cells like [`region_to_escape`], using the specified options.
+pub fn region_to_escape_with_options(rows: &[Row<Cell>], options: EscapeOptions) -> String {
+    let mut stream = EscapeStream::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        if i != 0 {
+            stream.out.push_str("\r\n");
+        }
+
+        let mut end = row.line_length();
+        let mut erase = None;
+
+        let last_cell = &row[Column(row.len() - 1)];
+        if options.erase_trailing_bg
+            && last_cell.bg != Color::Named(NamedColor::Background)
+            && *last_cell == Cell::from(last_cell.bg)
+        {
+            let run = row[..].iter().rev().take_while(|cell| *cell == last_cell).count();
+            end = Column(row.len() - run);
+            erase = Some(last_cell);
+        }
+
+        for cell in &row[..end] {
+            let placeholder = match options.empty_glyph {
+                Some(c) if cell.is_empty() => {
+                    let mut placeholder = cell.clone();
+                    placeholder.c = c;
+                    Some(placeholder)
+                },
+                _ => None,
+            };
+            stream.push_cell(placeholder.as_ref().unwrap_or(cell));
+        }
+
+        if let Some(cell) = erase {
+            cell.as_escape(&mut stream.out, &stream.last);
+            stream.out.push_str("\x1b[K");
+            stream.last = cell.clone();
+        }
+    }
+
+    stream.finish()
+}
+
+/// Incremental serializer for cells, remembering the attributes of the last written cell.
+///
+/// Like [`region_to_escape`], the output assumes the receiving terminal starts out with default
+/// attributes.
+#[derive(Default, Debug, Clone)]
+pub struct EscapeStream {
+    last: Cell,
+    out: String,
+
+    /// Column following the last written cell, if it is known.
+    next_column: Option<Column>,
+}
+
+impl EscapeStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a cell after the previous one.
+    ///
+    /// Wide char spacers are skipped, but still advance the column.
+    pub fn push_cell(&mut self, cell: &Cell) {
+        cell.write_cell(&mut self.out, &self.last);
+        if !cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+            self.last = cell.clone();
+        }
+
+        if let Some(column) = &mut self.next_column {
+            *column += 1;
+        }
+    }
+
+    /// Write a cell at `column` of the current line.
+    ///
+    /// Columns which were skipped since the previous cell are left untouched by moving the
+    /// cursor over them.
+    pub fn push_cell_at(&mut self, column: Column, cell: &Cell) {
+        if self.next_column != Some(column) {
+            let _ = write!(self.out, "\x1b[{}G", column.0 + 1);
+            self.next_column = Some(column);
+        }
+
+        self.push_cell(cell);
+    }
+
+    /// Switch to the default background color without changing any other attributes.
+    fn reset_background(&mut self) {
+        if self.last.bg != Color::Named(NamedColor::Background) {
+            let mut cell = self.last.clone();
+            cell.bg = Color::Named(NamedColor::Background);
+            cell.as_escape(&mut self.out, &self.last);
+            self.last = cell;
+        }
+    }
+
+    /// Reset all attributes and return the output.
+    pub fn finish(mut self) -> String {
+        // Hyperlinks are not affected by SGR 0.
+        if self.last.hyperlink().is_some() {
+            self.out.push_str("\x1b]8;;\x1b\\");
+        }
+        self.out.push_str("\x1b[0m");
+
+        self.out
+    }
+}
+
+/// Write the escape setting the window title and icon name to `title`.
+///
+/// Control characters would terminate the escape early or be interpreted by the receiving
+/// terminal, so they are removed from the title.
+pub fn title_escape(title: &str, out: &mut String) {
+    out.push_str("\x1b]0;");
+    out.extend(title.chars().filter(|c| !c.is_control()));
+    out.push('\x07');
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ClipboardType {
     Clipboard,
@@ -2533,12 +3572,14 @@ pub mod test {
 mod tests {
     use super::*;
 
+    use std::io;
     use std::mem;
+    use std::sync::Mutex;
 
     use crate::ansi::{self, CharsetIndex, Handler, StandardCharset};
     use crate::config::MockConfig;
     use crate::event::{Event, EventListener};
-    use crate::grid::{Grid, Scroll};
+    use crate::grid::{Grid, Row, Scroll};
     use crate::index::{Column, Line, Point, Side};
     use crate::selection::{Selection, SelectionType};
     use crate::term::cell::{Cell, Flags};
@@ -2548,6 +3589,46 @@ mod tests {
         fn send_event(&self, _event: Event) {}
     }
 
+    /// Event listener recording all events sent by the terminal.
+    #[derive(Clone, Default)]
+    struct EventCapture(Arc<Mutex<Vec<Event>>>);
+    impl EventListener for EventCapture {
+        fn send_event(&self, event: Event) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    impl EventCapture {
+        /// Remove all recorded events.
+        fn take(&self) -> Vec<Event> {
+            mem::take(&mut *self.0.lock().unwrap())
+        }
+
+        /// Remove all recorded events, returning the text written to the PTY.
+        fn take_pty_writes(&self) -> Vec<String> {
+            let events = self.take().into_iter();
+            events
+                .filter_map(|event| match event {
+                    Event::PtyWrite(text) => Some(text),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    /// Parse `bytes` into the terminal, returning the reply written by the parser.
+    fn feed<T: EventListener>(
+        term: &mut Term<T>,
+        parser: &mut ansi::Processor,
+        bytes: &str,
+    ) -> String {
+        let mut reply = Vec::new();
+        for byte in bytes.bytes() {
+            parser.advance(term, byte, &mut reply);
+        }
+        String::from_utf8(reply).unwrap()
+    }
+
     #[test]
     fn semantic_selection_works() {
         let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
@@ -2596,6 +3677,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn zerowidth_extraction_is_clamped() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+
+        term.input('e');
+        for _ in 0..1000 {
+            term.input('\u{0301}');
+        }
+
+        let line = term.screen_lines().0 - 1;
+        let text = term.line_to_string(line, Column(0)..Column(1), false);
+        assert_eq!(text.chars().count(), 1 + MAX_ZEROWIDTH_CHARS);
+    }
+
     #[test]
     fn line_selection_works() {
         let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
@@ -2825,6 +3921,916 @@ mod tests {
         assert_eq!(term.title, None);
     }
 
+    #[test]
+    fn cursor_color_resolves_through_palette() {
+        let mut config = MockConfig::default();
+        config.colors = serde_yaml::from_str("cursor: { cursor: '#ff0000' }").unwrap();
+
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let term = Term::new(&config, size, Mock);
+
+        let cursor = Color::Named(NamedColor::Cursor);
+        let rgb = RenderableCell::compute_fg_rgb(&config, &term.colors, cursor, Flags::empty());
+        assert_eq!(rgb, Rgb { r: 0xff, g: 0, b: 0 });
+
+        let rgb = RenderableCell::compute_bg_rgb(&term.colors, cursor);
+        assert_eq!(rgb, Rgb { r: 0xff, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn minimum_size_operations() {
+        // Cell size larger than the window, which clamps the grid to its minimum size.
+        let size = SizeInfo::new(5.0, 5.0, 10.0, 10.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        assert_eq!(term.cols(), Column(MIN_COLS));
+        assert_eq!(term.screen_lines(), Line(MIN_SCREEN_LINES));
+
+        // Wrapping input, including wide chars which only barely fit.
+        for c in "ab漢字c\u{0301}d".chars() {
+            term.input(c);
+        }
+        term.input('\t');
+        term.linefeed();
+        term.newline();
+
+        // Cursor movement past the edges.
+        term.goto(Line(5), Column(5));
+        term.move_forward(Column(10));
+        term.move_backward(Column(10));
+        term.move_up(Line(5));
+        term.move_down(Line(5));
+        term.move_backward_tabs(5);
+        term.move_forward_tabs(5);
+
+        // Editing around the cursor.
+        term.insert_blank(Column(10));
+        term.delete_chars(Column(10));
+        term.erase_chars(Column(10));
+        term.insert_blank_lines(Line(5));
+        term.delete_lines(Line(5));
+        term.scroll_up(Line(5));
+        term.scroll_down(Line(5));
+        term.set_scrolling_region(1, Some(10));
+        term.reverse_index();
+
+        // Selection spanning the entire grid.
+        term.selection = Some(Selection::new(
+            SelectionType::Simple,
+            Point { line: 0, col: Column(0) },
+            Side::Left,
+        ));
+        if let Some(selection) = &mut term.selection {
+            selection.update(Point { line: 0, col: Column(1) }, Side::Right);
+        }
+        assert!(term.selection_to_string().is_some());
+
+        // Vi mode motions in every direction.
+        term.toggle_vi_mode();
+        for motion in &[
+            ViMotion::Up,
+            ViMotion::Down,
+            ViMotion::Left,
+            ViMotion::Right,
+            ViMotion::Last,
+            ViMotion::Low,
+            ViMotion::SemanticRight,
+            ViMotion::WordRightEnd,
+            ViMotion::Bracket,
+        ] {
+            term.vi_motion(*motion);
+        }
+        term.toggle_vi_mode();
+        term.scroll_display(Scroll::PageUp);
+        let _ = term.renderable_cells(&MockConfig::default(), true).count();
+
+        // Reserving lines for the message bar can't shrink the grid further.
+        let mut reserved = size;
+        reserved.reserve_lines(5);
+        term.resize(reserved);
+        assert_eq!(term.screen_lines(), Line(MIN_SCREEN_LINES));
+
+        // Resize to an even smaller window and back.
+        term.resize(SizeInfo::new(1.0, 1.0, 10.0, 10.0, 0.0, 0.0, false));
+        term.resize(SizeInfo::new(100.0, 100.0, 10.0, 10.0, 0.0, 0.0, false));
+        term.resize(size);
+        term.input('x');
+    }
+
+    #[test]
+    fn size_info_minimum_dimensions() {
+        let mut size = SizeInfo::new(25.0, 25.0, 10.0, 10.0, 0.0, 0.0, false);
+        assert_eq!(size.cols(), Column(2));
+        assert_eq!(size.screen_lines(), Line(2));
+
+        size.set_minimum_dimensions(Column(10), Line(1));
+        assert_eq!(size.cols(), Column(10));
+        assert_eq!(size.screen_lines(), Line(2));
+    }
+
+    #[test]
+    fn region_to_escape_round_trip() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut grid: Grid<Cell> = Grid::new(Line(3), Column(5), 0);
+
+        grid[Line(0)][Column(0)].c = 'a';
+        grid[Line(0)][Column(1)].c = 'b';
+        grid[Line(0)][Column(1)].flags = Flags::BOLD | Flags::UNDERLINE;
+        grid[Line(0)][Column(2)].c = 'c';
+        grid[Line(0)][Column(2)].fg = Color::Named(NamedColor::Red);
+        grid[Line(0)][Column(2)].bg = Color::Indexed(42);
+        grid[Line(1)][Column(2)].c = 'd';
+        grid[Line(1)][Column(2)].flags = Flags::ITALIC;
+        grid[Line(2)][Column(0)].c = '字';
+        grid[Line(2)][Column(0)].flags = Flags::WIDE_CHAR;
+        grid[Line(2)][Column(1)].flags = Flags::WIDE_CHAR_SPACER;
+        grid[Line(2)][Column(2)].c = 'e';
+
+        let rows = (0..3).map(|line| grid[Line(line)].clone()).collect::<Vec<_>>();
+        let text = region_to_escape(&rows);
+        assert!(text.ends_with("\x1b[0m"));
+
+        let mut parser = ansi::Processor::new();
+        for byte in text.bytes() {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+
+        for line in 0..3 {
+            for col in 0..5 {
+                let (expected, actual) =
+                    (&grid[Line(line)][Column(col)], &term.grid[Line(line)][Column(col)]);
+                assert_eq!(actual.c, expected.c);
+                assert_eq!(actual.fg, expected.fg);
+                assert_eq!(actual.bg, expected.bg);
+                assert_eq!(actual.flags, expected.flags);
+            }
+        }
+        assert_eq!(term.grid.cursor.template, Cell::default());
+    }
+
+    #[test]
+    fn escape_stream_matches_manual_diffing() {
+        let mut bold = Cell::default();
+        bold.c = 'a';
+        bold.flags = Flags::BOLD;
+        let mut red = bold.clone();
+        red.c = 'b';
+        red.fg = Color::Named(NamedColor::Red);
+        let mut wide = Cell::default();
+        wide.c = '字';
+        wide.flags = Flags::WIDE_CHAR;
+        let mut spacer = Cell::default();
+        spacer.flags = Flags::WIDE_CHAR_SPACER;
+        let mut plain = Cell::default();
+        plain.c = 'c';
+        let cells = [bold, red, wide, spacer, plain];
+
+        let mut stream = EscapeStream::new();
+        let mut expected = String::new();
+        let mut last = Cell::default();
+        for cell in &cells {
+            stream.push_cell(cell);
+
+            if !cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                cell.as_escape(&mut expected, &last);
+                expected.push(cell.c);
+                last = cell.clone();
+            }
+        }
+        expected.push_str("\x1b[0m");
+
+        assert_eq!(stream.finish(), expected);
+        assert_eq!(expected, "\x1b[1ma\x1b[31mb\x1b[22;39m字c\x1b[0m");
+    }
+
+    #[test]
+    fn escape_stream_moves_over_gaps() {
+        let mut a = Cell::default();
+        a.c = 'a';
+        let mut b = a.clone();
+        b.c = 'b';
+        b.flags = Flags::ITALIC;
+
+        let mut stream = EscapeStream::new();
+        stream.push_cell_at(Column(2), &a);
+        stream.push_cell_at(Column(3), &a);
+        stream.push_cell_at(Column(7), &b);
+        stream.push_cell(&a);
+        stream.push_cell_at(Column(0), &a);
+        assert_eq!(stream.finish(), "\x1b[3Gaa\x1b[8G\x1b[3mb\x1b[23ma\x1b[1Ga\x1b[0m");
+
+        // Skipped columns keep their content.
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+        let mut stream = EscapeStream::new();
+        stream.push_cell_at(Column(1), &b);
+        stream.push_cell_at(Column(4), &a);
+        for byte in "xxxxxx\r".bytes().chain(stream.finish().bytes()) {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+
+        let text: String = term.grid[Line(0)][..Column(6)].iter().map(|cell| cell.c).collect();
+        assert_eq!(text, "xbxxax");
+        assert_eq!(term.grid[Line(0)][Column(1)].flags, Flags::ITALIC);
+        assert_eq!(term.grid[Line(0)][Column(4)].flags, Flags::empty());
+    }
+
+    #[test]
+    fn title_escape_sanitizes_control_chars() {
+        let mut text = String::new();
+        title_escape("vim README.md", &mut text);
+        assert_eq!(text, "\x1b]0;vim README.md\x07");
+
+        text.clear();
+        title_escape("a\x07b\x1b]2;c\x1b\\d\u{9c}e\nf", &mut text);
+        assert_eq!(text, "\x1b]0;ab]2;c\\def\x07");
+
+        // The title is parsed back unchanged.
+        let mut term = Term::new(
+            &MockConfig::default(),
+            SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false),
+            Mock,
+        );
+        let mut parser = ansi::Processor::new();
+        text.clear();
+        title_escape("✓ Tests", &mut text);
+        for byte in text.bytes() {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+        assert_eq!(term.title.as_deref(), Some("✓ Tests"));
+    }
+
+    #[test]
+    fn region_to_escape_erases_trailing_bg() {
+        let mut row = Row::<Cell>::new(Column(6));
+        row[Column(0)].c = 'a';
+        for col in 2..6 {
+            row[Column(col)].bg = Color::Indexed(4);
+        }
+
+        let rows = [row];
+        assert_eq!(region_to_escape(&rows), "a\x1b[0m");
+
+        let options = EscapeOptions { erase_trailing_bg: true, ..EscapeOptions::default() };
+        let text = region_to_escape_with_options(&rows, options);
+        assert_eq!(text, "a \x1b[48;5;4m\x1b[K\x1b[0m");
+
+        // Applying the escapes reproduces the highlight.
+        let size = SizeInfo::new(18.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+        for byte in text.bytes() {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+        assert_eq!(term.grid[Line(0)], rows[0]);
+
+        // Rows without a uniform background run are not affected.
+        let mut row = rows[0].clone();
+        row[Column(5)].flags.insert(Flags::UNDERLINE);
+        assert_eq!(region_to_escape_with_options(&[row], options).matches("\x1b[K").count(), 0);
+    }
+
+    #[test]
+    fn region_to_escape_empty_glyph() {
+        let mut row = Row::<Cell>::new(Column(8));
+        row[Column(0)].c = 'a';
+        row[Column(2)].flags = Flags::BOLD;
+        row[Column(3)].c = 'b';
+        row[Column(4)].c = '\t';
+        row[Column(5)].c = 'c';
+
+        let options = EscapeOptions { empty_glyph: Some('·'), ..EscapeOptions::default() };
+        let text = region_to_escape_with_options(&[row.clone()], options);
+        assert_eq!(text, "a·\x1b[1m·\x1b[22mb·c\x1b[0m");
+
+        // Cells with visible attributes are not empty.
+        row[Column(1)].bg = Color::Indexed(4);
+        let text = region_to_escape_with_options(&[row], options);
+        assert_eq!(text, "a\x1b[48;5;4m \x1b[1;49m·\x1b[22mb·c\x1b[0m");
+    }
+
+    #[test]
+    fn grid_invariants() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let narrow = SizeInfo::new(6.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+
+        let check = |resize: Option<SizeInfo>, before: &str, after: &str| {
+            let mut term = Term::new(&MockConfig::default(), size, Mock);
+            let mut parser = ansi::Processor::new();
+
+            if let Some(size) = resize {
+                term.resize(size);
+            }
+            for byte in before.bytes() {
+                parser.advance(&mut term, byte, &mut io::sink());
+            }
+
+            term.resize(size);
+            for byte in after.bytes() {
+                parser.advance(&mut term, byte, &mut io::sink());
+            }
+
+            term.verify_invariants();
+        };
+
+        // Overwriting either half of a wide char.
+        check(None, "字\ra", "");
+        check(None, "a字\x1b[3Gb", "");
+
+        // Wide char pushed out of the line in insert mode.
+        check(None, "abcde字\r\x1b[4hx", "");
+
+        // Clearing the spacer of a wide char.
+        check(None, "abcde字\x1b[J", "");
+
+        // Wrap flag shifted by deleting characters.
+        check(None, "abcdefgh\x1b[H\x1b[P", "");
+
+        // Pending wrap after moving the cursor backwards.
+        check(None, "abcdefg\x1b[Zh", "");
+
+        // Pending wrap of the cursor and the saved cursor after growing without reflow.
+        check(Some(narrow), "\x1b[?1049hab", "c");
+        check(Some(narrow), "\x1b[?1049hab\x1b7", "\x1b8c");
+    }
+
+    #[test]
+    fn dump_grid_round_trip() {
+        let size = SizeInfo::new(21.0, 15.0, 3.0, 3.0, 0.0, 0.0, false);
+        let colors = [
+            Color::Named(NamedColor::Red),
+            Color::Named(NamedColor::BrightBlue),
+            Color::Indexed(100),
+            Color::Spec(Rgb { r: 10, g: 20, b: 30 }),
+        ];
+        let flags = [Flags::BOLD, Flags::ITALIC, Flags::UNDERLINE, Flags::INVERSE, Flags::DIM];
+
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move |max: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as usize % max
+        };
+
+        for _ in 0..200 {
+            let mut term = Term::new(&MockConfig::default(), size, Mock);
+            let mut parser = ansi::Processor::new();
+
+            // Push three lines into the scrollback history.
+            for _ in 0..7 {
+                parser.advance(&mut term, b'\n', &mut io::sink());
+            }
+
+            let total_lines = term.grid.total_lines();
+            let cols = term.cols().0;
+            for index in 0..total_lines {
+                let row = &mut term.grid[index];
+
+                let mut col = 0;
+                while col < cols {
+                    let cell = &mut row[Column(col)];
+                    match random(6) {
+                        0 | 1 => (),
+                        2 => cell.bg = colors[random(colors.len())],
+                        3 if col + 1 < cols => {
+                            cell.c = '字';
+                            cell.flags = Flags::WIDE_CHAR;
+                            row[Column(col + 1)].flags = Flags::WIDE_CHAR_SPACER;
+                            col += 1;
+                        },
+                        _ => {
+                            cell.c = (b'a' + random(26) as u8) as char;
+                            cell.fg = colors[random(colors.len())];
+                            cell.flags = flags[random(flags.len())];
+                            if random(4) == 0 {
+                                cell.push_zerowidth('\u{301}');
+                            }
+                        },
+                    }
+                    col += 1;
+                }
+
+                // The wrap of the last line can't be reproduced.
+                if index != 0 && random(3) == 0 {
+                    row[Column(cols - 1)].flags.insert(Flags::WRAPLINE);
+                }
+            }
+
+            let text = term.dump_grid(Line(0)..=Line(total_lines - 1));
+            assert!(text.ends_with("\x1b[0m"));
+
+            let mut copy = Term::new(&MockConfig::default(), size, Mock);
+            for byte in text.bytes() {
+                parser.advance(&mut copy, byte, &mut io::sink());
+            }
+
+            assert_eq!(copy.grid.total_lines(), total_lines, "{:?}", text);
+            for index in 0..total_lines {
+                assert_eq!(copy.grid[index][..], term.grid[index][..], "{:?}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn hyperlinks_share_allocation() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        // Reopen the same hyperlink for every cell of the first row.
+        for _ in 0..term.cols().0 {
+            for byte in "\x1b]8;;https://example.org\x1b\\x".bytes() {
+                parser.advance(&mut term, byte, &mut io::sink());
+            }
+        }
+
+        let first = term.grid[Line(0)][Column(0)].hyperlink().unwrap().clone();
+        for cell in &term.grid[Line(0)][..] {
+            assert!(Arc::ptr_eq(cell.hyperlink().unwrap(), &first));
+        }
+
+        // Every cell, the cursor template and the local handle share the allocation.
+        assert_eq!(Arc::strong_count(&first), term.cols().0 + 2);
+    }
+
+    #[test]
+    fn cursor_blinking() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        term.reset_cursor_blink(start);
+        assert!(term.cursor_blink());
+
+        // Visibility only changes once per interval.
+        assert!(!term.advance_cursor_blink(at(100)));
+        assert!(term.cursor_blink());
+        assert!(term.advance_cursor_blink(at(740)));
+        assert!(!term.cursor_blink());
+        assert!(!term.advance_cursor_blink(at(800)));
+        assert!(term.advance_cursor_blink(at(1510)));
+        assert!(term.cursor_blink());
+        assert!(term.advance_cursor_blink(at(2250)));
+        assert!(!term.cursor_blink());
+
+        // Input shows the cursor and restarts the cycle.
+        term.reset_cursor_blink(at(2300));
+        assert!(term.cursor_blink());
+        assert!(!term.advance_cursor_blink(at(2500)));
+        assert!(term.advance_cursor_blink(at(3050)));
+    }
+
+    #[test]
+    fn cursor_visibility() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let config = MockConfig::default();
+        let events = EventCapture::default();
+        let mut term = Term::new(&config, size, events.clone());
+        let mut parser = ansi::Processor::new();
+        let mut advance = |term: &mut Term<EventCapture>, escape: &str| {
+            feed(term, &mut parser, escape);
+            let events = events.take();
+            events.iter().filter(|event| matches!(event, Event::CursorBlinkingChange(_))).count()
+        };
+
+        // Showing a visible cursor does not restart blinking.
+        let start = Instant::now();
+        term.reset_cursor_blink(start);
+        assert!(term.advance_cursor_blink(start + Duration::from_millis(750)));
+        assert_eq!(advance(&mut term, "\x1b[?25h"), 0);
+        assert!(!term.cursor_blink());
+
+        // Hiding the cursor notifies listeners to stop blinking.
+        assert_eq!(advance(&mut term, "\x1b[?25l\x1b[?25l"), 1);
+        assert!(!term.cursor_visible());
+        assert_eq!(term.renderable_cursor(&config, true).key.shape, CursorShape::Hidden);
+
+        // Showing it again restarts the cycle in the visible phase.
+        assert_eq!(advance(&mut term, "\x1b[?25h"), 1);
+        assert!(term.cursor_visible());
+        assert!(term.cursor_blink());
+        assert!(!term.advance_cursor_blink(Instant::now() + Duration::from_millis(100)));
+
+        // The vi mode cursor is visible even when the terminal cursor is hidden.
+        advance(&mut term, "\x1b[?25l");
+        term.toggle_vi_mode();
+        assert!(term.cursor_visible());
+        assert_ne!(term.renderable_cursor(&config, true).key.shape, CursorShape::Hidden);
+        term.toggle_vi_mode();
+        assert!(!term.cursor_visible());
+    }
+
+    #[test]
+    fn blink_attributes() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+
+        term.terminal_attribute(Attr::BlinkSlow);
+        assert_eq!(term.grid.cursor.template.flags, Flags::BLINKING);
+
+        term.terminal_attribute(Attr::CancelBlink);
+        assert_eq!(term.grid.cursor.template.flags, Flags::empty());
+
+        term.terminal_attribute(Attr::BlinkFast);
+        assert_eq!(term.grid.cursor.template.flags, Flags::RAPID_BLINKING);
+
+        term.terminal_attribute(Attr::BlinkSlow);
+        assert_eq!(term.grid.cursor.template.flags, Flags::BLINKING);
+
+        term.terminal_attribute(Attr::BlinkFast);
+        term.terminal_attribute(Attr::Reset);
+        assert_eq!(term.grid.cursor.template.flags, Flags::empty());
+    }
+
+    #[test]
+    fn kitty_graphics() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut config = MockConfig::default();
+        config.kitty_graphics = true;
+        let events = EventCapture::default();
+        let mut term = Term::new(&config, size, events.clone());
+        let mut parser = ansi::Processor::new();
+
+        // Transmit and display a 6x4 image, which covers 2x2 cells.
+        let payload = base64::encode(&[255; 6 * 4 * 4][..]);
+        feed(&mut term, &mut parser, &format!("a\x1b_Ga=T,f=32,s=6,v=4,i=7;{}\x1b\\b", payload));
+
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'a');
+        for (line, col) in &[(0, 1), (0, 2), (1, 1), (1, 2)] {
+            let graphic = term.grid[Line(*line)][Column(*col)].graphic().unwrap();
+            assert_eq!((graphic.line, graphic.col), (*line as u32, *col as u32 - 1));
+            assert_eq!(graphic.image.id, 7);
+        }
+        assert_eq!(term.grid[Line(1)][Column(3)].c, 'b');
+        assert_eq!(events.take_pty_writes(), ["\x1b_Gi=7;OK\x1b\\"]);
+
+        // Displaying an unknown image fails.
+        feed(&mut term, &mut parser, "\x1b_Ga=p,i=8\x1b\\");
+        assert_eq!(events.take_pty_writes(), ["\x1b_Gi=8;ENOENT:image not found\x1b\\"]);
+
+        // Deleting by ID removes all placements.
+        feed(&mut term, &mut parser, "\x1b_Ga=d,d=I,i=7\x1b\\");
+        assert!(term.grid[Line(0)][Column(1)].graphic().is_none());
+        assert!(term.images.get(7).is_none());
+        assert!(events.take_pty_writes().is_empty());
+
+        // Graphics commands are ignored when the protocol is disabled.
+        config.kitty_graphics = false;
+        term.update_config(&config);
+        feed(&mut term, &mut parser, &format!("\x1b_Ga=T,f=32,s=6,v=4,i=7;{}\x1b\\", payload));
+        assert!(term.grid[Line(1)][Column(4)].graphic().is_none());
+        assert!(events.take_pty_writes().is_empty());
+    }
+
+    #[test]
+    fn xtgettcap() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let events = EventCapture::default();
+        let mut term = Term::new(&MockConfig::default(), size, events.clone());
+        let mut parser = ansi::Processor::new();
+
+        // Query TN, colors, RGB, kcuu1, an unknown name and invalid hex.
+        feed(&mut term, &mut parser, "\x1bP+q544E;636f6c6f7273,524742;6B63757531;666F6F;ZZ\x1b\\");
+
+        assert_eq!(
+            events.take_pty_writes(),
+            [
+                "\x1bP1+r544E=616C61637269747479\x1b\\",
+                "\x1bP1+r636F6C6F7273=323536\x1b\\",
+                "\x1bP1+r524742\x1b\\",
+                "\x1bP1+r6B63757531=1B4F41\x1b\\",
+                "\x1bP0+r666F6F\x1b\\",
+                "\x1bP0+rZZ\x1b\\",
+            ]
+        );
+    }
+
+    #[test]
+    fn sixel_graphics() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut config = MockConfig::default();
+        config.sixel_graphics = true;
+        let mut term = Term::new(&config, size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        // Display a 4x6 image, which covers 2x2 cells.
+        feed(&mut term, &mut parser, "a\x1bP9q#1;2;100;0;0!4~\x1b\\b");
+
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'a');
+        for (line, col) in &[(0, 1), (0, 2), (1, 1), (1, 2)] {
+            let graphic = term.grid[Line(*line)][Column(*col)].graphic().unwrap();
+            assert_eq!((graphic.line, graphic.col), (*line as u32, *col as u32 - 1));
+            assert_eq!((graphic.image.width, graphic.image.height), (4, 6));
+        }
+        assert_eq!(term.grid[Line(2)][Column(1)].c, 'b');
+
+        // Sixel images are ignored when they are disabled.
+        config.sixel_graphics = false;
+        term.update_config(&config);
+        feed(&mut term, &mut parser, "\x1bP9q!4~\x1b\\");
+        assert!(term.grid[Line(2)][Column(2)].graphic().is_none());
+    }
+
+    #[test]
+    fn reverse_video() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let config = MockConfig::default();
+        let mut term = Term::new(&config, size, Mock);
+        let mut parser = ansi::Processor::new();
+        feed(&mut term, &mut parser, "a\x1b[7mb\x1b[?5h");
+
+        let foreground = term.colors[NamedColor::Foreground];
+        let background = term.colors[NamedColor::Background];
+        assert_eq!(term.background_color(), foreground);
+
+        // Cells are inverted unless they're inverse themselves, without changing the grid.
+        let cells = term.renderable_cells(&config, false).collect::<Vec<_>>();
+        assert_eq!((cells[0].fg, cells[0].bg, cells[0].bg_alpha), (background, foreground, 1.0));
+        assert_eq!((cells[1].fg, cells[1].bg, cells[1].bg_alpha), (foreground, background, 1.0));
+
+        // Empty cells are drawn too.
+        let last = cells.last().unwrap();
+        assert_eq!((last.line, last.column), (term.screen_lines() - 1, term.cols() - 1));
+        assert_eq!((last.fg, last.bg, last.bg_alpha), (background, foreground, 1.0));
+        assert!(!term.grid[Line(0)][Column(0)].flags.contains(Flags::INVERSE));
+
+        feed(&mut term, &mut parser, "\x1b[?5l");
+        assert_eq!(term.background_color(), background);
+        let cells = term.renderable_cells(&config, false).collect::<Vec<_>>();
+        assert!(cells.iter().all(|cell| cell.column < Column(2) || cell.bg_alpha == 0.0));
+        assert_eq!((cells[0].fg, cells[0].bg, cells[0].bg_alpha), (foreground, background, 0.0));
+        assert_eq!((cells[1].fg, cells[1].bg, cells[1].bg_alpha), (background, foreground, 1.0));
+    }
+
+    #[test]
+    fn osc52_clipboard_access() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let events = EventCapture::default();
+        let mut config = MockConfig::default();
+        let mut term = Term::new(&config, size, events.clone());
+        let mut parser = ansi::Processor::new();
+        let mut advance = |term: &mut Term<EventCapture>| {
+            feed(term, &mut parser, "\x1b]52;c;YWJj\x07\x1b]52;p;?\x07");
+            let mut events = events.take();
+            events.retain(|event| {
+                matches!(event, Event::ClipboardStore(..) | Event::ClipboardLoad(..))
+            });
+            events
+        };
+
+        // Only writes are allowed by default.
+        match advance(&mut term).as_slice() {
+            [Event::ClipboardStore(ClipboardType::Clipboard, text)] => assert_eq!(text, "abc"),
+            events => panic!("unexpected events: {:?}", events),
+        }
+
+        config.selection.osc52 = Osc52::CopyPaste;
+        term.update_config(&config);
+        match advance(&mut term).as_slice() {
+            [Event::ClipboardStore(..), Event::ClipboardLoad(ClipboardType::Selection, format)] => {
+                assert_eq!(format("abc"), "\x1b]52;p;YWJj\x07");
+                assert_eq!(format(""), "\x1b]52;p;\x07");
+            },
+            events => panic!("unexpected events: {:?}", events),
+        }
+
+        config.selection.osc52 = Osc52::Disabled;
+        term.update_config(&config);
+        assert!(advance(&mut term).is_empty());
+    }
+
+    #[test]
+    fn synchronized_updates() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?2026$p"), "\x1b[?2026;2$y");
+
+        feed(&mut term, &mut parser, "\x1b[?2026h");
+        assert!(term.sync_update_timeout().unwrap() > Instant::now());
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?2026$p"), "\x1b[?2026;1$y");
+
+        feed(&mut term, &mut parser, "\x1b[?2026l");
+        assert_eq!(term.sync_update_timeout(), None);
+
+        // Switching screens or resetting the terminal ends the update.
+        feed(&mut term, &mut parser, "\x1b[?2026h\x1b[?1049h");
+        assert_eq!(term.sync_update_timeout(), None);
+        feed(&mut term, &mut parser, "\x1b[?2026h\x1bc");
+        assert_eq!(term.sync_update_timeout(), None);
+
+        // Unknown modes are reported as not recognized.
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?9999$p"), "\x1b[?9999;0$y");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[4$p"), "\x1b[4;2$y");
+    }
+
+    #[test]
+    fn modify_other_keys() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;0m");
+
+        feed(&mut term, &mut parser, "\x1b[>4;1m");
+        assert!(term.mode().contains(TermMode::MODIFY_OTHER_KEYS));
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;1m");
+
+        feed(&mut term, &mut parser, "\x1b[>4;2m");
+        assert!(!term.mode().contains(TermMode::MODIFY_OTHER_KEYS));
+        assert!(term.mode().contains(TermMode::MODIFY_ALL_KEYS));
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;2m");
+
+        // Disabling or resetting the resource restores the regular encoding.
+        feed(&mut term, &mut parser, "\x1b[>4n");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;0m");
+        feed(&mut term, &mut parser, "\x1b[>4;2m\x1b[>4m");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;0m");
+
+        // Other resources are ignored.
+        feed(&mut term, &mut parser, "\x1b[>1;2m");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;0m");
+
+        feed(&mut term, &mut parser, "\x1b[>4;1m\x1bc");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?4m"), "\x1b[>4;0m");
+    }
+
+    #[test]
+    fn snapshot() {
+        let size = SizeInfo::new(60.0, 15.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        // Scrollback history is not part of the snapshot.
+        feed(&mut term, &mut parser, "\x1b]2;snap\x07history\n\n\n\n\n\x1b[H");
+        feed(&mut term, &mut parser, "one\r\n\r\n  two   \x1b[1;3H");
+
+        let snapshot = term.snapshot();
+        assert_eq!(snapshot.lines.len(), 5);
+        assert!(snapshot.lines.iter().all(|line| line.len() == 20));
+        assert_eq!(snapshot.cursor, Point::new(Line(0), Column(2)));
+        assert_eq!(snapshot.title, "snap");
+        assert_eq!(snapshot.to_plain_text(), "one\n\n  two\n\n");
+
+        // Attributes, wide chars and zerowidth characters are preserved.
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        feed(&mut term, &mut parser, "\x1b[1mbold\x1b[0m \u{4e2d}e\u{301}");
+
+        let snapshot = term.snapshot();
+        assert_eq!(snapshot.title, "");
+        assert_eq!(snapshot.to_plain_text(), "bold \u{4e2d}e\u{301}\n\n\n\n");
+        assert_eq!(
+            snapshot.to_ansi(),
+            "\x1b[1mbold\x1b[22m \u{4e2d}e\u{301}\r\n\r\n\r\n\r\n\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn win32_input_mode() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?9001$p"), "\x1b[?9001;2$y");
+
+        feed(&mut term, &mut parser, "\x1b[?9001h");
+        assert!(term.mode().contains(TermMode::WIN32_INPUT));
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?9001$p"), "\x1b[?9001;1$y");
+
+        feed(&mut term, &mut parser, "\x1b[?9001l");
+        assert!(!term.mode().contains(TermMode::WIN32_INPUT));
+
+        feed(&mut term, &mut parser, "\x1b[?9001h\x1bc");
+        assert!(!term.mode().contains(TermMode::WIN32_INPUT));
+    }
+
+    #[test]
+    fn keyboard_mode_stack() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+
+        // Flags are mirrored in the terminal mode.
+        feed(&mut term, &mut parser, "\x1b[>1u");
+        assert!(term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+        feed(&mut term, &mut parser, "\x1b[>11u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?11u");
+        assert!(term.mode().contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
+
+        // Changing flags only affects the top of the stack.
+        feed(&mut term, &mut parser, "\x1b[=16;2u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?27u");
+        feed(&mut term, &mut parser, "\x1b[=3;3u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?24u");
+        feed(&mut term, &mut parser, "\x1b[=1u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?1u");
+
+        feed(&mut term, &mut parser, "\x1b[<u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?1u");
+        feed(&mut term, &mut parser, "\x1b[<u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+
+        // Popping more entries than were pushed resets the flags.
+        feed(&mut term, &mut parser, "\x1b[>1u\x1b[>2u\x1b[<5u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+
+        // The oldest entries are dropped once the stack is full.
+        for flags in 1..=7 {
+            feed(&mut term, &mut parser, &format!("\x1b[>{}u", flags));
+        }
+        feed(&mut term, &mut parser, "\x1b[<4u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?3u");
+        feed(&mut term, &mut parser, "\x1b[<u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?2u");
+        feed(&mut term, &mut parser, "\x1b[<u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+
+        // Resetting the terminal clears the stack.
+        feed(&mut term, &mut parser, "\x1b[>1u\x1b[>8u\x1bc");
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+        feed(&mut term, &mut parser, "\x1b[<u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+
+        // Restoring the cursor is not affected.
+        feed(&mut term, &mut parser, "\x1b[>1u\x1b[u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?1u");
+    }
+
+    #[test]
+    fn keyboard_mode_per_screen() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        feed(&mut term, &mut parser, "\x1b[>1u\x1b[>3u");
+
+        // The alternate screen starts without any flags.
+        feed(&mut term, &mut parser, "\x1b[?1049h");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+        feed(&mut term, &mut parser, "\x1b[>8u\x1b[>31u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?31u");
+
+        // Leaving the alternate screen restores the primary screen's stack.
+        feed(&mut term, &mut parser, "\x1b[?1049l");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?3u");
+        assert!(!term.mode().contains(TermMode::REPORT_ALL_KEYS_AS_ESC));
+
+        // The alternate screen's stack is reset on exit.
+        feed(&mut term, &mut parser, "\x1b[?1049h");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?0u");
+        feed(&mut term, &mut parser, "\x1b[<u\x1b[?1049l");
+
+        feed(&mut term, &mut parser, "\x1b[<u");
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?u"), "\x1b[?1u");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn resize_sync_expires() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        // Further resizes do not extend the deadline.
+        term.start_resize_sync();
+        let timeout = term.sync_update_timeout().unwrap();
+        assert!(timeout <= Instant::now() + RESIZE_SYNC_TIMEOUT);
+        term.start_resize_sync();
+        assert_eq!(term.sync_update_timeout(), Some(timeout));
+
+        // The internal update is not reported to applications.
+        assert_eq!(feed(&mut term, &mut parser, "\x1b[?2026$p"), "\x1b[?2026;2$y");
+
+        // A resize after the deadline starts a new update.
+        std::thread::sleep(RESIZE_SYNC_TIMEOUT);
+        assert!(term.sync_update_timeout().unwrap() <= Instant::now());
+        term.start_resize_sync();
+        assert!(term.sync_update_timeout().unwrap() > timeout);
+
+        term.stop_sync_update();
+        assert_eq!(term.sync_update_timeout(), None);
+    }
+
+    #[test]
+    fn xtversion() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, Mock);
+        let mut parser = ansi::Processor::new();
+
+        // Replies keep the order of the queries.
+        let reply = feed(&mut term, &mut parser, "\x1b[>q\x1b[c\x1b[>0q\x1b[>1q");
+
+        let version = format!("\x1bP>|alacritty {}\x1b\\", env!("CARGO_PKG_VERSION"));
+        assert_eq!(reply, format!("{}\x1b[?6c{}", version, version));
+    }
+
     #[test]
     fn parse_cargo_version() {
         assert!(version_number(env!("CARGO_PKG_VERSION")) >= 10_01);
@@ -2843,9 +4849,11 @@ mod benches {
     use std::fs;
     use std::mem;
 
+    use crate::ansi::Handler;
     use crate::config::MockConfig;
     use crate::event::{Event, EventListener};
-    use crate::grid::Grid;
+    use crate::grid::{Dimensions, Grid};
+    use crate::index::{Column, Line};
 
     use super::cell::Cell;
     use super::{SizeInfo, Term};
@@ -2893,4 +4901,24 @@ mod benches {
             }
         })
     }
+
+    /// Benchmark for writing printable ASCII to the terminal.
+    ///
+    /// This is the most common workload and exercises the ASCII fast path in `Term::input`.
+    #[bench]
+    fn input_ascii(b: &mut test::Bencher) {
+        let size = SizeInfo::new(800., 600., 8., 16., 0., 0., false);
+        let mut terminal = Term::new(&MockConfig::default(), size, Mock);
+
+        let text: Vec<char> = "The quick brown fox jumps over the lazy dog. ".chars().collect();
+
+        b.iter(|| {
+            for line in 0..terminal.screen_lines().0 {
+                terminal.goto(Line(line), Column(0));
+                for &c in text.iter().cycle().take(terminal.cols().0 - 1) {
+                    terminal.input(test::black_box(c));
+                }
+            }
+        })
+    }
 }
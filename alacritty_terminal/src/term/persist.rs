@@ -0,0 +1,370 @@
+//! Persistence of the scrollback history across terminal crashes.
+//!
+//! Every terminal periodically writes its primary grid to its own file in the persistence
+//! directory, named after the ID of the process owning it. The file is removed again on clean
+//! exit, so only crashed processes leave their history behind. When a new terminal is created, it
+//! claims one of the files whose owner is no longer running and restores its rows.
+//!
+//! The file starts with a fixed size header, followed by the run-length encoded rows of the grid
+//! from the oldest to the newest:
+//!
+//! ```notrust
+//! ┌──────────┬─────────┬─────────┬────────────────┬─────────────────────┐
+//! │ ALACRTSB │ version │ columns │ payload length │ payload (rows)  ... │
+//! │ 8 bytes  │ u32 LE  │ u32 LE  │ u64 LE         │                     │
+//! └──────────┴─────────┴─────────┴────────────────┴─────────────────────┘
+//! ```
+
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+use log::info;
+
+use crate::grid::{CompressedRow, Dimensions, Grid, Row};
+use crate::index::Column;
+use crate::term::cell::{Cell, Flags};
+
+/// Magic bytes identifying a scrollback file.
+const MAGIC: &[u8; 8] = b"ALACRTSB";
+
+/// Version of the file format, bumped whenever the serialized cell layout changes.
+const VERSION: u32 = 4;
+
+/// Size of the file header in bytes.
+const HEADER_LEN: usize = 24;
+
+/// File extension of scrollback files.
+const EXTENSION: &str = "scrollback";
+
+/// Rows of a grid, captured for writing them to disk without holding the terminal lock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    cols: Column,
+    rows: Vec<CompressedRow<Cell>>,
+}
+
+impl Snapshot {
+    /// Capture all rows of a grid.
+    ///
+    /// Empty lines at the bottom of the screen are not persisted.
+    pub fn new(grid: &Grid<Cell>) -> Self {
+        let total_lines = grid.total_lines();
+        let bottom = (0..total_lines).find(|&index| !grid[index].is_clear()).unwrap_or(total_lines);
+        let rows = (bottom..total_lines).rev().map(|index| grid[index].compress()).collect();
+        Self { cols: grid.cols(), rows }
+    }
+}
+
+/// Scrollback file of the current process in `dir`.
+pub fn file(dir: &Path) -> PathBuf {
+    dir.join(format!("{}.{}", process::id(), EXTENSION))
+}
+
+/// Take over the most recent scrollback file in `dir` left behind by a process which is no longer
+/// running, moving it to the [`file`] of the current process.
+///
+/// Returns `false` if there was no file to claim.
+pub fn claim_orphan(dir: &Path) -> io::Result<bool> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    let mut orphans = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let pid = match owner(&path) {
+            Some(pid) if pid != process::id() && !is_running(pid) => pid,
+            _ => continue,
+        };
+
+        let modified = path.metadata().and_then(|metadata| metadata.modified());
+        orphans.push((modified.unwrap_or(SystemTime::UNIX_EPOCH), pid, path));
+    }
+
+    // Another terminal starting at the same time might claim the same file first.
+    orphans.sort();
+    let target = file(dir);
+    for (_, pid, path) in orphans.into_iter().rev() {
+        match fs::rename(&path, &target) {
+            Ok(()) => {
+                info!("Claiming scrollback of terminated process {}", pid);
+                return Ok(true);
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(false)
+}
+
+/// Write a snapshot to `path`.
+///
+/// The data is written to a temporary file first, so a crash while writing never leaves behind a
+/// partially written file.
+pub fn save(path: &Path, snapshot: &Snapshot) -> io::Result<()> {
+    let payload = bincode::serialize(&snapshot.rows).map_err(invalid_data)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp_path = path.to_owned().into_os_string();
+    tmp_path.push(".tmp");
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&header(snapshot.cols, payload.len()))?;
+    file.write_all(&payload)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(tmp_path, path)
+}
+
+/// Read the rows persisted at `path`, from the oldest to the newest.
+///
+/// Rows written by a terminal with a different width are truncated or padded to `cols`. A missing
+/// file is treated as an empty history.
+pub fn restore(path: &Path, cols: Column) -> io::Result<Vec<Row<Cell>>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let (saved_cols, payload) = parse_header(&bytes)?;
+
+    let compressed: Vec<CompressedRow<Cell>> =
+        bincode::deserialize(payload).map_err(invalid_data)?;
+    let mut rows = compressed.iter().map(|row| row.decompress(saved_cols.0)).collect::<Vec<_>>();
+
+    if saved_cols != cols {
+        info!("Migrating persisted scrollback from {} to {} columns", saved_cols, cols);
+        for row in &mut rows {
+            resize_row(row, cols);
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Discard the history persisted at `path`.
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// ID of the process owning a scrollback file.
+fn owner(path: &Path) -> Option<u32> {
+    if path.extension()? != EXTENSION {
+        return None;
+    }
+
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+/// Check if the process with the ID `pid` is still running.
+///
+/// Since process IDs are reused, this might also find an unrelated process. Its scrollback file is
+/// then kept until that process has exited too.
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let pid = match pid as libc::pid_t {
+        pid if pid > 0 => pid,
+        _ => return false,
+    };
+
+    // Without permission to signal the process, it still exists.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Check if the process with the ID `pid` is still running.
+///
+/// Since process IDs are reused, this might also find an unrelated process. Its scrollback file is
+/// then kept until that process has exited too.
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    use winapi::shared::minwindef::{DWORD, FALSE};
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::minwinbase::STILL_ACTIVE;
+    use winapi::um::processthreadsapi::{GetExitCodeProcess, OpenProcess};
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+        if handle.is_null() {
+            return false;
+        }
+
+        let mut exit_code: DWORD = 0;
+        let running = GetExitCodeProcess(handle, &mut exit_code) != 0 && exit_code == STILL_ACTIVE;
+        CloseHandle(handle);
+        running
+    }
+}
+
+/// Create the file header for a payload.
+fn header(cols: Column, payload_len: usize) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&(cols.0 as u32).to_le_bytes());
+    header[16..].copy_from_slice(&(payload_len as u64).to_le_bytes());
+    header
+}
+
+/// Validate the file header, returning the saved width and the payload.
+fn parse_header(bytes: &[u8]) -> io::Result<(Column, &[u8])> {
+    if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+        return Err(invalid_data("missing scrollback file header"));
+    }
+
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != VERSION {
+        return Err(invalid_data(format!("unsupported scrollback file version {}", version)));
+    }
+
+    let cols = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+    let payload_len = u64::from_le_bytes(bytes[16..HEADER_LEN].try_into().unwrap()) as usize;
+    if cols == 0 || bytes.len() - HEADER_LEN != payload_len {
+        return Err(invalid_data("corrupted scrollback file header"));
+    }
+
+    Ok((Column(cols), &bytes[HEADER_LEN..]))
+}
+
+/// Change the width of a persisted row.
+fn resize_row(row: &mut Row<Cell>, cols: Column) {
+    let len = row.len();
+    if len < cols.0 {
+        // The old last column no longer is at the end of the line, so it can't wrap.
+        if len > 0 {
+            let last = &mut row[Column(len - 1)];
+            last.flags.remove(Flags::WRAPLINE);
+            if last.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER) {
+                *last = Cell::default();
+            }
+        }
+
+        row.grow(cols);
+    } else {
+        row.shrink(cols);
+
+        // Remove wide chars which have lost their spacer.
+        let last = &mut row[cols - 1];
+        if last.flags.contains(Flags::WIDE_CHAR) {
+            *last = Cell::default();
+        }
+    }
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use crate::index::Line;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("alacritty-persist-{}-{}", process::id(), name))
+    }
+
+    fn grid(lines: &[&str], cols: usize) -> Grid<Cell> {
+        let mut grid: Grid<Cell> = Grid::new(Line(lines.len()), Column(cols), 0);
+        for (line, text) in lines.iter().enumerate() {
+            for (col, c) in text.chars().enumerate() {
+                grid[Line(line)][Column(col)].c = c;
+            }
+        }
+        grid
+    }
+
+    fn text(row: &Row<Cell>) -> String {
+        row[..].iter().map(|cell| cell.c).collect::<String>().trim_end().into()
+    }
+
+    #[test]
+    fn save_and_restore() {
+        let path = temp_path("roundtrip");
+        save(&path, &Snapshot::new(&grid(&["one", "two", "", ""], 5))).unwrap();
+
+        let rows = restore(&path, Column(5)).unwrap();
+        assert_eq!(rows.iter().map(text).collect::<Vec<_>>(), vec!["one", "two"]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn migrate_width() {
+        let path = temp_path("migrate");
+        let mut grid = grid(&["abcde", "字"], 5);
+        grid[Line(0)][Column(4)].flags.insert(Flags::WRAPLINE);
+        grid[Line(1)][Column(0)].flags.insert(Flags::WIDE_CHAR);
+        grid[Line(1)][Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        save(&path, &Snapshot::new(&grid)).unwrap();
+
+        let rows = restore(&path, Column(8)).unwrap();
+        assert_eq!(rows[0].len(), 8);
+        assert!(!rows[0][Column(4)].flags.contains(Flags::WRAPLINE));
+
+        let rows = restore(&path, Column(1)).unwrap();
+        assert_eq!(text(&rows[0]), "a");
+        assert_eq!(rows[1][Column(0)], Cell::default());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn clear_and_invalid_files() {
+        let path = temp_path("clear");
+        assert!(restore(&path, Column(5)).unwrap().is_empty());
+
+        save(&path, &Snapshot::new(&grid(&["one"], 5))).unwrap();
+        clear(&path).unwrap();
+        assert!(restore(&path, Column(5)).unwrap().is_empty());
+
+        fs::write(&path, b"not a scrollback file at all").unwrap();
+        assert!(restore(&path, Column(5)).is_err());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn claim_orphaned_file() {
+        let dir = temp_path("claim");
+        fs::create_dir_all(&dir).unwrap();
+
+        // No process can have an ID this large.
+        let orphan = dir.join(format!("{}.{}", 0x7fff_fff0, EXTENSION));
+        save(&orphan, &Snapshot::new(&grid(&["one"], 5))).unwrap();
+        fs::write(dir.join("unrelated"), b"").unwrap();
+
+        assert!(claim_orphan(&dir).unwrap());
+        assert!(!orphan.exists());
+        assert_eq!(restore(&file(&dir), Column(5)).unwrap().len(), 1);
+
+        assert!(!claim_orphan(&dir).unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -9,9 +9,13 @@ use crate::index::{Boundary, Column, Direction, Point, Side};
 use crate::term::cell::{Cell, Flags};
 use crate::term::Term;
 
+/// Maximum number of wrapped rows followed when searching for the start or end of a line.
+const MAX_WRAPPED_LINES: usize = 10_000;
+
 /// Used to match equal brackets, when performing a bracket-pair selection.
 const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
 
+/// Start and end point of a regex match.
 pub type Match = RangeInclusive<Point<usize>>;
 
 /// Terminal regex search state.
@@ -62,6 +66,32 @@ impl<T> Term<T> {
         self.dirty = true;
     }
 
+    /// Iterate over all matches of a regex to the right of the start point.
+    ///
+    /// The entire buffer is searched once, wrapping around from the bottom of the screen to the
+    /// top of the scrollback history.
+    pub fn search_forward<'a>(
+        &'a self,
+        regex: &'a RegexSearch,
+        start: Point<usize>,
+    ) -> impl Iterator<Item = Match> + 'a {
+        let end = start.sub_absolute(self, Boundary::Wrap, 1);
+        RegexIter::new(start, end, Direction::Right, self, regex)
+    }
+
+    /// Iterate over all matches of a regex to the left of the start point.
+    ///
+    /// The entire buffer is searched once, wrapping around from the top of the scrollback
+    /// history to the bottom of the screen.
+    pub fn search_backward<'a>(
+        &'a self,
+        regex: &'a RegexSearch,
+        start: Point<usize>,
+    ) -> impl Iterator<Item = Match> + 'a {
+        let end = start.add_absolute(self, Boundary::Wrap, 1);
+        RegexIter::new(start, end, Direction::Left, self, regex)
+    }
+
     /// Get next search match in the specified direction.
     pub fn search_next(
         &self,
@@ -87,6 +117,8 @@ impl<T> Term<T> {
         side: Side,
         max_lines: Option<usize>,
     ) -> Option<Match> {
+        let regex = self.regex_search.as_ref()?;
+
         // Skip origin itself to exclude it from the search results.
         let origin = origin.add_absolute(self, Boundary::Wrap, 1);
         let start = self.line_search_left(origin);
@@ -102,7 +134,7 @@ impl<T> Term<T> {
             _ => end.sub_absolute(self, Boundary::Wrap, 1),
         };
 
-        let mut regex_iter = RegexIter::new(start, end, Direction::Right, &self).peekable();
+        let mut regex_iter = RegexIter::new(start, end, Direction::Right, self, regex).peekable();
 
         // Check if there's any match at all.
         let first_match = regex_iter.peek()?.clone();
@@ -128,6 +160,8 @@ impl<T> Term<T> {
         side: Side,
         max_lines: Option<usize>,
     ) -> Option<Match> {
+        let regex = self.regex_search.as_ref()?;
+
         // Skip origin itself to exclude it from the search results.
         let origin = origin.sub_absolute(self, Boundary::Wrap, 1);
         let start = self.line_search_right(origin);
@@ -139,7 +173,7 @@ impl<T> Term<T> {
             _ => end.add_absolute(self, Boundary::Wrap, 1),
         };
 
-        let mut regex_iter = RegexIter::new(start, end, Direction::Left, &self).peekable();
+        let mut regex_iter = RegexIter::new(start, end, Direction::Left, self, regex).peekable();
 
         // Check if there's any match at all.
         let first_match = regex_iter.peek()?.clone();
@@ -170,7 +204,19 @@ impl<T> Term<T> {
     ///
     /// The origin is always included in the regex.
     pub fn regex_search_left(&self, start: Point<usize>, end: Point<usize>) -> Option<Match> {
-        let RegexSearch { left_fdfa: fdfa, left_rdfa: rdfa, .. } = self.regex_search.as_ref()?;
+        self.regex_match_left(self.regex_search.as_ref()?, start, end)
+    }
+
+    /// Find the next match of a regex to the left of the origin point.
+    ///
+    /// The origin is always included in the regex.
+    fn regex_match_left(
+        &self,
+        regex: &RegexSearch,
+        start: Point<usize>,
+        end: Point<usize>,
+    ) -> Option<Match> {
+        let RegexSearch { left_fdfa: fdfa, left_rdfa: rdfa, .. } = regex;
 
         // Find start and end of match.
         let match_start = self.regex_search(start, end, Direction::Left, &fdfa)?;
@@ -183,7 +229,19 @@ impl<T> Term<T> {
     ///
     /// The origin is always included in the regex.
     pub fn regex_search_right(&self, start: Point<usize>, end: Point<usize>) -> Option<Match> {
-        let RegexSearch { right_fdfa: fdfa, right_rdfa: rdfa, .. } = self.regex_search.as_ref()?;
+        self.regex_match_right(self.regex_search.as_ref()?, start, end)
+    }
+
+    /// Find the next match of a regex to the right of the origin point.
+    ///
+    /// The origin is always included in the regex.
+    fn regex_match_right(
+        &self,
+        regex: &RegexSearch,
+        start: Point<usize>,
+        end: Point<usize>,
+    ) -> Option<Match> {
+        let RegexSearch { right_fdfa: fdfa, right_rdfa: rdfa, .. } = regex;
 
         // Find start and end of match.
         let match_end = self.regex_search(start, end, Direction::Right, &fdfa)?;
@@ -409,8 +467,11 @@ impl<T> Term<T> {
     }
 
     /// Find the beginning of the current line across linewraps.
+    ///
+    /// At most [`MAX_WRAPPED_LINES`] rows are followed in each direction.
     pub fn line_search_left(&self, mut point: Point<usize>) -> Point<usize> {
-        while point.line + 1 < self.total_lines()
+        let max_line = min(point.line + MAX_WRAPPED_LINES, self.total_lines() - 1);
+        while point.line < max_line
             && self.grid[point.line + 1][self.cols() - 1].flags.contains(Flags::WRAPLINE)
         {
             point.line += 1;
@@ -422,8 +483,11 @@ impl<T> Term<T> {
     }
 
     /// Find the end of the current line across linewraps.
+    ///
+    /// At most [`MAX_WRAPPED_LINES`] rows are followed in each direction.
     pub fn line_search_right(&self, mut point: Point<usize>) -> Point<usize> {
-        while point.line > 0
+        let min_line = point.line.saturating_sub(MAX_WRAPPED_LINES);
+        while point.line > min_line
             && self.grid[point.line][self.cols() - 1].flags.contains(Flags::WRAPLINE)
         {
             point.line -= 1;
@@ -441,6 +505,7 @@ pub struct RegexIter<'a, T> {
     end: Point<usize>,
     direction: Direction,
     term: &'a Term<T>,
+    regex: &'a RegexSearch,
     done: bool,
 }
 
@@ -450,8 +515,9 @@ impl<'a, T> RegexIter<'a, T> {
         end: Point<usize>,
         direction: Direction,
         term: &'a Term<T>,
+        regex: &'a RegexSearch,
     ) -> Self {
-        Self { point: start, done: false, end, direction, term }
+        Self { point: start, done: false, end, direction, term, regex }
     }
 
     /// Skip one cell, advancing the origin point to the next one.
@@ -467,8 +533,8 @@ impl<'a, T> RegexIter<'a, T> {
     /// Get the next match in the specified direction.
     fn next_match(&self) -> Option<Match> {
         match self.direction {
-            Direction::Right => self.term.regex_search_right(self.point, self.end),
-            Direction::Left => self.term.regex_search_left(self.point, self.end),
+            Direction::Right => self.term.regex_match_right(self.regex, self.point, self.end),
+            Direction::Left => self.term.regex_match_left(self.regex, self.point, self.end),
         }
     }
 }
@@ -485,6 +551,11 @@ impl<'a, T> Iterator for RegexIter<'a, T> {
 
         let regex_match = self.next_match()?;
 
+        // Stop once a match reaches the end, skipping past it would restart the search.
+        if *regex_match.end() == self.end {
+            self.done = true;
+        }
+
         self.point = *regex_match.end();
         self.skip();
 
@@ -539,6 +610,36 @@ mod tests {
         assert_eq!(term.regex_search_left(start, end), Some(match_start..=match_end));
     }
 
+    #[test]
+    fn search_forward_backward() {
+        #[rustfmt::skip]
+        let term = mock_term("\
+            Ala 1\r\n\
+            xx\r\n\
+            Ala 2\r\n\
+            Ala 3\
+        ");
+
+        let regex = RegexSearch::new("Ala [0-9]").unwrap();
+        let first = Point::new(3, Column(0))..=Point::new(3, Column(4));
+        let second = Point::new(1, Column(0))..=Point::new(1, Column(4));
+        let third = Point::new(0, Column(0))..=Point::new(0, Column(4));
+
+        let matches: Vec<_> = term.search_forward(&regex, Point::new(3, Column(0))).collect();
+        assert_eq!(matches, [first.clone(), second.clone(), third.clone()]);
+
+        // Search wraps around to the top of the buffer.
+        let matches: Vec<_> = term.search_forward(&regex, Point::new(2, Column(0))).collect();
+        assert_eq!(matches, [second.clone(), third.clone(), first.clone()]);
+
+        let matches: Vec<_> = term.search_backward(&regex, Point::new(0, Column(4))).collect();
+        assert_eq!(matches, [third.clone(), second.clone(), first.clone()]);
+
+        // Search wraps around to the bottom of the buffer.
+        let matches: Vec<_> = term.search_backward(&regex, Point::new(2, Column(0))).collect();
+        assert_eq!(matches, [first, third, second]);
+    }
+
     #[test]
     fn nested_regex() {
         #[rustfmt::skip]
@@ -796,4 +897,17 @@ mod benches {
             test::black_box(term.regex_search_left(end, start));
         });
     }
+
+    #[bench]
+    fn search_iter(b: &mut test::Bencher) {
+        let input = format!("{:<79}\r\n", "Alacritty").repeat(1000);
+        let term = mock_term(&input);
+        let regex = RegexSearch::new("Alacritty").unwrap();
+        let start = Point::new(term.total_lines() - 1, Column(0));
+
+        b.iter(|| {
+            test::black_box(term.search_forward(&regex, start).count());
+            test::black_box(term.search_backward(&regex, start).count());
+        });
+    }
 }
@@ -0,0 +1,251 @@
+//! Semantic zones written by shells with prompt integration.
+//!
+//! Shells mark their prompt, the command typed after it and the output of the command using
+//! `OSC 133`. Every cell records the zone it was written in, so the marks move with the text
+//! through scrollback, resizes and reflow, and are discarded with the lines they are part of.
+
+use crate::grid::{Dimensions, Row};
+use crate::index::{Column, Direction, Point};
+use crate::term::cell::{row_flag_union, Cell, Flags};
+use crate::term::Term;
+
+/// Semantic zone of a cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SemanticZone {
+    /// Prompt printed by the shell.
+    Prompt,
+
+    /// Command typed by the user.
+    Input,
+
+    /// Command output and any text written without shell integration.
+    Output,
+}
+
+impl SemanticZone {
+    /// Cell flags marking this zone.
+    #[inline]
+    pub fn flags(self) -> Flags {
+        match self {
+            SemanticZone::Prompt => Flags::PROMPT,
+            SemanticZone::Input => Flags::COMMAND_INPUT,
+            SemanticZone::Output => Flags::COMMAND_OUTPUT,
+        }
+    }
+}
+
+/// Extract the command typed by the user from a region of rows, ordered from top to bottom.
+///
+/// Only cells of the [`SemanticZone::Input`] zone are included, lines are joined unless they
+/// were soft wrapped. Returns `None` if the region does not contain any command.
+pub fn extract_command(rows: &[Row<Cell>]) -> Option<String> {
+    let mut command = String::new();
+    let mut found = false;
+
+    for row in rows {
+        let mut line = String::new();
+        let mut has_input = false;
+        for cell in row[..].iter().filter(|cell| cell.semantic_zone() == SemanticZone::Input) {
+            has_input = true;
+            if cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            line.push(cell.c);
+            line.extend(cell.zerowidth().into_iter().flatten());
+        }
+
+        if !has_input {
+            continue;
+        }
+
+        found = true;
+
+        // Lines of multi-line commands are separated by newlines, unless they were soft wrapped.
+        if matches!(row[..].last(), Some(cell) if cell.flags.contains(Flags::WRAPLINE)) {
+            command.push_str(&line);
+        } else {
+            command.push_str(line.trim_end());
+            command.push('\n');
+        }
+    }
+
+    if !found {
+        return None;
+    }
+
+    command.truncate(command.trim_end().len());
+    Some(command)
+}
+
+impl<T> Term<T> {
+    /// Find the closest prompt above or below the top of the viewport.
+    ///
+    /// Prompts start on the first of consecutive rows containing prompt cells. The `direction`
+    /// is [`Direction::Left`] to search upwards into the history. Returns the buffer line of the
+    /// prompt, with line 0 at the bottom of the terminal.
+    pub fn prompt_line(&self, direction: Direction) -> Option<usize> {
+        let total_lines = self.total_lines();
+        let top = self.grid.display_offset() + self.screen_lines().0 - 1;
+
+        let has_prompt = |line: usize| row_flag_union(&self.grid[line]).contains(Flags::PROMPT);
+        let is_prompt_start =
+            |line: usize| has_prompt(line) && (line + 1 == total_lines || !has_prompt(line + 1));
+
+        match direction {
+            Direction::Left => (top + 1..total_lines).find(|&line| is_prompt_start(line)),
+            Direction::Right => (0..top).rev().find(|&line| is_prompt_start(line)),
+        }
+    }
+
+    /// Text of the most recent command output.
+    ///
+    /// The output starts after the last prompt or command input above the last output cell.
+    /// Lines are extracted like a selection, so soft wrapped lines are joined. Returns `None` if
+    /// the shell did not mark any command output.
+    pub fn last_command_output(&self) -> Option<String> {
+        let cols = self.cols().0;
+        let zone_flags =
+            |point: &Point<usize>| self.grid[point.line][point.col].flags & Flags::SEMANTIC_ZONE;
+
+        // Iterate over all cells from the bottom right to the top left of the buffer.
+        let mut points = (0..self.total_lines())
+            .flat_map(|line| (0..cols).rev().map(move |col| Point::new(line, Column(col))));
+
+        let end = points.find(|point| zone_flags(point).contains(Flags::COMMAND_OUTPUT))?;
+
+        let mut start = end;
+        for point in points {
+            let flags = zone_flags(&point);
+            if flags.intersects(Flags::PROMPT | Flags::COMMAND_INPUT) {
+                break;
+            } else if flags.contains(Flags::COMMAND_OUTPUT) {
+                start = point;
+            }
+        }
+
+        Some(self.bounds_to_string(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use crate::ansi::Processor;
+    use crate::config::MockConfig;
+    use crate::event::VoidListener;
+    use crate::grid::Scroll;
+    use crate::index::Line;
+    use crate::term::SizeInfo;
+
+    fn term_with_output(lines: f32, output: &str) -> Term<VoidListener> {
+        let size = SizeInfo::new(21.0, lines * 3., 3.0, 3.0, 0.0, 0.0, false);
+        let mut term = Term::new(&MockConfig::default(), size, VoidListener);
+        let mut parser = Processor::new();
+        for byte in output.bytes() {
+            parser.advance(&mut term, byte, &mut io::sink());
+        }
+        term
+    }
+
+    fn row(zones: &[(SemanticZone, &str)], columns: usize) -> Row<Cell> {
+        let mut row = Row::<Cell>::new(Column(columns));
+        let cells = zones.iter().flat_map(|(zone, text)| text.chars().map(move |c| (*zone, c)));
+        for (col, (zone, c)) in cells.enumerate() {
+            row[Column(col)].c = c;
+            row[Column(col)].flags.insert(zone.flags());
+        }
+        row
+    }
+
+    #[test]
+    fn extract_command_between_prompt_and_output() {
+        let rows = vec![
+            row(&[(SemanticZone::Output, "earlier output")], 20),
+            row(&[(SemanticZone::Prompt, "~ $ "), (SemanticZone::Input, "ls -la  ")], 20),
+            row(&[(SemanticZone::Output, "total 0")], 20),
+        ];
+
+        assert_eq!(extract_command(&rows).as_deref(), Some("ls -la"));
+        assert_eq!(extract_command(&rows[2..]), None);
+    }
+
+    #[test]
+    fn extract_wrapped_and_multiline_command() {
+        let mut wrapped = row(&[(SemanticZone::Prompt, "$ "), (SemanticZone::Input, "echo a")], 8);
+        wrapped[Column(7)].flags.insert(Flags::WRAPLINE);
+        let rows = vec![
+            wrapped,
+            row(&[(SemanticZone::Input, "bc \\")], 8),
+            row(&[(SemanticZone::Prompt, "> "), (SemanticZone::Input, "d")], 8),
+            row(&[(SemanticZone::Output, "abc d")], 8),
+        ];
+
+        assert_eq!(extract_command(&rows).as_deref(), Some("echo abc \\\nd"));
+    }
+
+    #[test]
+    fn zones_from_marks() {
+        let output = concat!(
+            "\x1b]133;A\x07$ \x1b[1m\x1b]133;B\x07ls\x1b[0m\r\n",
+            "\x1b]133;C\x07out\r\n\x1b]133;D\x07x",
+        );
+        let term = term_with_output(5., output);
+
+        let zones = |line, cols: usize| {
+            (0..cols)
+                .map(|col| term.grid[Line(line)][Column(col)].semantic_zone())
+                .collect::<Vec<_>>()
+        };
+        let (prompt, input, output) =
+            (SemanticZone::Prompt, SemanticZone::Input, SemanticZone::Output);
+        assert_eq!(zones(0, 4), vec![prompt, prompt, input, input]);
+        assert_eq!(zones(1, 3), vec![output; 3]);
+
+        // Attributes are reset without leaving the zone.
+        assert!(term.grid[Line(0)][Column(3)].flags.contains(Flags::BOLD | Flags::COMMAND_INPUT));
+        assert!(term.grid[Line(1)][Column(0)].flags.contains(Flags::COMMAND_OUTPUT));
+        assert_eq!(term.grid[Line(2)][Column(0)].flags, Flags::empty());
+    }
+
+    #[test]
+    fn last_command_output() {
+        let term = term_with_output(5., "no marks\r\n");
+        assert_eq!(term.last_command_output(), None);
+
+        // Output with a soft wrapped line, an empty line and no trailing newline.
+        let output = concat!(
+            "\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07first\r\n",
+            "\x1b]133;D\x07\x1b]133;A\x07$ \x1b]133;B\x07cat\r\n\x1b]133;C\x07",
+            "wrapped line of output\r\n\r\nlast",
+            "\x1b]133;D\x07\x1b]133;A\x07$ ",
+        );
+        let term = term_with_output(5., output);
+        let expected = "wrapped line of output\n\nlast";
+        assert_eq!(term.last_command_output().as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn find_prompts() {
+        // Three two-line prompts, each followed by three lines of output.
+        let block =
+            "\x1b]133;A\x07~\r\n$\x1b]133;B\x07c\r\n\x1b]133;C\x07o\r\no\r\no\r\n\x1b]133;D\x07";
+        let mut term = term_with_output(5., &block.repeat(3));
+        assert_eq!(term.total_lines(), 16);
+
+        // Prompts start on buffer lines 5, 10 and 15.
+        assert_eq!(term.prompt_line(Direction::Left), Some(5));
+        assert_eq!(term.prompt_line(Direction::Right), None);
+
+        term.scroll_display(Scroll::Delta(1));
+        assert_eq!(term.prompt_line(Direction::Left), Some(10));
+        assert_eq!(term.prompt_line(Direction::Right), None);
+
+        term.scroll_display(Scroll::Top);
+        assert_eq!(term.prompt_line(Direction::Left), None);
+        assert_eq!(term.prompt_line(Direction::Right), Some(10));
+    }
+}
@@ -0,0 +1,409 @@
+//! Decoder for sixel graphics.
+//!
+//! Sixel images are sent as `ESC P P1 ; P2 ; P3 q <data> ESC \`. The data consists of sixel
+//! characters, each painting a column of six vertical pixels in the current color, and commands
+//! to select or define colors (`#`), repeat a sixel (`!`), set the raster attributes (`"`) and
+//! move to the start of the current (`$`) or next (`-`) band of six pixel rows.
+//!
+//! Pixels which are never painted are transparent, the background selection `P2` is ignored.
+
+use std::mem;
+
+use crate::term::color::Rgb;
+use crate::term::image::{SixelImage, MAX_IMAGE_SIZE};
+
+/// Number of color registers.
+const MAX_COLORS: usize = 256;
+
+/// Maximum number of parameters used by any sixel command.
+const MAX_PARAMS: usize = 5;
+
+/// Default color registers of the VT340, in RGB percentages.
+const DEFAULT_COLORS: [(u32, u32, u32); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Command whose parameters are being received.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    /// Sixel data, without a pending command.
+    Ground,
+
+    /// Graphics repeat introducer (`!`).
+    Repeat,
+
+    /// Color introducer (`#`).
+    Color,
+
+    /// Raster attributes (`"`).
+    Raster,
+}
+
+/// Incremental decoder for the data of a sixel DCS sequence.
+#[derive(Debug)]
+pub struct SixelDecoder {
+    command: Command,
+    params: [u32; MAX_PARAMS],
+    param_count: usize,
+
+    palette: [Rgb; MAX_COLORS],
+    color: usize,
+
+    /// Position of the next sixel, the line is the top pixel row of the current band.
+    col: usize,
+    line: usize,
+
+    /// Size of the image painted so far.
+    width: usize,
+    height: usize,
+
+    /// RGBA pixels, with `stride` pixels per row and `rows` rows allocated.
+    data: Vec<u8>,
+    stride: usize,
+    rows: usize,
+
+    /// Aspect ratio of a single sixel pixel.
+    pixel_width: u32,
+    pixel_height: u32,
+}
+
+impl SixelDecoder {
+    /// Create a decoder using the parameters of the DCS sequence.
+    ///
+    /// The first parameter selects the aspect ratio of the pixels, the other parameters are
+    /// ignored.
+    pub fn new(params: &[u16]) -> Self {
+        let pixel_height = match params.first().copied().unwrap_or(0) {
+            2 => 5,
+            3 | 4 => 3,
+            7..=9 => 1,
+            _ => 2,
+        };
+
+        let mut palette = [Rgb::default(); MAX_COLORS];
+        for (color, &(r, g, b)) in palette.iter_mut().zip(DEFAULT_COLORS.iter()) {
+            *color = Rgb { r: percent(r), g: percent(g), b: percent(b) };
+        }
+
+        Self {
+            command: Command::Ground,
+            params: [0; MAX_PARAMS],
+            param_count: 0,
+            palette,
+            color: 0,
+            col: 0,
+            line: 0,
+            width: 0,
+            height: 0,
+            data: Vec::new(),
+            stride: 0,
+            rows: 0,
+            pixel_width: 1,
+            pixel_height,
+        }
+    }
+
+    /// Process a byte of the sixel data.
+    pub fn put(&mut self, byte: u8) {
+        match byte {
+            b'0'..=b'9' if self.command != Command::Ground => {
+                if let Some(param) = self.params.get_mut(self.param_count) {
+                    *param = param.saturating_mul(10).saturating_add(u32::from(byte - b'0'));
+                }
+                return;
+            },
+            b';' if self.command != Command::Ground => {
+                self.param_count = (self.param_count + 1).min(MAX_PARAMS);
+                return;
+            },
+            _ => (),
+        }
+
+        let repeat = match self.finish_command() {
+            Command::Repeat => self.params[0].max(1) as usize,
+            _ => 1,
+        };
+        self.params = [0; MAX_PARAMS];
+        self.param_count = 0;
+
+        match byte {
+            b'?'..=b'~' => self.paint(byte - b'?', repeat),
+            b'!' => self.command = Command::Repeat,
+            b'#' => self.command = Command::Color,
+            b'"' => self.command = Command::Raster,
+            b'$' => self.col = 0,
+            b'-' => {
+                self.col = 0;
+                self.line += 6;
+            },
+            _ => (),
+        }
+    }
+
+    /// Complete decoding, returning the painted image.
+    ///
+    /// Returns `None` if no pixels were painted.
+    pub fn finish(mut self) -> Option<SixelImage> {
+        self.finish_command();
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let mut data = mem::take(&mut self.data);
+        if self.stride != self.width {
+            let row_len = self.width * 4;
+            for row in 1..self.height {
+                let start = row * self.stride * 4;
+                data.copy_within(start..start + row_len, row * row_len);
+            }
+        }
+        data.truncate(self.width * self.height * 4);
+
+        Some(SixelImage {
+            data,
+            width: self.width as u32,
+            height: self.height as u32,
+            pixel_width: self.pixel_width,
+            pixel_height: self.pixel_height,
+        })
+    }
+
+    /// Execute the pending command, returning it.
+    fn finish_command(&mut self) -> Command {
+        let command = mem::replace(&mut self.command, Command::Ground);
+
+        match command {
+            Command::Color => self.color_command(),
+            Command::Raster => self.raster_command(),
+            Command::Ground | Command::Repeat => (),
+        }
+
+        command
+    }
+
+    /// Select a color register, defining its color if all parameters are present.
+    ///
+    /// Registers beyond the last one wrap around.
+    fn color_command(&mut self) {
+        let [register, space, x, y, z] = self.params;
+        self.color = register as usize % MAX_COLORS;
+
+        if self.param_count < MAX_PARAMS - 1 {
+            return;
+        }
+
+        let color = match space {
+            1 => hls_to_rgb(x, y, z),
+            2 => Rgb { r: percent(x), g: percent(y), b: percent(z) },
+            _ => return,
+        };
+
+        self.palette[self.color] = color;
+    }
+
+    /// Apply the pixel aspect ratio and image size of the raster attributes.
+    fn raster_command(&mut self) {
+        let [numerator, denominator, width, height, _] = self.params;
+
+        if numerator > 0 && denominator > 0 {
+            let divisor = gcd(numerator, denominator);
+            self.pixel_height = numerator / divisor;
+            self.pixel_width = denominator / divisor;
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        if self.reserve(width, height) {
+            self.width = self.width.max(width);
+            self.height = self.height.max(height);
+        }
+    }
+
+    /// Paint a sixel `repeat` times in the current color.
+    fn paint(&mut self, sixel: u8, repeat: usize) {
+        let start = self.col;
+        self.col = self.col.saturating_add(repeat);
+
+        if !self.reserve(self.col, self.line + 6) {
+            return;
+        }
+
+        let color = self.palette[self.color];
+        for bit in (0..6).filter(|bit| sixel & (1 << bit) != 0) {
+            let row = self.line + bit;
+            for col in start..self.col {
+                let index = (row * self.stride + col) * 4;
+                self.data[index..index + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+            self.height = self.height.max(row + 1);
+        }
+
+        self.width = self.width.max(self.col);
+    }
+
+    /// Make sure the pixel buffer can hold an image of the specified size.
+    ///
+    /// Returns `false` if the image would exceed the maximum image size.
+    fn reserve(&mut self, width: usize, height: usize) -> bool {
+        if width <= self.stride && height <= self.rows {
+            return true;
+        }
+
+        match width.checked_mul(height).and_then(|pixels| pixels.checked_mul(4)) {
+            Some(size) if size <= MAX_IMAGE_SIZE => (),
+            _ => return false,
+        }
+
+        // Grow exponentially to avoid copying the image for every sixel.
+        let mut stride = width.max(self.stride);
+        let mut rows = height.max(self.rows);
+        if stride > self.stride && stride * 2 * rows * 4 <= MAX_IMAGE_SIZE {
+            stride = stride.max(self.stride * 2);
+        }
+        if rows > self.rows && stride * rows * 2 * 4 <= MAX_IMAGE_SIZE {
+            rows = rows.max(self.rows * 2);
+        }
+
+        let mut data = vec![0; stride * rows * 4];
+        for row in 0..self.rows {
+            let old = row * self.stride * 4;
+            let new = row * stride * 4;
+            data[new..new + self.stride * 4]
+                .copy_from_slice(&self.data[old..old + self.stride * 4]);
+        }
+
+        self.data = data;
+        self.stride = stride;
+        self.rows = rows;
+
+        true
+    }
+}
+
+/// Convert a percentage to an 8-bit color component.
+fn percent(value: u32) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+/// Convert a color in DEC's HLS color space to RGB.
+///
+/// The hue is in degrees, with blue at 0°, lightness and saturation are percentages.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> Rgb {
+    // DEC's hue is rotated by 120° compared to the usual HSL color space.
+    let hue = ((hue + 240) % 360) as f32 / 60.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let x = chroma * (1. - (hue % 2. - 1.).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    let m = lightness - chroma / 2.;
+    let component = |value: f32| ((value + m) * 255.).round() as u8;
+
+    Rgb { r: component(r), g: component(g), b: component(b) }
+}
+
+/// Greatest common divisor of two numbers.
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(params: &[u16], data: &[u8]) -> Option<SixelImage> {
+        let mut decoder = SixelDecoder::new(params);
+        for &byte in data {
+            decoder.put(byte);
+        }
+        decoder.finish()
+    }
+
+    /// Color of a single pixel.
+    fn pixel(image: &SixelImage, col: usize, row: usize) -> [u8; 4] {
+        let index = (row * image.width as usize + col) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&image.data[index..index + 4]);
+        pixel
+    }
+
+    #[test]
+    fn decode_bands() {
+        // Red top row and a green column, then a blue band using the repeat introducer.
+        let image = decode(&[9], b"#1;2;100;0;0#2;2;0;100;0#1@@@$#2~-#3;2;0;0;100!3~").unwrap();
+
+        assert_eq!((image.width, image.height), (3, 12));
+        assert_eq!((image.pixel_width, image.pixel_height), (1, 1));
+        assert_eq!(image.data.len(), 3 * 12 * 4);
+
+        assert_eq!(pixel(&image, 0, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&image, 1, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 2, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 0, 5), [0, 255, 0, 255]);
+        assert_eq!(pixel(&image, 1, 5), [0, 0, 0, 0]);
+        for col in 0..3 {
+            for row in 6..12 {
+                assert_eq!(pixel(&image, col, row), [0, 0, 255, 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn default_palette_and_hls() {
+        // Default color register 2 and a pure red defined as HLS, with blue at 0°.
+        let image = decode(&[], b"#2@#3;1;120;50;100@").unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!((image.pixel_width, image.pixel_height), (1, 2));
+        assert_eq!(pixel(&image, 0, 0), [204, 33, 33, 255]);
+        assert_eq!(pixel(&image, 1, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn raster_attributes() {
+        let image = decode(&[0], b"\"2;4;5;3#1;2;0;0;100@").unwrap();
+
+        assert_eq!((image.width, image.height), (5, 3));
+        assert_eq!((image.pixel_width, image.pixel_height), (2, 1));
+        assert_eq!(pixel(&image, 0, 0), [0, 0, 255, 255]);
+        assert_eq!(pixel(&image, 4, 2), [0, 0, 0, 0]);
+
+        assert!(decode(&[], b"#1;2;0;0;100$-").is_none());
+    }
+
+    #[test]
+    fn oversized_image() {
+        let image = decode(&[], b"!99999999~-@").unwrap();
+
+        assert_eq!((image.width, image.height), (1, 7));
+    }
+}
@@ -0,0 +1,55 @@
+//! Terminfo capabilities reported through XTGETTCAP.
+
+/// Name reported for the `TN` capability.
+const TERMINAL_NAME: &str = "alacritty";
+
+/// Capabilities of the `alacritty` terminfo entry.
+///
+/// Both the terminfo and termcap names are listed. Boolean capabilities have no value. Key
+/// capabilities describe the sequences sent in keypad transmit mode, just like the terminfo entry.
+const CAPABILITIES: &[(&[&str], Option<&str>)] = &[
+    (&["TN", "name"], Some(TERMINAL_NAME)),
+    (&["Co", "colors"], Some("256")),
+    (&["RGB"], None),
+    (&["Tc"], None),
+    (&["kbs", "kb"], Some("\x7f")),
+    (&["kcbt", "kB"], Some("\x1b[Z")),
+    (&["kcuu1", "ku"], Some("\x1bOA")),
+    (&["kcud1", "kd"], Some("\x1bOB")),
+    (&["kcuf1", "kr"], Some("\x1bOC")),
+    (&["kcub1", "kl"], Some("\x1bOD")),
+    (&["khome", "kh"], Some("\x1bOH")),
+    (&["kend", "@7"], Some("\x1bOF")),
+    (&["kich1", "kI"], Some("\x1b[2~")),
+    (&["kdch1", "kD"], Some("\x1b[3~")),
+    (&["kpp", "kP"], Some("\x1b[5~")),
+    (&["knp", "kN"], Some("\x1b[6~")),
+    (&["kLFT", "#4"], Some("\x1b[1;2D")),
+    (&["kRIT", "%i"], Some("\x1b[1;2C")),
+    (&["kri", "kR"], Some("\x1b[1;2A")),
+    (&["kind", "kF"], Some("\x1b[1;2B")),
+    (&["kf1", "k1"], Some("\x1bOP")),
+    (&["kf2", "k2"], Some("\x1bOQ")),
+    (&["kf3", "k3"], Some("\x1bOR")),
+    (&["kf4", "k4"], Some("\x1bOS")),
+    (&["kf5", "k5"], Some("\x1b[15~")),
+    (&["kf6", "k6"], Some("\x1b[17~")),
+    (&["kf7", "k7"], Some("\x1b[18~")),
+    (&["kf8", "k8"], Some("\x1b[19~")),
+    (&["kf9", "k9"], Some("\x1b[20~")),
+    (&["kf10", "k;"], Some("\x1b[21~")),
+    (&["kf11", "F1"], Some("\x1b[23~")),
+    (&["kf12", "F2"], Some("\x1b[24~")),
+];
+
+/// Look up a capability by its terminfo or termcap name.
+///
+/// Returns `None` for unknown capabilities and `Some(None)` for boolean capabilities.
+pub fn capability(name: &str) -> Option<Option<&'static str>> {
+    CAPABILITIES.iter().find(|(names, _)| names.contains(&name)).map(|(_, value)| *value)
+}
+
+/// Encode a string as hexadecimal byte pairs.
+pub fn encode_hex(text: &str) -> String {
+    text.bytes().map(|byte| format!("{:02X}", byte)).collect()
+}
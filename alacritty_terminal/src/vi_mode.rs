@@ -87,12 +87,12 @@ impl ViModeCursor {
                 {
                     buffer_point = wrap_point;
                 } else {
-                    buffer_point.col = Column(buffer_point.col.saturating_sub(1));
+                    buffer_point.col = buffer_point.col.saturating_sub(1);
                 }
             },
             ViMotion::Right => {
                 buffer_point = term.expand_wide(buffer_point, Direction::Right);
-                if is_wrap(term, buffer_point) {
+                if buffer_point.line > 0 && is_wrap(term, buffer_point) {
                     buffer_point = Point::new(buffer_point.line - 1, Column(0));
                 } else {
                     buffer_point.col = min(buffer_point.col + 1, cols - 1);
@@ -770,4 +770,54 @@ mod tests {
         cursor = cursor.motion(&mut term, ViMotion::WordLeft);
         assert_eq!(cursor.point, Point::new(Line(0), Column(0)));
     }
+
+    #[test]
+    fn motions_at_grid_corners() {
+        let mut term = term();
+        let last_line = term.screen_lines() - 1;
+        let last_col = term.cols() - 1;
+
+        // Wrap every line, including the bottommost one.
+        for line in 0..=last_line.0 {
+            term.grid_mut()[Line(line)][last_col].flags.insert(Flags::WRAPLINE);
+        }
+        term.grid_mut()[Line(0)][Column(0)].c = '(';
+        term.grid_mut()[last_line][last_col].c = ')';
+
+        let corners = [
+            Point::new(Line(0), Column(0)),
+            Point::new(Line(0), last_col),
+            Point::new(last_line, Column(0)),
+            Point::new(last_line, last_col),
+        ];
+        let motions = [
+            ViMotion::Up,
+            ViMotion::Down,
+            ViMotion::Left,
+            ViMotion::Right,
+            ViMotion::First,
+            ViMotion::Last,
+            ViMotion::FirstOccupied,
+            ViMotion::High,
+            ViMotion::Middle,
+            ViMotion::Low,
+            ViMotion::SemanticLeft,
+            ViMotion::SemanticRight,
+            ViMotion::SemanticLeftEnd,
+            ViMotion::SemanticRightEnd,
+            ViMotion::WordLeft,
+            ViMotion::WordRight,
+            ViMotion::WordLeftEnd,
+            ViMotion::WordRightEnd,
+            ViMotion::Bracket,
+        ];
+
+        for &corner in &corners {
+            for &motion in &motions {
+                let cursor = ViModeCursor::new(corner).motion(&mut term, motion);
+                assert!(cursor.point.line <= last_line, "{:?} from {:?}", motion, corner);
+                assert!(cursor.point.col <= last_col, "{:?} from {:?}", motion, corner);
+            }
+        }
+    }
 }
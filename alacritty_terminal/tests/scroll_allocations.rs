@@ -0,0 +1,64 @@
+//! Scrolling must not touch the allocator once the scrollback history is full.
+//!
+//! This lives in its own test binary since it installs a counting global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use alacritty_terminal::ansi;
+use alacritty_terminal::config::MockConfig;
+use alacritty_terminal::event::VoidListener;
+use alacritty_terminal::term::{SizeInfo, Term};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn scrolling_full_history_reuses_rows() {
+    const HISTORY: u32 = 1_000;
+
+    let mut config = MockConfig::default();
+    config.scrolling.set_history(HISTORY);
+
+    let size = SizeInfo::new(800., 240., 10., 10., 0., 0., false);
+    let mut terminal = Term::new(&config, size, VoidListener);
+    let mut parser = ansi::Processor::new();
+
+    let mut advance = |terminal: &mut Term<VoidListener>, lines: u32| {
+        for _ in 0..lines {
+            for &byte in b"scrolling\r\n" {
+                parser.advance(terminal, byte, &mut io::sink());
+            }
+        }
+    };
+
+    // Fill the entire scrollback, which has to allocate every history row once.
+    advance(&mut terminal, 2 * HISTORY);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    advance(&mut terminal, 10 * HISTORY);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    assert_eq!(allocations, 0);
+}